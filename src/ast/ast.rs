@@ -10,12 +10,51 @@ pub trait Statement: Node {
     fn as_let_statement(&self) -> Option<&LetStatement> {
         None
     }
+    fn as_let_statement_mut(&mut self) -> Option<&mut LetStatement> {
+        None
+    }
     fn as_return_statement(&self) -> Option<&ReturnStatement> {
         None
     }
+    fn as_return_statement_mut(&mut self) -> Option<&mut ReturnStatement> {
+        None
+    }
     fn as_expression_statement(&self) -> Option<&ExpressionStatement> {
         None
     }
+    fn as_expression_statement_mut(&mut self) -> Option<&mut ExpressionStatement> {
+        None
+    }
+    fn as_block_statement(&self) -> Option<&BlockStatement> {
+        None
+    }
+    fn as_block_statement_mut(&mut self) -> Option<&mut BlockStatement> {
+        None
+    }
+    fn as_while_statement(&self) -> Option<&WhileStatement> {
+        None
+    }
+    fn as_while_statement_mut(&mut self) -> Option<&mut WhileStatement> {
+        None
+    }
+    fn as_for_statement(&self) -> Option<&ForStatement> {
+        None
+    }
+    fn as_for_statement_mut(&mut self) -> Option<&mut ForStatement> {
+        None
+    }
+    fn as_break_statement(&self) -> Option<&BreakStatement> {
+        None
+    }
+    fn as_break_statement_mut(&mut self) -> Option<&mut BreakStatement> {
+        None
+    }
+    fn as_continue_statement(&self) -> Option<&ContinueStatement> {
+        None
+    }
+    fn as_continue_statement_mut(&mut self) -> Option<&mut ContinueStatement> {
+        None
+    }
 }
 
 pub trait Expression: Node {
@@ -23,6 +62,69 @@ pub trait Expression: Node {
     fn as_identifier_expression(&self) -> Option<&Identifier> {
         None
     }
+    fn as_identifier_expression_mut(&mut self) -> Option<&mut Identifier> {
+        None
+    }
+    fn as_integer_literal(&self) -> Option<&IntegerLiteral> {
+        None
+    }
+    fn as_integer_literal_mut(&mut self) -> Option<&mut IntegerLiteral> {
+        None
+    }
+    fn as_float_literal(&self) -> Option<&FloatLiteral> {
+        None
+    }
+    fn as_float_literal_mut(&mut self) -> Option<&mut FloatLiteral> {
+        None
+    }
+    fn as_boolean(&self) -> Option<&Boolean> {
+        None
+    }
+    fn as_boolean_mut(&mut self) -> Option<&mut Boolean> {
+        None
+    }
+    fn as_prefix_expression(&self) -> Option<&PrefixExpression> {
+        None
+    }
+    fn as_prefix_expression_mut(&mut self) -> Option<&mut PrefixExpression> {
+        None
+    }
+    fn as_infix_expression(&self) -> Option<&InfixExpression> {
+        None
+    }
+    fn as_infix_expression_mut(&mut self) -> Option<&mut InfixExpression> {
+        None
+    }
+    fn as_if_expression(&self) -> Option<&IfExpression> {
+        None
+    }
+    fn as_if_expression_mut(&mut self) -> Option<&mut IfExpression> {
+        None
+    }
+    fn as_function_literal(&self) -> Option<&FunctionLiteral> {
+        None
+    }
+    fn as_function_literal_mut(&mut self) -> Option<&mut FunctionLiteral> {
+        None
+    }
+    fn as_macro_literal(&self) -> Option<&MacroLiteral> {
+        None
+    }
+    fn as_macro_literal_mut(&mut self) -> Option<&mut MacroLiteral> {
+        None
+    }
+    fn as_assign_expression(&self) -> Option<&AssignExpression> {
+        None
+    }
+    fn as_assign_expression_mut(&mut self) -> Option<&mut AssignExpression> {
+        None
+    }
+    fn as_call_expression(&self) -> Option<&CallExpression> {
+        None
+    }
+    fn as_call_expression_mut(&mut self) -> Option<&mut CallExpression> {
+        None
+    }
 }
 
 pub struct Program {
@@ -49,7 +151,8 @@ impl Node for Program {
 pub struct LetStatement {
     pub token: Token,
     pub name: Box<Identifier>,
-    // pub value: dyn Expression,
+    pub r#type: Option<TypeExpression>,
+    pub value: Box<dyn Expression>,
 }
 
 impl Node for LetStatement {
@@ -60,8 +163,12 @@ impl Node for LetStatement {
         let mut out = String::new();
         out.push_str(format!("{} ", self.token_literal()).as_str());
         out.push_str(&self.name.string());
+        if let Some(r#type) = &self.r#type {
+            out.push_str(": ");
+            out.push_str(&r#type.string());
+        }
         out.push_str(" = ");
-        // add value here
+        out.push_str(&self.value.string());
         out.push_str(";");
         out
     }
@@ -72,6 +179,9 @@ impl Statement for LetStatement {
     fn as_let_statement(&self) -> Option<&LetStatement> {
         Some(&self)
     }
+    fn as_let_statement_mut(&mut self) -> Option<&mut LetStatement> {
+        Some(self)
+    }
 }
 
 pub struct Identifier {
@@ -97,11 +207,209 @@ impl Expression for Identifier {
     fn as_identifier_expression(&self) -> Option<&Identifier> {
         Some(&self)
     }
+    fn as_identifier_expression_mut(&mut self) -> Option<&mut Identifier> {
+        Some(self)
+    }
+}
+
+pub struct IntegerLiteral {
+    pub token: Token,
+    pub value: i64,
+}
+
+impl Node for IntegerLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Expression for IntegerLiteral {
+    fn expression_node(&self) {}
+    fn as_integer_literal(&self) -> Option<&IntegerLiteral> {
+        Some(self)
+    }
+    fn as_integer_literal_mut(&mut self) -> Option<&mut IntegerLiteral> {
+        Some(self)
+    }
+}
+
+pub struct FloatLiteral {
+    pub token: Token,
+    pub value: f64,
+}
+
+impl Node for FloatLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Expression for FloatLiteral {
+    fn expression_node(&self) {}
+    fn as_float_literal(&self) -> Option<&FloatLiteral> {
+        Some(self)
+    }
+    fn as_float_literal_mut(&mut self) -> Option<&mut FloatLiteral> {
+        Some(self)
+    }
+}
+
+pub struct Boolean {
+    pub token: Token,
+    pub value: bool,
+}
+
+impl Node for Boolean {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl Expression for Boolean {
+    fn expression_node(&self) {}
+    fn as_boolean(&self) -> Option<&Boolean> {
+        Some(self)
+    }
+    fn as_boolean_mut(&mut self) -> Option<&mut Boolean> {
+        Some(self)
+    }
+}
+
+pub struct PrefixExpression {
+    pub token: Token,
+    pub operator: String,
+    pub right: Box<dyn Expression>,
+}
+
+impl Node for PrefixExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!("({}{})", self.operator, self.right.string())
+    }
+}
+
+impl Expression for PrefixExpression {
+    fn expression_node(&self) {}
+    fn as_prefix_expression(&self) -> Option<&PrefixExpression> {
+        Some(self)
+    }
+    fn as_prefix_expression_mut(&mut self) -> Option<&mut PrefixExpression> {
+        Some(self)
+    }
+}
+
+pub struct InfixExpression {
+    pub token: Token,
+    pub left: Box<dyn Expression>,
+    pub operator: String,
+    pub right: Box<dyn Expression>,
+}
+
+impl Node for InfixExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!(
+            "({} {} {})",
+            self.left.string(),
+            self.operator,
+            self.right.string()
+        )
+    }
+}
+
+impl Expression for InfixExpression {
+    fn expression_node(&self) {}
+    fn as_infix_expression(&self) -> Option<&InfixExpression> {
+        Some(self)
+    }
+    fn as_infix_expression_mut(&mut self) -> Option<&mut InfixExpression> {
+        Some(self)
+    }
+}
+
+// `name = value`, or a compound assignment such as `name += value`. The
+// token is the operator.
+pub struct AssignExpression {
+    pub token: Token,
+    pub name: Identifier,
+    pub operator: String,
+    pub value: Box<dyn Expression>,
+}
+
+impl Node for AssignExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!(
+            "({} {} {})",
+            self.name.string(),
+            self.operator,
+            self.value.string()
+        )
+    }
+}
+
+impl Expression for AssignExpression {
+    fn expression_node(&self) {}
+    fn as_assign_expression(&self) -> Option<&AssignExpression> {
+        Some(self)
+    }
+    fn as_assign_expression_mut(&mut self) -> Option<&mut AssignExpression> {
+        Some(self)
+    }
+}
+
+pub struct IfExpression {
+    pub token: Token,
+    pub condition: Box<dyn Expression>,
+    pub consequence: BlockStatement,
+    pub alternative: Option<BlockStatement>,
+}
+
+impl Node for IfExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let mut out = format!(
+            "if{} {}",
+            self.condition.string(),
+            self.consequence.string()
+        );
+        if let Some(alternative) = &self.alternative {
+            out.push_str(&format!("else {}", alternative.string()));
+        }
+        out
+    }
+}
+
+impl Expression for IfExpression {
+    fn expression_node(&self) {}
+    fn as_if_expression(&self) -> Option<&IfExpression> {
+        Some(self)
+    }
+    fn as_if_expression_mut(&mut self) -> Option<&mut IfExpression> {
+        Some(self)
+    }
 }
 
 pub struct ReturnStatement {
     pub token: Token,
-    // return_value: dyn Expression,
+    pub return_value: Box<dyn Expression>,
 }
 
 impl Node for ReturnStatement {
@@ -111,7 +419,7 @@ impl Node for ReturnStatement {
     fn string(&self) -> String {
         let mut out = String::new();
         out.push_str(format!("{} ", self.token_literal()).as_str());
-        // add value here
+        out.push_str(&self.return_value.string());
         out.push_str(";");
         out
     }
@@ -122,6 +430,9 @@ impl Statement for ReturnStatement {
     fn as_return_statement(&self) -> Option<&ReturnStatement> {
         Some(&self)
     }
+    fn as_return_statement_mut(&mut self) -> Option<&mut ReturnStatement> {
+        Some(self)
+    }
 }
 
 pub struct ExpressionStatement {
@@ -143,11 +454,302 @@ impl Statement for ExpressionStatement {
     fn as_expression_statement(&self) -> Option<&ExpressionStatement> {
         Some(&self)
     }
+    fn as_expression_statement_mut(&mut self) -> Option<&mut ExpressionStatement> {
+        Some(self)
+    }
+}
+
+pub struct BlockStatement {
+    pub token: Token,
+    pub statements: Vec<Box<dyn Statement>>,
+}
+
+impl Node for BlockStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let mut out = String::new();
+        for statement in self.statements.iter() {
+            out.push_str(&statement.string())
+        }
+        out
+    }
+}
+
+impl Statement for BlockStatement {
+    fn statement_node(&self) {}
+    fn as_block_statement(&self) -> Option<&BlockStatement> {
+        Some(self)
+    }
+    fn as_block_statement_mut(&mut self) -> Option<&mut BlockStatement> {
+        Some(self)
+    }
+}
+
+pub struct WhileStatement {
+    pub token: Token,
+    pub condition: Box<dyn Expression>,
+    pub body: BlockStatement,
+}
+
+impl Node for WhileStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!("while{} {}", self.condition.string(), self.body.string())
+    }
+}
+
+impl Statement for WhileStatement {
+    fn statement_node(&self) {}
+    fn as_while_statement(&self) -> Option<&WhileStatement> {
+        Some(self)
+    }
+    fn as_while_statement_mut(&mut self) -> Option<&mut WhileStatement> {
+        Some(self)
+    }
+}
+
+// `for (value in iterable) body` or `for (key, value in iterable) body`.
+pub struct ForStatement {
+    pub token: Token,
+    pub key: Option<Identifier>,
+    pub value: Identifier,
+    pub iterable: Box<dyn Expression>,
+    pub body: BlockStatement,
+}
+
+impl Node for ForStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let mut variables = String::new();
+        if let Some(key) = &self.key {
+            variables.push_str(&key.string());
+            variables.push_str(", ");
+        }
+        variables.push_str(&self.value.string());
+        format!(
+            "for({} in {}) {}",
+            variables,
+            self.iterable.string(),
+            self.body.string()
+        )
+    }
+}
+
+impl Statement for ForStatement {
+    fn statement_node(&self) {}
+    fn as_for_statement(&self) -> Option<&ForStatement> {
+        Some(self)
+    }
+    fn as_for_statement_mut(&mut self) -> Option<&mut ForStatement> {
+        Some(self)
+    }
+}
+
+pub struct BreakStatement {
+    pub token: Token,
+}
+
+impl Node for BreakStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!("{};", self.token_literal())
+    }
+}
+
+impl Statement for BreakStatement {
+    fn statement_node(&self) {}
+    fn as_break_statement(&self) -> Option<&BreakStatement> {
+        Some(self)
+    }
+    fn as_break_statement_mut(&mut self) -> Option<&mut BreakStatement> {
+        Some(self)
+    }
+}
+
+pub struct ContinueStatement {
+    pub token: Token,
+}
+
+impl Node for ContinueStatement {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        format!("{};", self.token_literal())
+    }
+}
+
+impl Statement for ContinueStatement {
+    fn statement_node(&self) {}
+    fn as_continue_statement(&self) -> Option<&ContinueStatement> {
+        Some(self)
+    }
+    fn as_continue_statement_mut(&mut self) -> Option<&mut ContinueStatement> {
+        Some(self)
+    }
+}
+
+// `parameter_types` has one entry per parameter, `None` where the
+// parameter is not annotated.
+pub struct FunctionLiteral {
+    pub token: Token,
+    pub parameters: Vec<Identifier>,
+    pub parameter_types: Vec<Option<TypeExpression>>,
+    pub return_type: Option<TypeExpression>,
+    pub body: BlockStatement,
+}
+
+impl Node for FunctionLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let parameters: Vec<String> = self
+            .parameters
+            .iter()
+            .zip(self.parameter_types.iter())
+            .map(|(p, t)| match t {
+                Some(t) => format!("{}: {}", p.string(), t.string()),
+                None => p.string(),
+            })
+            .collect();
+        let return_type = match &self.return_type {
+            Some(t) => format!(" -> {}", t.string()),
+            None => String::new(),
+        };
+        format!(
+            "{}({}){} {}",
+            self.token_literal(),
+            parameters.join(", "),
+            return_type,
+            self.body.string()
+        )
+    }
+}
+
+impl Expression for FunctionLiteral {
+    fn expression_node(&self) {}
+    fn as_function_literal(&self) -> Option<&FunctionLiteral> {
+        Some(self)
+    }
+    fn as_function_literal_mut(&mut self) -> Option<&mut FunctionLiteral> {
+        Some(self)
+    }
+}
+
+// `function(arguments)`. The token is the `(`.
+pub struct CallExpression {
+    pub token: Token,
+    pub function: Box<dyn Expression>,
+    pub arguments: Vec<Box<dyn Expression>>,
+}
+
+impl Node for CallExpression {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let arguments: Vec<String> = self.arguments.iter().map(|a| a.string()).collect();
+        format!("{}({})", self.function.string(), arguments.join(", "))
+    }
+}
+
+impl Expression for CallExpression {
+    fn expression_node(&self) {}
+    fn as_call_expression(&self) -> Option<&CallExpression> {
+        Some(self)
+    }
+    fn as_call_expression_mut(&mut self) -> Option<&mut CallExpression> {
+        Some(self)
+    }
+}
+
+pub struct MacroLiteral {
+    pub token: Token,
+    pub parameters: Vec<Identifier>,
+    pub body: BlockStatement,
+}
+
+impl Node for MacroLiteral {
+    fn token_literal(&self) -> &str {
+        &self.token.literal
+    }
+    fn string(&self) -> String {
+        let parameters: Vec<String> = self.parameters.iter().map(|p| p.string()).collect();
+        format!(
+            "{}({}) {}",
+            self.token_literal(),
+            parameters.join(", "),
+            self.body.string()
+        )
+    }
+}
+
+impl Expression for MacroLiteral {
+    fn expression_node(&self) {}
+    fn as_macro_literal(&self) -> Option<&MacroLiteral> {
+        Some(self)
+    }
+    fn as_macro_literal_mut(&mut self) -> Option<&mut MacroLiteral> {
+        Some(self)
+    }
+}
+
+// A type annotation: a type name such as `int`, or a function type such as
+// `fn(int, int) -> bool`. Names are not checked by the parser.
+pub enum TypeExpression {
+    Named(Token),
+    Function {
+        token: Token,
+        parameters: Vec<TypeExpression>,
+        result: Box<TypeExpression>,
+    },
+}
+
+impl TypeExpression {
+    pub fn token(&self) -> &Token {
+        match self {
+            TypeExpression::Named(token) => token,
+            TypeExpression::Function { token, .. } => token,
+        }
+    }
+}
+
+impl Node for TypeExpression {
+    fn token_literal(&self) -> &str {
+        &self.token().literal
+    }
+    fn string(&self) -> String {
+        match self {
+            TypeExpression::Named(token) => token.literal.to_string(),
+            TypeExpression::Function {
+                token,
+                parameters,
+                result,
+            } => {
+                let parameters: Vec<String> = parameters.iter().map(|p| p.string()).collect();
+                format!(
+                    "{}({}) -> {}",
+                    token.literal,
+                    parameters.join(", "),
+                    result.string()
+                )
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod ast_tests {
-    use crate::token::token::{IDENT, LET};
+    use crate::token::token::{Span, IDENT, LET};
 
     use super::*;
 
@@ -158,20 +760,31 @@ mod ast_tests {
                 token: Token {
                     r#type: LET,
                     literal: "let".to_string(),
+                    span: Span::default(),
                 },
                 name: Box::new(Identifier {
                     token: Token {
                         r#type: IDENT,
                         literal: "myVar".to_string(),
+                        span: Span::default(),
                     },
                     value: "myVar".to_string(),
                 }),
+                r#type: None,
+                value: Box::new(Identifier {
+                    token: Token {
+                        r#type: IDENT,
+                        literal: "anotherVar".to_string(),
+                        span: Span::default(),
+                    },
+                    value: "anotherVar".to_string(),
+                }),
             })],
         };
 
         assert_eq!(
             program.string(),
-            "let myVar = ;",
+            "let myVar = anotherVar;",
             "program string wrong. got {}",
             program.string()
         );
@@ -0,0 +1,348 @@
+use super::ast::{
+    AssignExpression, BlockStatement, Boolean, BreakStatement, CallExpression, ContinueStatement,
+    Expression, ExpressionStatement, FloatLiteral, ForStatement, FunctionLiteral, Identifier,
+    IfExpression, InfixExpression, IntegerLiteral, LetStatement, MacroLiteral, PrefixExpression,
+    Program, ReturnStatement, Statement, TypeExpression, WhileStatement,
+};
+
+// Rewriting traversal of the AST. Methods receive the slot that holds a
+// node, so an implementation can mutate the node or replace it outright
+// (`*expression = Box::new(...)`). The defaults rewrite children before
+// returning, so overriding a method and calling its `walk_*` function
+// first gives a bottom-up rewrite.
+pub trait Fold {
+    fn fold_program(&mut self, program: &mut Program) {
+        walk_program(self, program);
+    }
+    fn fold_statement(&mut self, statement: &mut Box<dyn Statement>) {
+        walk_statement(self, statement);
+    }
+    fn fold_let_statement(&mut self, statement: &mut LetStatement) {
+        walk_let_statement(self, statement);
+    }
+    fn fold_return_statement(&mut self, statement: &mut ReturnStatement) {
+        walk_return_statement(self, statement);
+    }
+    fn fold_expression_statement(&mut self, statement: &mut ExpressionStatement) {
+        walk_expression_statement(self, statement);
+    }
+    fn fold_block_statement(&mut self, block: &mut BlockStatement) {
+        walk_block_statement(self, block);
+    }
+    fn fold_while_statement(&mut self, statement: &mut WhileStatement) {
+        walk_while_statement(self, statement);
+    }
+    fn fold_for_statement(&mut self, statement: &mut ForStatement) {
+        walk_for_statement(self, statement);
+    }
+    fn fold_break_statement(&mut self, _statement: &mut BreakStatement) {}
+    fn fold_continue_statement(&mut self, _statement: &mut ContinueStatement) {}
+    fn fold_expression(&mut self, expression: &mut Box<dyn Expression>) {
+        walk_expression(self, expression);
+    }
+    fn fold_identifier(&mut self, _identifier: &mut Identifier) {}
+    fn fold_integer_literal(&mut self, _literal: &mut IntegerLiteral) {}
+    fn fold_float_literal(&mut self, _literal: &mut FloatLiteral) {}
+    fn fold_boolean(&mut self, _boolean: &mut Boolean) {}
+    fn fold_prefix_expression(&mut self, expression: &mut PrefixExpression) {
+        walk_prefix_expression(self, expression);
+    }
+    fn fold_infix_expression(&mut self, expression: &mut InfixExpression) {
+        walk_infix_expression(self, expression);
+    }
+    fn fold_if_expression(&mut self, expression: &mut IfExpression) {
+        walk_if_expression(self, expression);
+    }
+    fn fold_function_literal(&mut self, literal: &mut FunctionLiteral) {
+        walk_function_literal(self, literal);
+    }
+    fn fold_macro_literal(&mut self, literal: &mut MacroLiteral) {
+        walk_macro_literal(self, literal);
+    }
+    fn fold_assign_expression(&mut self, expression: &mut AssignExpression) {
+        walk_assign_expression(self, expression);
+    }
+    fn fold_call_expression(&mut self, expression: &mut CallExpression) {
+        walk_call_expression(self, expression);
+    }
+    fn fold_type_expression(&mut self, r#type: &mut TypeExpression) {
+        walk_type_expression(self, r#type);
+    }
+}
+
+pub fn walk_program<F: Fold + ?Sized>(folder: &mut F, program: &mut Program) {
+    for statement in program.statements.iter_mut() {
+        folder.fold_statement(statement);
+    }
+}
+
+pub fn walk_statement<F: Fold + ?Sized>(folder: &mut F, statement: &mut Box<dyn Statement>) {
+    if let Some(stmt) = statement.as_let_statement_mut() {
+        folder.fold_let_statement(stmt);
+    } else if let Some(stmt) = statement.as_return_statement_mut() {
+        folder.fold_return_statement(stmt);
+    } else if let Some(stmt) = statement.as_expression_statement_mut() {
+        folder.fold_expression_statement(stmt);
+    } else if let Some(stmt) = statement.as_block_statement_mut() {
+        folder.fold_block_statement(stmt);
+    } else if let Some(stmt) = statement.as_while_statement_mut() {
+        folder.fold_while_statement(stmt);
+    } else if let Some(stmt) = statement.as_for_statement_mut() {
+        folder.fold_for_statement(stmt);
+    } else if let Some(stmt) = statement.as_break_statement_mut() {
+        folder.fold_break_statement(stmt);
+    } else if let Some(stmt) = statement.as_continue_statement_mut() {
+        folder.fold_continue_statement(stmt);
+    }
+}
+
+pub fn walk_let_statement<F: Fold + ?Sized>(folder: &mut F, statement: &mut LetStatement) {
+    folder.fold_identifier(&mut statement.name);
+    if let Some(r#type) = &mut statement.r#type {
+        folder.fold_type_expression(r#type);
+    }
+    folder.fold_expression(&mut statement.value);
+}
+
+pub fn walk_return_statement<F: Fold + ?Sized>(folder: &mut F, statement: &mut ReturnStatement) {
+    folder.fold_expression(&mut statement.return_value);
+}
+
+pub fn walk_expression_statement<F: Fold + ?Sized>(
+    folder: &mut F,
+    statement: &mut ExpressionStatement,
+) {
+    folder.fold_expression(&mut statement.expression);
+}
+
+pub fn walk_block_statement<F: Fold + ?Sized>(folder: &mut F, block: &mut BlockStatement) {
+    for statement in block.statements.iter_mut() {
+        folder.fold_statement(statement);
+    }
+}
+
+pub fn walk_while_statement<F: Fold + ?Sized>(folder: &mut F, statement: &mut WhileStatement) {
+    folder.fold_expression(&mut statement.condition);
+    folder.fold_block_statement(&mut statement.body);
+}
+
+pub fn walk_for_statement<F: Fold + ?Sized>(folder: &mut F, statement: &mut ForStatement) {
+    if let Some(key) = &mut statement.key {
+        folder.fold_identifier(key);
+    }
+    folder.fold_identifier(&mut statement.value);
+    folder.fold_expression(&mut statement.iterable);
+    folder.fold_block_statement(&mut statement.body);
+}
+
+pub fn walk_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut Box<dyn Expression>) {
+    if let Some(expr) = expression.as_identifier_expression_mut() {
+        folder.fold_identifier(expr);
+    } else if let Some(expr) = expression.as_integer_literal_mut() {
+        folder.fold_integer_literal(expr);
+    } else if let Some(expr) = expression.as_float_literal_mut() {
+        folder.fold_float_literal(expr);
+    } else if let Some(expr) = expression.as_boolean_mut() {
+        folder.fold_boolean(expr);
+    } else if let Some(expr) = expression.as_prefix_expression_mut() {
+        folder.fold_prefix_expression(expr);
+    } else if let Some(expr) = expression.as_infix_expression_mut() {
+        folder.fold_infix_expression(expr);
+    } else if let Some(expr) = expression.as_if_expression_mut() {
+        folder.fold_if_expression(expr);
+    } else if let Some(expr) = expression.as_function_literal_mut() {
+        folder.fold_function_literal(expr);
+    } else if let Some(expr) = expression.as_macro_literal_mut() {
+        folder.fold_macro_literal(expr);
+    } else if let Some(expr) = expression.as_assign_expression_mut() {
+        folder.fold_assign_expression(expr);
+    } else if let Some(expr) = expression.as_call_expression_mut() {
+        folder.fold_call_expression(expr);
+    }
+}
+
+pub fn walk_prefix_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut PrefixExpression) {
+    folder.fold_expression(&mut expression.right);
+}
+
+pub fn walk_infix_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut InfixExpression) {
+    folder.fold_expression(&mut expression.left);
+    folder.fold_expression(&mut expression.right);
+}
+
+pub fn walk_if_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut IfExpression) {
+    folder.fold_expression(&mut expression.condition);
+    folder.fold_block_statement(&mut expression.consequence);
+    if let Some(alternative) = &mut expression.alternative {
+        folder.fold_block_statement(alternative);
+    }
+}
+
+pub fn walk_function_literal<F: Fold + ?Sized>(folder: &mut F, literal: &mut FunctionLiteral) {
+    for (parameter, r#type) in literal
+        .parameters
+        .iter_mut()
+        .zip(literal.parameter_types.iter_mut())
+    {
+        folder.fold_identifier(parameter);
+        if let Some(r#type) = r#type {
+            folder.fold_type_expression(r#type);
+        }
+    }
+    if let Some(r#type) = &mut literal.return_type {
+        folder.fold_type_expression(r#type);
+    }
+    folder.fold_block_statement(&mut literal.body);
+}
+
+pub fn walk_macro_literal<F: Fold + ?Sized>(folder: &mut F, literal: &mut MacroLiteral) {
+    for parameter in literal.parameters.iter_mut() {
+        folder.fold_identifier(parameter);
+    }
+    folder.fold_block_statement(&mut literal.body);
+}
+
+pub fn walk_assign_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut AssignExpression) {
+    folder.fold_identifier(&mut expression.name);
+    folder.fold_expression(&mut expression.value);
+}
+
+pub fn walk_call_expression<F: Fold + ?Sized>(folder: &mut F, expression: &mut CallExpression) {
+    folder.fold_expression(&mut expression.function);
+    for argument in expression.arguments.iter_mut() {
+        folder.fold_expression(argument);
+    }
+}
+
+pub fn walk_type_expression<F: Fold + ?Sized>(folder: &mut F, r#type: &mut TypeExpression) {
+    if let TypeExpression::Function {
+        parameters, result, ..
+    } = r#type
+    {
+        for parameter in parameters.iter_mut() {
+            folder.fold_type_expression(parameter);
+        }
+        folder.fold_type_expression(result);
+    }
+}
+
+struct Modifier<M: FnMut(&mut Box<dyn Expression>)> {
+    modifier: M,
+}
+
+impl<M: FnMut(&mut Box<dyn Expression>)> Fold for Modifier<M> {
+    fn fold_expression(&mut self, expression: &mut Box<dyn Expression>) {
+        walk_expression(self, expression);
+        (self.modifier)(expression);
+    }
+}
+
+// Applies `modifier` to every expression in the program, children first.
+pub fn modify<M: FnMut(&mut Box<dyn Expression>)>(program: &mut Program, modifier: M) {
+    Modifier { modifier }.fold_program(program);
+}
+
+#[cfg(test)]
+mod fold_tests {
+    use crate::{ast::ast::Node, lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    fn parse(input: &str) -> Program {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        parser.parse_program()
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut program = parse("1; let one = 1; let m = macro(x) { 1; 2.5; return one; };");
+
+        modify(&mut program, |expression| {
+            let Some(integer) = expression.as_integer_literal() else {
+                return;
+            };
+            if integer.value != 1 {
+                return;
+            }
+            let mut token = integer.token.clone();
+            token.literal = "2".to_string();
+            *expression = Box::new(IntegerLiteral { token, value: 2 });
+        });
+
+        assert_eq!(
+            program.string(),
+            "2let one = 2;let m = macro(x) 22.5return one;;",
+            "modified program wrong. got {}",
+            program.string()
+        );
+    }
+
+    struct Renamer;
+
+    impl Fold for Renamer {
+        fn fold_identifier(&mut self, identifier: &mut Identifier) {
+            identifier.value = format!("{}_renamed", identifier.value);
+        }
+        fn fold_type_expression(&mut self, r#type: &mut TypeExpression) {
+            walk_type_expression(self, r#type);
+            if let TypeExpression::Named(token) = r#type {
+                token.literal = format!("{}_renamed", token.literal);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fold_reaches_every_identifier() {
+        let mut program =
+            parse("let a = b; macro(c) { d; }; fn(e) { f }; if (-g < h) { i } else { j }; while (k) { l }; for (m, n in o) { p }; q -= r");
+
+        Renamer.fold_program(&mut program);
+
+        assert_eq!(
+            program.string(),
+            "let a_renamed = b_renamed;macro(c_renamed) d_renamedfn(e_renamed) f_renamed\
+             if((-g_renamed) < h_renamed) i_renamedelse j_renamedwhilek_renamed l_renamed\
+             for(m_renamed, n_renamed in o_renamed) p_renamed(q_renamed -= r_renamed)",
+            "folded program wrong. got {}",
+            program.string()
+        );
+    }
+
+    #[derive(Default)]
+    struct LoopExits(Vec<String>);
+
+    impl Fold for LoopExits {
+        fn fold_break_statement(&mut self, statement: &mut BreakStatement) {
+            self.0.push(statement.token_literal().to_string());
+        }
+        fn fold_continue_statement(&mut self, statement: &mut ContinueStatement) {
+            self.0.push(statement.token_literal().to_string());
+        }
+    }
+
+    #[test]
+    fn test_fold_reaches_loop_exits() {
+        let mut program =
+            parse("while (x) { break; for (a in b) { continue } }; while (y) { continue; }");
+
+        let mut exits = LoopExits::default();
+        exits.fold_program(&mut program);
+
+        assert_eq!(exits.0, vec!["break", "continue", "continue"]);
+    }
+
+    #[test]
+    fn test_fold_reaches_every_type() {
+        let mut program = parse("let f: fn(int, bool) -> int = fn(a: int, b) -> float { a };");
+
+        Renamer.fold_program(&mut program);
+
+        assert_eq!(
+            program.string(),
+            "let f_renamed: fn(int_renamed, bool_renamed) -> int_renamed = \
+             fn(a_renamed: int_renamed, b_renamed) -> float_renamed a_renamed;",
+            "folded program wrong. got {}",
+            program.string()
+        );
+    }
+}
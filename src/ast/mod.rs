@@ -1 +1,6 @@
 pub mod ast;
+// Public API for rewriting passes; nothing in the binary rewrites the AST
+// until the macro expander lands.
+#[allow(dead_code)]
+pub mod fold;
+pub mod visit;
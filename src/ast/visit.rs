@@ -0,0 +1,300 @@
+use super::ast::{
+    AssignExpression, BlockStatement, Boolean, BreakStatement, CallExpression, ContinueStatement,
+    Expression, ExpressionStatement, FloatLiteral, ForStatement, FunctionLiteral, Identifier,
+    IfExpression, InfixExpression, IntegerLiteral, LetStatement, MacroLiteral, PrefixExpression,
+    Program, ReturnStatement, Statement, TypeExpression, WhileStatement,
+};
+
+// Read-only traversal of the AST. Every method defaults to walking the
+// node's children, so an implementation only overrides the nodes it cares
+// about and calls the matching `walk_*` function to keep descending.
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+    fn visit_statement(&mut self, statement: &dyn Statement) {
+        walk_statement(self, statement);
+    }
+    fn visit_let_statement(&mut self, statement: &LetStatement) {
+        walk_let_statement(self, statement);
+    }
+    fn visit_return_statement(&mut self, statement: &ReturnStatement) {
+        walk_return_statement(self, statement);
+    }
+    fn visit_expression_statement(&mut self, statement: &ExpressionStatement) {
+        walk_expression_statement(self, statement);
+    }
+    fn visit_block_statement(&mut self, block: &BlockStatement) {
+        walk_block_statement(self, block);
+    }
+    fn visit_while_statement(&mut self, statement: &WhileStatement) {
+        walk_while_statement(self, statement);
+    }
+    fn visit_for_statement(&mut self, statement: &ForStatement) {
+        walk_for_statement(self, statement);
+    }
+    fn visit_break_statement(&mut self, _statement: &BreakStatement) {}
+    fn visit_continue_statement(&mut self, _statement: &ContinueStatement) {}
+    fn visit_expression(&mut self, expression: &dyn Expression) {
+        walk_expression(self, expression);
+    }
+    fn visit_identifier(&mut self, _identifier: &Identifier) {}
+    fn visit_integer_literal(&mut self, _literal: &IntegerLiteral) {}
+    fn visit_float_literal(&mut self, _literal: &FloatLiteral) {}
+    fn visit_boolean(&mut self, _boolean: &Boolean) {}
+    fn visit_prefix_expression(&mut self, expression: &PrefixExpression) {
+        walk_prefix_expression(self, expression);
+    }
+    fn visit_infix_expression(&mut self, expression: &InfixExpression) {
+        walk_infix_expression(self, expression);
+    }
+    fn visit_if_expression(&mut self, expression: &IfExpression) {
+        walk_if_expression(self, expression);
+    }
+    fn visit_function_literal(&mut self, literal: &FunctionLiteral) {
+        walk_function_literal(self, literal);
+    }
+    fn visit_macro_literal(&mut self, literal: &MacroLiteral) {
+        walk_macro_literal(self, literal);
+    }
+    fn visit_assign_expression(&mut self, expression: &AssignExpression) {
+        walk_assign_expression(self, expression);
+    }
+    fn visit_call_expression(&mut self, expression: &CallExpression) {
+        walk_call_expression(self, expression);
+    }
+    fn visit_type_expression(&mut self, r#type: &TypeExpression) {
+        walk_type_expression(self, r#type);
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for statement in program.statements.iter() {
+        visitor.visit_statement(statement.as_ref());
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &dyn Statement) {
+    if let Some(stmt) = statement.as_let_statement() {
+        visitor.visit_let_statement(stmt);
+    } else if let Some(stmt) = statement.as_return_statement() {
+        visitor.visit_return_statement(stmt);
+    } else if let Some(stmt) = statement.as_expression_statement() {
+        visitor.visit_expression_statement(stmt);
+    } else if let Some(stmt) = statement.as_block_statement() {
+        visitor.visit_block_statement(stmt);
+    } else if let Some(stmt) = statement.as_while_statement() {
+        visitor.visit_while_statement(stmt);
+    } else if let Some(stmt) = statement.as_for_statement() {
+        visitor.visit_for_statement(stmt);
+    } else if let Some(stmt) = statement.as_break_statement() {
+        visitor.visit_break_statement(stmt);
+    } else if let Some(stmt) = statement.as_continue_statement() {
+        visitor.visit_continue_statement(stmt);
+    }
+}
+
+pub fn walk_let_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &LetStatement) {
+    visitor.visit_identifier(&statement.name);
+    if let Some(r#type) = &statement.r#type {
+        visitor.visit_type_expression(r#type);
+    }
+    visitor.visit_expression(statement.value.as_ref());
+}
+
+pub fn walk_return_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &ReturnStatement) {
+    visitor.visit_expression(statement.return_value.as_ref());
+}
+
+pub fn walk_expression_statement<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    statement: &ExpressionStatement,
+) {
+    visitor.visit_expression(statement.expression.as_ref());
+}
+
+pub fn walk_block_statement<V: Visitor + ?Sized>(visitor: &mut V, block: &BlockStatement) {
+    for statement in block.statements.iter() {
+        visitor.visit_statement(statement.as_ref());
+    }
+}
+
+pub fn walk_while_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &WhileStatement) {
+    visitor.visit_expression(statement.condition.as_ref());
+    visitor.visit_block_statement(&statement.body);
+}
+
+pub fn walk_for_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &ForStatement) {
+    if let Some(key) = &statement.key {
+        visitor.visit_identifier(key);
+    }
+    visitor.visit_identifier(&statement.value);
+    visitor.visit_expression(statement.iterable.as_ref());
+    visitor.visit_block_statement(&statement.body);
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &dyn Expression) {
+    if let Some(expr) = expression.as_identifier_expression() {
+        visitor.visit_identifier(expr);
+    } else if let Some(expr) = expression.as_integer_literal() {
+        visitor.visit_integer_literal(expr);
+    } else if let Some(expr) = expression.as_float_literal() {
+        visitor.visit_float_literal(expr);
+    } else if let Some(expr) = expression.as_boolean() {
+        visitor.visit_boolean(expr);
+    } else if let Some(expr) = expression.as_prefix_expression() {
+        visitor.visit_prefix_expression(expr);
+    } else if let Some(expr) = expression.as_infix_expression() {
+        visitor.visit_infix_expression(expr);
+    } else if let Some(expr) = expression.as_if_expression() {
+        visitor.visit_if_expression(expr);
+    } else if let Some(expr) = expression.as_function_literal() {
+        visitor.visit_function_literal(expr);
+    } else if let Some(expr) = expression.as_macro_literal() {
+        visitor.visit_macro_literal(expr);
+    } else if let Some(expr) = expression.as_assign_expression() {
+        visitor.visit_assign_expression(expr);
+    } else if let Some(expr) = expression.as_call_expression() {
+        visitor.visit_call_expression(expr);
+    }
+}
+
+pub fn walk_prefix_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &PrefixExpression) {
+    visitor.visit_expression(expression.right.as_ref());
+}
+
+pub fn walk_infix_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &InfixExpression) {
+    visitor.visit_expression(expression.left.as_ref());
+    visitor.visit_expression(expression.right.as_ref());
+}
+
+pub fn walk_if_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &IfExpression) {
+    visitor.visit_expression(expression.condition.as_ref());
+    visitor.visit_block_statement(&expression.consequence);
+    if let Some(alternative) = &expression.alternative {
+        visitor.visit_block_statement(alternative);
+    }
+}
+
+pub fn walk_function_literal<V: Visitor + ?Sized>(visitor: &mut V, literal: &FunctionLiteral) {
+    for (parameter, r#type) in literal
+        .parameters
+        .iter()
+        .zip(literal.parameter_types.iter())
+    {
+        visitor.visit_identifier(parameter);
+        if let Some(r#type) = r#type {
+            visitor.visit_type_expression(r#type);
+        }
+    }
+    if let Some(r#type) = &literal.return_type {
+        visitor.visit_type_expression(r#type);
+    }
+    visitor.visit_block_statement(&literal.body);
+}
+
+pub fn walk_macro_literal<V: Visitor + ?Sized>(visitor: &mut V, literal: &MacroLiteral) {
+    for parameter in literal.parameters.iter() {
+        visitor.visit_identifier(parameter);
+    }
+    visitor.visit_block_statement(&literal.body);
+}
+
+pub fn walk_assign_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &AssignExpression) {
+    visitor.visit_identifier(&expression.name);
+    visitor.visit_expression(expression.value.as_ref());
+}
+
+pub fn walk_call_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &CallExpression) {
+    visitor.visit_expression(expression.function.as_ref());
+    for argument in expression.arguments.iter() {
+        visitor.visit_expression(argument.as_ref());
+    }
+}
+
+pub fn walk_type_expression<V: Visitor + ?Sized>(visitor: &mut V, r#type: &TypeExpression) {
+    if let TypeExpression::Function {
+        parameters, result, ..
+    } = r#type
+    {
+        for parameter in parameters.iter() {
+            visitor.visit_type_expression(parameter);
+        }
+        visitor.visit_type_expression(result);
+    }
+}
+
+#[cfg(test)]
+mod visit_tests {
+    use crate::{ast::ast::Node, lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        identifiers: Vec<String>,
+        integers: usize,
+        floats: usize,
+        blocks: usize,
+        types: Vec<String>,
+    }
+
+    impl Visitor for Counter {
+        fn visit_identifier(&mut self, identifier: &Identifier) {
+            self.identifiers.push(identifier.value.clone());
+        }
+        fn visit_integer_literal(&mut self, _literal: &IntegerLiteral) {
+            self.integers += 1;
+        }
+        fn visit_float_literal(&mut self, _literal: &FloatLiteral) {
+            self.floats += 1;
+        }
+        fn visit_block_statement(&mut self, block: &BlockStatement) {
+            self.blocks += 1;
+            walk_block_statement(self, block);
+        }
+        fn visit_type_expression(&mut self, r#type: &TypeExpression) {
+            self.types.push(r#type.token_literal().to_string());
+            walk_type_expression(self, r#type);
+        }
+    }
+
+    #[test]
+    fn test_visitor_walks_every_node() {
+        let input = r"
+        let x = y;
+        let m = macro(a, b) { a; 5; 2.5; };
+        let f: fn(int) -> int = fn(c: int) -> int { c };
+        if (!d == 1 + e) { g } else { true };
+        while (h) { break; }
+        for (i, j in k) { j }
+        l += 1;
+        f(l, 2);
+        return z;
+        ";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        let mut counter = Counter::default();
+        counter.visit_program(&program);
+
+        assert_eq!(
+            counter.identifiers,
+            vec![
+                "x", "y", "m", "a", "b", "a", "f", "c", "c", "d", "e", "g", "h", "i", "j", "k",
+                "j", "l", "f", "l", "z",
+            ],
+            "visited identifiers wrong. got {:?}",
+            counter.identifiers
+        );
+        assert_eq!(counter.integers, 4, "integer literals visited wrong");
+        assert_eq!(counter.floats, 1, "float literals visited wrong");
+        assert_eq!(counter.blocks, 6, "blocks visited wrong");
+        assert_eq!(
+            counter.types,
+            vec!["fn", "int", "int", "int", "int"],
+            "visited types wrong. got {:?}",
+            counter.types
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use std::process::ExitCode;
+
+use crate::{
+    ast::ast::Node,
+    cli::input::{parse_args, read_source},
+    cst::cst::parse,
+    export::{dot::program_to_dot, json::ast_to_json},
+    lexer::lexer::Lexer,
+    parser::parser::Parser,
+};
+
+const USAGE: &str = "usage: ast [--json | --dot | --cst] [FILE]";
+
+// Prints the parsed program of FILE (or stdin). With `--json` the program
+// and any parse errors are printed as one JSON document instead; with
+// `--dot` the program is printed as a Graphviz graph, and with `--cst` the
+// concrete syntax tree is dumped with every token and trivia.
+pub fn run(args: &[String]) -> ExitCode {
+    let (flags, path) = match parse_args(args, &["--json", "--dot", "--cst"], USAGE) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    if flags.len() > 1 {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if flags.contains(&"--json") {
+        println!("{}", ast_to_json(&source).pretty());
+        return ExitCode::SUCCESS;
+    }
+
+    if flags.contains(&"--cst") {
+        let parse = parse(&source);
+        print!("{}", parse.root.debug_tree());
+        for error in parse.errors.iter() {
+            eprintln!("{}", error);
+        }
+        return if parse.errors.is_empty() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+    if flags.contains(&"--dot") {
+        print!("{}", program_to_dot(&program));
+    } else {
+        println!("{}", program.string());
+    }
+
+    for error in parser.errors() {
+        eprintln!("{}", error);
+    }
+    if parser.errors().is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
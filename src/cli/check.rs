@@ -0,0 +1,63 @@
+use std::process::ExitCode;
+
+use crate::{
+    cli::input::{parse_args, read_source},
+    lexer::lexer::Lexer,
+    parser::parser::Parser,
+    resolver::resolver::resolve,
+    types::{check::check, types::infer},
+};
+
+const USAGE: &str = "usage: check [--types] [FILE]";
+
+// Reports the parse errors of FILE (or stdin), then the identifiers that
+// are undefined or declared twice, then the code that does not match its
+// type annotations. With `--types` the types of unannotated code are
+// inferred and checked as well, and the type of every top-level binding is
+// printed. Nothing is run; the command fails when anything is reported.
+pub fn run(args: &[String]) -> ExitCode {
+    let (flags, path) = match parse_args(args, &["--types"], USAGE) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+    let name = path.unwrap_or("<stdin>");
+
+    let mut parser = Parser::new(Lexer::new(source.clone()));
+    let program = parser.parse_program();
+    let mut messages: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+    // Statements with parse errors are dropped, so resolving the rest would
+    // report their names as undefined.
+    if messages.is_empty() {
+        let resolution = resolve(&source, &program);
+        messages.extend(resolution.errors.iter().map(|e| e.to_string()));
+
+        if flags.contains(&"--types") {
+            let inference = infer(&program);
+            messages.extend(inference.errors.iter().map(|e| e.to_string()));
+            for binding in inference.bindings.iter() {
+                println!("{}: {}", binding.name, binding.ty);
+            }
+        } else {
+            messages.extend(check(&program).iter().map(|e| e.to_string()));
+        }
+    }
+
+    for msg in messages.iter() {
+        eprintln!("{}:{}", name, msg);
+    }
+    if messages.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
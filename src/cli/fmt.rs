@@ -0,0 +1,102 @@
+use std::{
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use crate::formatter::formatter::format;
+
+const USAGE: &str = "usage: fmt [--check] [FILE...]";
+
+// Formats the given files in place, or stdin to stdout when no file is
+// given. With `--check` nothing is written; files that are not formatted
+// are listed and the command fails.
+pub fn run(args: &[String]) -> ExitCode {
+    let mut check = false;
+    let mut paths = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            flag if flag.starts_with('-') => {
+                eprintln!("unknown flag {}\n{}", flag, USAGE);
+                return ExitCode::from(2);
+            }
+            path => paths.push(path),
+        }
+    }
+
+    let ok = if paths.is_empty() {
+        format_stdin(check)
+    } else {
+        let mut ok = true;
+        for path in paths {
+            ok &= format_file(path, check);
+        }
+        ok
+    };
+
+    if ok {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn format_file(path: &str, check: bool) -> bool {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            return false;
+        }
+    };
+    let formatted = match format(&source) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            for line in err.to_string().lines() {
+                eprintln!("{}:{}", path, line);
+            }
+            return false;
+        }
+    };
+
+    if formatted == source {
+        return true;
+    }
+    if check {
+        println!("{}", path);
+        return false;
+    }
+    if let Err(err) = fs::write(path, formatted) {
+        eprintln!("{}: {}", path, err);
+        return false;
+    }
+    true
+}
+
+fn format_stdin(check: bool) -> bool {
+    let mut source = String::new();
+    if let Err(err) = io::stdin().read_to_string(&mut source) {
+        eprintln!("<stdin>: {}", err);
+        return false;
+    }
+    let formatted = match format(&source) {
+        Ok(formatted) => formatted,
+        Err(err) => {
+            for line in err.to_string().lines() {
+                eprintln!("<stdin>:{}", line);
+            }
+            return false;
+        }
+    };
+
+    if check {
+        if formatted != source {
+            println!("<stdin>");
+            return false;
+        }
+        return true;
+    }
+    print!("{}", formatted);
+    true
+}
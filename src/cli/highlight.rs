@@ -0,0 +1,35 @@
+use std::process::ExitCode;
+
+use crate::{
+    cli::input::{parse_args, read_source},
+    highlight::highlight::{classify, to_ansi, to_html},
+};
+
+const USAGE: &str = "usage: highlight [--html] [FILE]";
+
+// Prints FILE (or stdin) with ANSI color escapes, or as an HTML block with
+// `--html`.
+pub fn run(args: &[String]) -> ExitCode {
+    let (flags, path) = match parse_args(args, &["--html"], USAGE) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let highlights = classify(&source);
+    if flags.contains(&"--html") {
+        print!("{}", to_html(&source, &highlights));
+    } else {
+        print!("{}", to_ansi(&source, &highlights));
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,42 @@
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+// Reads the file at `path`, or stdin when no path is given. Errors are
+// already prefixed with the input's name.
+pub fn read_source(path: Option<&str>) -> Result<String, String> {
+    match path {
+        Some(path) => fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err)),
+        None => {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|err| format!("<stdin>: {}", err))?;
+            Ok(source)
+        }
+    }
+}
+
+// Splits arguments into the flags in `known` and at most one path.
+pub fn parse_args<'a>(
+    args: &'a [String],
+    known: &[&str],
+    usage: &str,
+) -> Result<(Vec<&'a str>, Option<&'a str>), String> {
+    let mut flags = vec![];
+    let mut path = None;
+    for arg in args {
+        if arg.starts_with('-') {
+            if !known.contains(&arg.as_str()) {
+                return Err(format!("unknown flag {}\n{}", arg, usage));
+            }
+            flags.push(arg.as_str());
+        } else if path.is_none() {
+            path = Some(arg.as_str());
+        } else {
+            return Err(usage.to_string());
+        }
+    }
+    Ok((flags, path))
+}
@@ -0,0 +1,119 @@
+use std::{env, fs, path::Path, process::ExitCode};
+
+use crate::{
+    cli::input::{parse_args, read_source},
+    fix::fix::{apply, Fix},
+    lexer::lexer::Lexer,
+    lint::{
+        config::Config,
+        lint::{lint, Diagnostic, Severity, RULES},
+    },
+    parser::parser::{ParseError, Parser},
+    resolver::resolver::resolve,
+};
+
+const USAGE: &str = "usage: lint [--fix] [--rules] [FILE]";
+
+// Lints FILE (or stdin) with the severities from the nearest `.monkeylint`
+// in the file's directory or one of its parents (the working directory for
+// stdin). Parse errors are reported instead of lints. The command fails
+// when anything at error severity is reported. `--rules` lists the rules
+// with their configured severity.
+//
+// `--fix` first applies the suggested fixes, rewriting FILE in place or
+// printing the fixed source for stdin, then reports what is left. Fixes are
+// applied once: a binding left unused by a removal is only reported.
+pub fn run(args: &[String]) -> ExitCode {
+    let (flags, path) = match parse_args(args, &["--fix", "--rules"], USAGE) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+
+    let dir = match path {
+        Some(path) => Path::new(path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+        None => env::current_dir().unwrap_or_default(),
+    };
+    let config = match Config::load(&dir) {
+        Ok(config) => config,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+    if flags.contains(&"--rules") {
+        for rule in RULES.iter() {
+            println!(
+                "{:<20} {:<8} {}",
+                rule.id,
+                config.severity(rule.id).name(),
+                rule.description
+            );
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut source = match read_source(path) {
+        Ok(source) => source,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+    let name = path.unwrap_or("<stdin>");
+
+    if flags.contains(&"--fix") {
+        let fixes: Vec<Fix> = match analyze(&source, &config) {
+            Err(errors) => errors.into_iter().filter_map(|e| e.fix).collect(),
+            Ok(diagnostics) => diagnostics.into_iter().filter_map(|d| d.fix).collect(),
+        };
+        let (fixed, applied) = apply(&source, &fixes.iter().collect::<Vec<_>>());
+        source = fixed;
+
+        match path {
+            Some(path) if applied > 0 => {
+                if let Err(err) = fs::write(path, &source) {
+                    eprintln!("{}: {}", path, err);
+                    return ExitCode::FAILURE;
+                }
+            }
+            Some(_) => {}
+            None => print!("{}", source),
+        }
+    }
+
+    match analyze(&source, &config) {
+        Err(errors) => {
+            for err in errors.iter() {
+                eprintln!("{}:{}", name, err);
+            }
+            ExitCode::FAILURE
+        }
+        Ok(diagnostics) => {
+            for diagnostic in diagnostics.iter() {
+                eprintln!("{}:{}", name, diagnostic);
+            }
+            if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+                ExitCode::FAILURE
+            } else {
+                ExitCode::SUCCESS
+            }
+        }
+    }
+}
+
+// The lint diagnostics of `source`, or its parse errors if it has any.
+fn analyze(source: &str, config: &Config) -> Result<Vec<Diagnostic>, Vec<ParseError>> {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    let program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().to_vec());
+    }
+    let resolution = resolve(source, &program);
+    Ok(lint(source, &program, &resolution, config))
+}
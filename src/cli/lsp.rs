@@ -0,0 +1,21 @@
+use std::{io, process::ExitCode};
+
+use crate::lsp::lsp::serve;
+
+const USAGE: &str = "usage: lsp";
+
+// Runs the language server on stdin and stdout until the client exits.
+pub fn run(args: &[String]) -> ExitCode {
+    if !args.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    }
+
+    match serve(&mut io::stdin().lock(), &mut io::stdout().lock()) {
+        Ok(code) => ExitCode::from(code as u8),
+        Err(err) => {
+            eprintln!("lsp: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,8 @@
+pub mod ast;
+pub mod check;
+pub mod fmt;
+pub mod highlight;
+pub mod input;
+pub mod lint;
+pub mod lsp;
+pub mod tokens;
@@ -0,0 +1,60 @@
+use std::process::ExitCode;
+
+use crate::{
+    cli::input::{parse_args, read_source},
+    export::json::tokens_to_json,
+    lexer::lexer::Lexer,
+    token::token::EOF,
+};
+
+const USAGE: &str = "usage: tokens [--json] [FILE]";
+
+// Prints the token stream of FILE (or stdin), one token per line. With
+// `--json` the stream and any lexer errors are printed as one JSON
+// document instead.
+pub fn run(args: &[String]) -> ExitCode {
+    let (flags, path) = match parse_args(args, &["--json"], USAGE) {
+        Ok(parsed) => parsed,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::from(2);
+        }
+    };
+    let source = match read_source(path) {
+        Ok(source) => source,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if flags.contains(&"--json") {
+        println!("{}", tokens_to_json(&source).pretty());
+        return ExitCode::SUCCESS;
+    }
+
+    let mut lexer = Lexer::new(source);
+    loop {
+        let tok = lexer.next_token();
+        println!(
+            "{}:{}\t{}\t{:?}",
+            tok.span.line, tok.span.column, tok.r#type, tok.literal
+        );
+        if tok.r#type == EOF {
+            break;
+        }
+    }
+
+    let errors = lexer.take_errors();
+    for error in errors.iter() {
+        eprintln!(
+            "{}:{}: {}",
+            error.span.line, error.span.column, error.message
+        );
+    }
+    if errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,565 @@
+use crate::{
+    lexer::lexer::{Comment, Lexer},
+    parser::parser::{ParseError, Parser},
+    token::token::{Span, Token, EOF},
+};
+
+pub type SyntaxKind = &'static str;
+
+// Node kinds
+pub const PROGRAM: SyntaxKind = "PROGRAM";
+pub const LET_STATEMENT: SyntaxKind = "LET_STATEMENT";
+pub const RETURN_STATEMENT: SyntaxKind = "RETURN_STATEMENT";
+pub const EXPRESSION_STATEMENT: SyntaxKind = "EXPRESSION_STATEMENT";
+pub const EMPTY_STATEMENT: SyntaxKind = "EMPTY_STATEMENT";
+pub const BLOCK_STATEMENT: SyntaxKind = "BLOCK_STATEMENT";
+pub const WHILE_STATEMENT: SyntaxKind = "WHILE_STATEMENT";
+pub const FOR_STATEMENT: SyntaxKind = "FOR_STATEMENT";
+pub const BREAK_STATEMENT: SyntaxKind = "BREAK_STATEMENT";
+pub const CONTINUE_STATEMENT: SyntaxKind = "CONTINUE_STATEMENT";
+pub const IDENTIFIER: SyntaxKind = "IDENTIFIER";
+pub const INTEGER_LITERAL: SyntaxKind = "INTEGER_LITERAL";
+pub const FLOAT_LITERAL: SyntaxKind = "FLOAT_LITERAL";
+pub const BOOLEAN_LITERAL: SyntaxKind = "BOOLEAN_LITERAL";
+pub const PREFIX_EXPRESSION: SyntaxKind = "PREFIX_EXPRESSION";
+pub const INFIX_EXPRESSION: SyntaxKind = "INFIX_EXPRESSION";
+pub const ASSIGN_EXPRESSION: SyntaxKind = "ASSIGN_EXPRESSION";
+pub const CALL_EXPRESSION: SyntaxKind = "CALL_EXPRESSION";
+pub const PAREN_EXPRESSION: SyntaxKind = "PAREN_EXPRESSION";
+pub const IF_EXPRESSION: SyntaxKind = "IF_EXPRESSION";
+pub const FUNCTION_LITERAL: SyntaxKind = "FUNCTION_LITERAL";
+pub const MACRO_LITERAL: SyntaxKind = "MACRO_LITERAL";
+pub const PARAMETER_LIST: SyntaxKind = "PARAMETER_LIST";
+pub const ARGUMENT_LIST: SyntaxKind = "ARGUMENT_LIST";
+pub const NAMED_TYPE: SyntaxKind = "NAMED_TYPE";
+pub const FUNCTION_TYPE: SyntaxKind = "FUNCTION_TYPE";
+pub const ERROR: SyntaxKind = "ERROR";
+
+// Trivia kinds. The lexer skips these instead of returning them as tokens,
+// so the tree recovers them from the source between token spans.
+pub const WHITESPACE: SyntaxKind = "WHITESPACE";
+pub const COMMENT: SyntaxKind = "COMMENT";
+
+// Token kinds are the lexer's token types plus the trivia kinds above.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxToken {
+    pub kind: SyntaxKind,
+    pub text: String,
+    pub span: Span,
+}
+
+impl SyntaxToken {
+    pub fn is_trivia(&self) -> bool {
+        self.kind == WHITESPACE || self.kind == COMMENT
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    // The exact source text covered by the node, trivia included. Tools
+    // that edit the tree print it back with this; nothing in the binary
+    // does yet.
+    #[allow(dead_code)]
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.push_text(&mut out);
+        out
+    }
+
+    // Appends the node's text to `out`.
+    pub fn push_text(&self, out: &mut String) {
+        for child in self.children.iter() {
+            match child {
+                SyntaxElement::Node(node) => node.push_text(out),
+                SyntaxElement::Token(token) => out.push_str(&token.text),
+            }
+        }
+    }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // The node's own tokens, without trivia.
+    pub fn child_tokens(&self) -> impl Iterator<Item = &SyntaxToken> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Token(token) if !token.is_trivia() => Some(token),
+            _ => None,
+        })
+    }
+
+    // First non-trivia token anywhere below the node.
+    pub fn first_token(&self) -> Option<&SyntaxToken> {
+        self.children.iter().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.first_token(),
+            SyntaxElement::Token(token) if !token.is_trivia() => Some(token),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // Last non-trivia token anywhere below the node.
+    pub fn last_token(&self) -> Option<&SyntaxToken> {
+        self.children.iter().rev().find_map(|child| match child {
+            SyntaxElement::Node(node) => node.last_token(),
+            SyntaxElement::Token(token) if !token.is_trivia() => Some(token),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    // An indented dump of the tree, one element per line.
+    pub fn debug_tree(&self) -> String {
+        let mut out = String::new();
+        self.push_debug_tree(&mut out, 0);
+        out
+    }
+
+    fn push_debug_tree(&self, out: &mut String, depth: usize) {
+        out.push_str(&format!("{}{}\n", "  ".repeat(depth), self.kind));
+        for child in self.children.iter() {
+            match child {
+                SyntaxElement::Node(node) => node.push_debug_tree(out, depth + 1),
+                SyntaxElement::Token(token) => out.push_str(&format!(
+                    "{}{} {:?}\n",
+                    "  ".repeat(depth + 1),
+                    token.kind,
+                    token.text
+                )),
+            }
+        }
+    }
+}
+
+// What the parser records about the shape of the program, in order.
+// Tokens are numbered from 0 in lexing order. A node covers the tokens
+// from the current token when it starts to the current token when it
+// finishes; `ok` is false when its parse failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Start { kind: SyntaxKind, token: usize },
+    Finish { token: usize, ok: bool },
+}
+
+pub struct Parse {
+    pub root: SyntaxNode,
+    pub errors: Vec<ParseError>,
+}
+
+// Builds a lossless syntax tree: every character of `source` belongs to
+// exactly one token of the tree, so `parse(source).root.text() == source`
+// for any input. The nodes are the ones the parser recorded while parsing
+// the program; nodes it could not parse become ERROR nodes. `lower` turns
+// the tree into the AST.
+pub fn parse(source: &str) -> Parse {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    parser.parse_program();
+
+    let mut lexer = Lexer::new(source.to_string());
+    let mut raw_tokens = vec![];
+    loop {
+        let tok = lexer.next_token();
+        let is_eof = tok.r#type == EOF;
+        raw_tokens.push(tok);
+        if is_eof {
+            break;
+        }
+    }
+    let comments = lexer.take_comments();
+
+    let source: Vec<char> = source.chars().collect();
+    let mut builder = Builder {
+        tokens: attach_trivia(&source, raw_tokens, &comments),
+        pos: 0,
+        stack: vec![SyntaxNode {
+            kind: PROGRAM,
+            children: vec![],
+        }],
+    };
+    for event in parser.take_events() {
+        match event {
+            Event::Start { kind, token } => builder.start_node(kind, token),
+            Event::Finish { token, ok } => builder.finish_node(token, ok),
+        }
+    }
+    builder.bump_until(builder.tokens.len());
+    builder.flush_trivia();
+
+    Parse {
+        root: builder
+            .stack
+            .pop()
+            .expect("root node should be on the stack"),
+        errors: parser.errors().to_vec(),
+    }
+}
+
+struct RawToken {
+    trivia: Vec<SyntaxToken>,
+    token: SyntaxToken,
+}
+
+fn attach_trivia(source: &[char], tokens: Vec<Token>, comments: &[Comment]) -> Vec<RawToken> {
+    let mut line_starts = vec![0];
+    for (i, ch) in source.iter().enumerate() {
+        if *ch == '\n' {
+            line_starts.push(i as u32 + 1);
+        }
+    }
+    let span = |start: u32, end: u32| {
+        let line = line_starts.partition_point(|s| *s <= start);
+        Span {
+            start,
+            end,
+            line: line as u32,
+            column: start - line_starts[line - 1] + 1,
+        }
+    };
+    let text =
+        |start: u32, end: u32| -> String { source[start as usize..end as usize].iter().collect() };
+
+    let mut comments = comments.iter().peekable();
+    let mut raw_tokens = vec![];
+    let mut offset = 0;
+    for tok in tokens {
+        let mut trivia = vec![];
+        while offset < tok.span.start {
+            let kind = match comments.peek() {
+                Some(comment) if comment.span.start == offset => COMMENT,
+                _ => WHITESPACE,
+            };
+            let end = if kind == COMMENT {
+                comments.next().expect("comment was peeked").span.end
+            } else {
+                let next_comment = comments.peek().map_or(tok.span.start, |c| c.span.start);
+                next_comment.min(tok.span.start)
+            };
+            trivia.push(SyntaxToken {
+                kind,
+                text: text(offset, end),
+                span: span(offset, end),
+            });
+            offset = end;
+        }
+
+        offset = tok.span.end.max(offset);
+        raw_tokens.push(RawToken {
+            trivia,
+            token: SyntaxToken {
+                kind: tok.r#type,
+                text: tok.literal,
+                span: tok.span,
+            },
+        });
+    }
+    raw_tokens
+}
+
+struct Builder {
+    tokens: Vec<RawToken>,
+    // Index of the next token to add to the tree.
+    pos: usize,
+    stack: Vec<SyntaxNode>,
+}
+
+impl Builder {
+    fn push(&mut self, element: SyntaxElement) {
+        self.stack
+            .last_mut()
+            .expect("a node should be open")
+            .children
+            .push(element);
+    }
+
+    // Moves the trivia in front of the next token into the open node, so
+    // that trivia between statements stays outside of them.
+    fn flush_trivia(&mut self) {
+        let trivia = std::mem::take(&mut self.tokens[self.pos].trivia);
+        for token in trivia {
+            self.push(SyntaxElement::Token(token));
+        }
+    }
+
+    // Adds the tokens before index `end` to the open node. EOF is never
+    // added; its leading trivia is flushed into the root.
+    fn bump_until(&mut self, end: usize) {
+        let end = end.min(self.tokens.len() - 1);
+        while self.pos < end {
+            self.flush_trivia();
+            let token = self.tokens[self.pos].token.clone();
+            self.push(SyntaxElement::Token(token));
+            self.pos += 1;
+        }
+    }
+
+    fn start_node(&mut self, kind: SyntaxKind, token: usize) {
+        self.bump_until(token);
+        self.flush_trivia();
+        self.stack.push(SyntaxNode {
+            kind,
+            children: vec![],
+        });
+    }
+
+    // Nodes that end before their first token, such as a type the parser
+    // gave up on at the end of the input, are left out.
+    fn finish_node(&mut self, token: usize, ok: bool) {
+        self.bump_until(token + 1);
+        let mut node = self.stack.pop().expect("a node should be open");
+        if node.children.is_empty() {
+            return;
+        }
+        if !ok {
+            node.kind = ERROR;
+        }
+        self.push(SyntaxElement::Node(node));
+    }
+}
+
+#[cfg(test)]
+mod cst_tests {
+    use crate::{ast::ast::Node, cst::lower::lower};
+
+    use super::*;
+
+    const CORPUS: [&str; 21] = [
+        "",
+        "   \n\t ",
+        "let x = 5;",
+        "let x = 5; // five\n// done",
+        "let m = macro(a, b) {\n    a; // first\n    b;\n};\r\nreturn m;",
+        "let z = x + y;",
+        "let = 5; }",
+        "macro(x { x",
+        "0xZZ 1__0 ٣ @ # $",
+        "// only a comment",
+        "let a = fn(x, y) { x + y; };\n\nlet b = a(1, 2);\n",
+        "let 日本 = 1;\n  \"not a string\"",
+        "let f : fn(int)->bool = fn(a: int, b) -> bool { a };",
+        "let g: fn(int = fn(a:) -> { a",
+        "while (x < 3) { // loop\n  break ; continue }; break x",
+        "while x) { fn() { continue; } } while (",
+        "for (k, v in h) { k; break }; for(x in -1){continue;}",
+        "for (k v in) { } for (x, in y {",
+        "a = b += (c) -= 1; (d) *= 2 /= e",
+        "1 = x; if (x = 1) { y = } x + y = z",
+        "f(a, g(b))(); h(1, ; k(",
+    ];
+
+    #[test]
+    fn test_round_trip() {
+        for input in CORPUS {
+            let parse = parse(input);
+            assert_eq!(
+                parse.root.text(),
+                input,
+                "tree does not reproduce {:?}",
+                input
+            );
+        }
+    }
+
+    // Deterministic pseudo-random inputs built from fragments of Monkey
+    // syntax and garbage; printing the tree must reproduce every one.
+    #[test]
+    fn test_round_trip_generated() {
+        let fragments = [
+            "let", " ", "\n", "\t", "x", "=", "5", "2.5", "0x1f", ";", "(", ")", "{", "}", ",",
+            "macro", "return", "while", "break", "for", "in", "+=", "// c\n", "//", "+", "!=",
+            "1_", "é", "\r\n",
+        ];
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        for _ in 0..500 {
+            let mut input = String::new();
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let len = (seed >> 59) as usize;
+            for _ in 0..len {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                input.push_str(fragments[(seed >> 33) as usize % fragments.len()]);
+            }
+            let parse = parse(&input);
+            assert_eq!(
+                parse.root.text(),
+                input,
+                "tree does not reproduce {:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_tree() {
+        let parse = parse("let m = macro(a) { a }; // c\n");
+        assert!(
+            parse.errors.is_empty(),
+            "unexpected errors {:?}",
+            parse.errors
+        );
+        assert_eq!(
+            parse.root.debug_tree(),
+            r#"PROGRAM
+  LET_STATEMENT
+    LET "let"
+    WHITESPACE " "
+    IDENT "m"
+    WHITESPACE " "
+    = "="
+    WHITESPACE " "
+    MACRO_LITERAL
+      MACRO "macro"
+      PARAMETER_LIST
+        ( "("
+        IDENT "a"
+        ) ")"
+      WHITESPACE " "
+      BLOCK_STATEMENT
+        { "{"
+        WHITESPACE " "
+        EXPRESSION_STATEMENT
+          IDENTIFIER
+            IDENT "a"
+        WHITESPACE " "
+        } "}"
+    ; ";"
+  WHITESPACE " "
+  COMMENT "// c"
+  WHITESPACE "\n"
+"#
+        );
+    }
+
+    #[test]
+    fn test_expression_tree() {
+        let parse = parse("-a * (b + c)");
+        assert!(
+            parse.errors.is_empty(),
+            "unexpected errors {:?}",
+            parse.errors
+        );
+        assert_eq!(
+            parse.root.debug_tree(),
+            r#"PROGRAM
+  EXPRESSION_STATEMENT
+    INFIX_EXPRESSION
+      PREFIX_EXPRESSION
+        - "-"
+        IDENTIFIER
+          IDENT "a"
+      WHITESPACE " "
+      * "*"
+      WHITESPACE " "
+      PAREN_EXPRESSION
+        ( "("
+        INFIX_EXPRESSION
+          IDENTIFIER
+            IDENT "b"
+          WHITESPACE " "
+          + "+"
+          WHITESPACE " "
+          IDENTIFIER
+            IDENT "c"
+        ) ")"
+"#
+        );
+    }
+
+    #[test]
+    fn test_nul_byte_is_kept() {
+        let input = "let x = 1;\0 let y = x; // \0\n";
+        let parse = parse(input);
+
+        assert_eq!(parse.root.text(), input);
+        let messages: Vec<String> = parse.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, vec!["1:11: illegal character '\\u{0}'"]);
+        assert_eq!(lower(&parse.root).string(), "let x = 1;let y = x;");
+    }
+
+    #[test]
+    fn test_error_nodes() {
+        let parse = parse("let x = (1; y");
+
+        let kinds: Vec<SyntaxKind> = parse.root.child_nodes().map(|n| n.kind).collect();
+        assert_eq!(kinds, vec![ERROR, EXPRESSION_STATEMENT]);
+        let error = parse.root.child_nodes().next().unwrap();
+        assert_eq!(error.text(), "let x = (1;");
+        let inner: Vec<SyntaxKind> = error.child_nodes().map(|n| n.kind).collect();
+        assert_eq!(inner, vec![ERROR]);
+
+        let messages: Vec<String> = parse.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, vec!["1:11: expected next token to be ), got ;"]);
+        assert_eq!(lower(&parse.root).statements.len(), 1);
+    }
+
+    #[test]
+    fn test_assignment_errors() {
+        let parse = parse("1 = x; (a) = 2; a + b -= 1;");
+
+        let messages: Vec<String> = parse.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec!["1:3: cannot assign to 1", "1:23: cannot assign to (a + b)"]
+        );
+    }
+
+    #[test]
+    fn test_trivia_spans() {
+        let parse = parse("x;\n  // note\ny");
+        let trivia: Vec<(SyntaxKind, Span)> = parse
+            .root
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) => Some((token.kind, token.span)),
+                SyntaxElement::Node(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            trivia,
+            vec![
+                (
+                    WHITESPACE,
+                    Span {
+                        start: 2,
+                        end: 5,
+                        line: 1,
+                        column: 3
+                    }
+                ),
+                (
+                    COMMENT,
+                    Span {
+                        start: 5,
+                        end: 12,
+                        line: 2,
+                        column: 3
+                    }
+                ),
+                (
+                    WHITESPACE,
+                    Span {
+                        start: 12,
+                        end: 13,
+                        line: 2,
+                        column: 10
+                    }
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,508 @@
+use crate::{
+    ast::ast::{
+        AssignExpression, BlockStatement, Boolean, BreakStatement, CallExpression,
+        ContinueStatement, Expression, ExpressionStatement, FloatLiteral, ForStatement,
+        FunctionLiteral, Identifier, IfExpression, InfixExpression, IntegerLiteral, LetStatement,
+        MacroLiteral, PrefixExpression, Program, ReturnStatement, Statement, TypeExpression,
+        WhileStatement,
+    },
+    parser::parser::{float_value, integer_value},
+    token::token::{
+        Token, ARROW, ASSIGN, COLON, COMMA, ELSE, FOR, FUNCTION, IDENT, IF, IN, LPAREN, RBRACE,
+        RPAREN, SEMICOLON, TRUE, WHILE,
+    },
+};
+
+use super::cst::{
+    SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken, ARGUMENT_LIST, ASSIGN_EXPRESSION,
+    BLOCK_STATEMENT, BOOLEAN_LITERAL, BREAK_STATEMENT, CALL_EXPRESSION, CONTINUE_STATEMENT,
+    EXPRESSION_STATEMENT, FLOAT_LITERAL, FOR_STATEMENT, FUNCTION_LITERAL, FUNCTION_TYPE,
+    IDENTIFIER, IF_EXPRESSION, INFIX_EXPRESSION, INTEGER_LITERAL, LET_STATEMENT, MACRO_LITERAL,
+    NAMED_TYPE, PARAMETER_LIST, PAREN_EXPRESSION, PREFIX_EXPRESSION, RETURN_STATEMENT,
+    WHILE_STATEMENT,
+};
+
+// Lowers a syntax tree to the AST. Incomplete nodes are dropped the same way
+// the parser drops statements it reports errors for, so the result matches
+// `Parser::parse_program` even for input with errors.
+pub fn lower(root: &SyntaxNode) -> Program {
+    Program {
+        statements: root.child_nodes().filter_map(lower_statement).collect(),
+    }
+}
+
+fn lower_statement(node: &SyntaxNode) -> Option<Box<dyn Statement>> {
+    match node.kind {
+        LET_STATEMENT => {
+            let tokens: Vec<&SyntaxToken> = node.child_tokens().collect();
+            let kinds: Vec<SyntaxKind> = tokens.iter().map(|t| t.kind).collect();
+            let mut nodes = node.child_nodes();
+            let r#type = match kinds.get(1..3)? {
+                [IDENT, ASSIGN] => None,
+                [IDENT, COLON] if kinds.get(3) == Some(&ASSIGN) => Some(lower_type(nodes.next()?)?),
+                _ => return None,
+            };
+            Some(Box::new(LetStatement {
+                token: to_token(tokens[0]),
+                name: Box::new(to_identifier(tokens[1])),
+                r#type,
+                value: lower_expression(nodes.next()?)?,
+            }))
+        }
+        RETURN_STATEMENT => Some(Box::new(ReturnStatement {
+            token: to_token(node.child_tokens().next()?),
+            return_value: lower_expression(node.child_nodes().next()?)?,
+        })),
+        EXPRESSION_STATEMENT => {
+            let expression = node.child_nodes().next()?;
+            Some(Box::new(ExpressionStatement {
+                token: to_token(expression.first_token()?),
+                expression: lower_expression(expression)?,
+            }))
+        }
+        BLOCK_STATEMENT => Some(Box::new(lower_block_statement(node)?)),
+        WHILE_STATEMENT => lower_while_statement(node),
+        FOR_STATEMENT => lower_for_statement(node),
+        BREAK_STATEMENT | CONTINUE_STATEMENT => {
+            if node.child_nodes().next().is_some() {
+                return None;
+            }
+            let token = to_token(node.child_tokens().next()?);
+            if node.kind == BREAK_STATEMENT {
+                Some(Box::new(BreakStatement { token }))
+            } else {
+                Some(Box::new(ContinueStatement { token }))
+            }
+        }
+        _ => None,
+    }
+}
+
+// Accepts exactly `while` `(` condition `)` block [`;`].
+fn lower_while_statement(node: &SyntaxNode) -> Option<Box<dyn Statement>> {
+    let tokens: Vec<SyntaxKind> = node.child_tokens().map(|t| t.kind).collect();
+    if !matches!(
+        tokens.as_slice(),
+        [WHILE, LPAREN, RPAREN] | [WHILE, LPAREN, RPAREN, SEMICOLON]
+    ) {
+        return None;
+    }
+    let nodes: Vec<&SyntaxNode> = node.child_nodes().collect();
+    match nodes.as_slice() {
+        [condition, body] if body.kind == BLOCK_STATEMENT => Some(Box::new(WhileStatement {
+            token: to_token(node.child_tokens().next()?),
+            condition: lower_expression(condition)?,
+            body: lower_block_statement(body)?,
+        })),
+        _ => None,
+    }
+}
+
+fn lower_expression(node: &SyntaxNode) -> Option<Box<dyn Expression>> {
+    match node.kind {
+        IDENTIFIER => Some(Box::new(to_identifier(node.child_tokens().next()?))),
+        INTEGER_LITERAL => {
+            let token = to_token(node.child_tokens().next()?);
+            let value = integer_value(&token.literal).ok()?;
+            Some(Box::new(IntegerLiteral { token, value }))
+        }
+        FLOAT_LITERAL => {
+            let token = to_token(node.child_tokens().next()?);
+            let value = float_value(&token.literal).ok()?;
+            Some(Box::new(FloatLiteral { token, value }))
+        }
+        BOOLEAN_LITERAL => {
+            let token = to_token(node.child_tokens().next()?);
+            let value = token.r#type == TRUE;
+            Some(Box::new(Boolean { token, value }))
+        }
+        PREFIX_EXPRESSION => {
+            let token = to_token(node.child_tokens().next()?);
+            Some(Box::new(PrefixExpression {
+                operator: token.literal.clone(),
+                token,
+                right: lower_expression(node.child_nodes().next()?)?,
+            }))
+        }
+        ASSIGN_EXPRESSION => {
+            let token = to_token(node.child_tokens().next()?);
+            let mut operands = node.child_nodes();
+            let target = lower_expression(operands.next()?)?;
+            let name = target.as_identifier_expression()?;
+            Some(Box::new(AssignExpression {
+                operator: token.literal.clone(),
+                token,
+                name: Identifier {
+                    token: name.token.clone(),
+                    value: name.value.clone(),
+                },
+                value: lower_expression(operands.next()?)?,
+            }))
+        }
+        INFIX_EXPRESSION => {
+            let token = to_token(node.child_tokens().next()?);
+            let mut operands = node.child_nodes();
+            Some(Box::new(InfixExpression {
+                operator: token.literal.clone(),
+                token,
+                left: lower_expression(operands.next()?)?,
+                right: lower_expression(operands.next()?)?,
+            }))
+        }
+        CALL_EXPRESSION => {
+            let mut nodes = node.child_nodes();
+            let function = lower_expression(nodes.next()?)?;
+            let arguments = nodes.next().filter(|n| n.kind == ARGUMENT_LIST)?;
+            if nodes.next().is_some() {
+                return None;
+            }
+            Some(Box::new(CallExpression {
+                token: to_token(arguments.child_tokens().next()?),
+                function,
+                arguments: lower_arguments(arguments)?,
+            }))
+        }
+        // Grouping only shapes the tree; the AST has no node for it.
+        PAREN_EXPRESSION => {
+            if node.child_tokens().last()?.kind != RPAREN {
+                return None;
+            }
+            lower_expression(node.child_nodes().next()?)
+        }
+        IF_EXPRESSION => lower_if_expression(node),
+        FUNCTION_LITERAL => {
+            let function = lower_function_like(node)?;
+            Some(Box::new(FunctionLiteral {
+                token: function.token,
+                parameters: function.parameters,
+                parameter_types: function.parameter_types,
+                return_type: function.return_type,
+                body: function.body,
+            }))
+        }
+        MACRO_LITERAL => {
+            let LoweredFunction {
+                token,
+                parameters,
+                body,
+                ..
+            } = lower_function_like(node)?;
+            Some(Box::new(MacroLiteral {
+                token,
+                parameters,
+                body,
+            }))
+        }
+        _ => None,
+    }
+}
+
+// Accepts exactly `if` `(` condition `)` block [`else` block].
+fn lower_if_expression(node: &SyntaxNode) -> Option<Box<dyn Expression>> {
+    let tokens: Vec<SyntaxKind> = node.child_tokens().map(|t| t.kind).collect();
+    let has_else = match tokens.as_slice() {
+        [IF, LPAREN, RPAREN] => false,
+        [IF, LPAREN, RPAREN, ELSE] => true,
+        _ => return None,
+    };
+
+    let nodes: Vec<&SyntaxNode> = node.child_nodes().collect();
+    if nodes.len() != 2 + has_else as usize || nodes[1..].iter().any(|n| n.kind != BLOCK_STATEMENT)
+    {
+        return None;
+    }
+    let alternative = match nodes.get(2) {
+        Some(block) => Some(lower_block_statement(block)?),
+        None => None,
+    };
+    Some(Box::new(IfExpression {
+        token: to_token(node.child_tokens().next()?),
+        condition: lower_expression(nodes[0])?,
+        consequence: lower_block_statement(nodes[1])?,
+        alternative,
+    }))
+}
+
+// Accepts exactly `for` `(` IDENT [`,` IDENT] `in` iterable `)` block [`;`].
+fn lower_for_statement(node: &SyntaxNode) -> Option<Box<dyn Statement>> {
+    let tokens: Vec<&SyntaxToken> = node.child_tokens().collect();
+    let kinds: Vec<SyntaxKind> = tokens.iter().map(|t| t.kind).collect();
+    let (key, value) = match kinds.as_slice() {
+        [FOR, LPAREN, IDENT, IN, RPAREN] | [FOR, LPAREN, IDENT, IN, RPAREN, SEMICOLON] => {
+            (None, to_identifier(tokens[2]))
+        }
+        [FOR, LPAREN, IDENT, COMMA, IDENT, IN, RPAREN]
+        | [FOR, LPAREN, IDENT, COMMA, IDENT, IN, RPAREN, SEMICOLON] => {
+            (Some(to_identifier(tokens[2])), to_identifier(tokens[4]))
+        }
+        _ => return None,
+    };
+    let nodes: Vec<&SyntaxNode> = node.child_nodes().collect();
+    match nodes.as_slice() {
+        [iterable, body] if body.kind == BLOCK_STATEMENT => Some(Box::new(ForStatement {
+            token: to_token(tokens[0]),
+            key,
+            value,
+            iterable: lower_expression(iterable)?,
+            body: lower_block_statement(body)?,
+        })),
+        _ => None,
+    }
+}
+
+// The parts of a function or macro literal. Macros never have types.
+struct LoweredFunction {
+    token: Token,
+    parameters: Vec<Identifier>,
+    parameter_types: Vec<Option<TypeExpression>>,
+    return_type: Option<TypeExpression>,
+    body: BlockStatement,
+}
+
+fn lower_function_like(node: &SyntaxNode) -> Option<LoweredFunction> {
+    let tokens: Vec<SyntaxKind> = node.child_tokens().map(|t| t.kind).collect();
+    let mut nodes = node.child_nodes();
+    let parameter_list = nodes.next().filter(|n| n.kind == PARAMETER_LIST)?;
+    let (parameters, parameter_types) = lower_parameters(parameter_list)?;
+    let return_type = match tokens.get(1) {
+        Some(&ARROW) => Some(lower_type(nodes.next()?)?),
+        Some(_) => return None,
+        None => None,
+    };
+    let body = nodes.next().filter(|n| n.kind == BLOCK_STATEMENT)?;
+    Some(LoweredFunction {
+        token: to_token(node.child_tokens().next()?),
+        parameters,
+        parameter_types,
+        return_type,
+        body: lower_block_statement(body)?,
+    })
+}
+
+// Accepts exactly `(` [parameter {`,` parameter}] `)`, where a parameter is
+// IDENT, optionally followed by `:` and a type.
+fn lower_parameters(node: &SyntaxNode) -> Option<(Vec<Identifier>, Vec<Option<TypeExpression>>)> {
+    let mut children = node
+        .children
+        .iter()
+        .filter(|child| !matches!(child, SyntaxElement::Token(t) if t.is_trivia()))
+        .peekable();
+    children.next().filter(|c| is_token(c, LPAREN))?;
+
+    let mut parameters = vec![];
+    let mut types = vec![];
+    if children.next_if(|c| is_token(c, RPAREN)).is_none() {
+        loop {
+            match children.next()? {
+                SyntaxElement::Token(t) if t.kind == IDENT => parameters.push(to_identifier(t)),
+                _ => return None,
+            }
+            if children.next_if(|c| is_token(c, COLON)).is_some() {
+                match children.next()? {
+                    SyntaxElement::Node(node) => types.push(Some(lower_type(node)?)),
+                    SyntaxElement::Token(_) => return None,
+                }
+            } else {
+                types.push(None);
+            }
+            match children.next()? {
+                c if is_token(c, COMMA) => continue,
+                c if is_token(c, RPAREN) => break,
+                _ => return None,
+            }
+        }
+    }
+
+    if children.next().is_some() {
+        return None;
+    }
+    Some((parameters, types))
+}
+
+// Accepts exactly `(` [expression {`,` expression}] `)`.
+fn lower_arguments(node: &SyntaxNode) -> Option<Vec<Box<dyn Expression>>> {
+    let mut children = node
+        .children
+        .iter()
+        .filter(|child| !matches!(child, SyntaxElement::Token(t) if t.is_trivia()))
+        .peekable();
+    children.next().filter(|c| is_token(c, LPAREN))?;
+
+    let mut arguments = vec![];
+    if children.next_if(|c| is_token(c, RPAREN)).is_none() {
+        loop {
+            match children.next()? {
+                SyntaxElement::Node(node) => arguments.push(lower_expression(node)?),
+                SyntaxElement::Token(_) => return None,
+            }
+            match children.next()? {
+                c if is_token(c, COMMA) => continue,
+                c if is_token(c, RPAREN) => break,
+                _ => return None,
+            }
+        }
+    }
+
+    if children.next().is_some() {
+        return None;
+    }
+    Some(arguments)
+}
+
+// Accepts a type name, or exactly `fn` `(` [type {`,` type}] `)` `->` type.
+fn lower_type(node: &SyntaxNode) -> Option<TypeExpression> {
+    let tokens: Vec<&SyntaxToken> = node.child_tokens().collect();
+    match node.kind {
+        NAMED_TYPE => Some(TypeExpression::Named(to_token(tokens.first()?))),
+        FUNCTION_TYPE => {
+            let mut types = node
+                .child_nodes()
+                .map(lower_type)
+                .collect::<Option<Vec<TypeExpression>>>()?;
+            let result = types.pop()?;
+            let mut expected = vec![FUNCTION, LPAREN];
+            expected.extend(std::iter::repeat_n(COMMA, types.len().saturating_sub(1)));
+            expected.extend([RPAREN, ARROW]);
+            if tokens.iter().map(|t| t.kind).ne(expected) {
+                return None;
+            }
+            Some(TypeExpression::Function {
+                token: to_token(tokens[0]),
+                parameters: types,
+                result: Box::new(result),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn is_token(element: &SyntaxElement, kind: SyntaxKind) -> bool {
+    matches!(element, SyntaxElement::Token(token) if token.kind == kind)
+}
+
+// Statements that failed to parse are dropped from the block, as the
+// parser does; a block without its `}` is dropped whole.
+fn lower_block_statement(node: &SyntaxNode) -> Option<BlockStatement> {
+    let tokens: Vec<&SyntaxToken> = node.child_tokens().collect();
+    if tokens.last()?.kind != RBRACE {
+        return None;
+    }
+    Some(BlockStatement {
+        token: to_token(tokens[0]),
+        statements: node.child_nodes().filter_map(lower_statement).collect(),
+    })
+}
+
+fn to_token(token: &SyntaxToken) -> Token {
+    Token {
+        r#type: token.kind,
+        literal: token.text.clone(),
+        span: token.span,
+    }
+}
+
+fn to_identifier(token: &SyntaxToken) -> Identifier {
+    Identifier {
+        token: to_token(token),
+        value: token.text.clone(),
+    }
+}
+
+#[cfg(test)]
+mod lower_tests {
+    use crate::{ast::ast::Node, cst::cst::parse, lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    #[test]
+    fn test_lower_matches_parser() {
+        let tests = [
+            "let x = 5;",
+            "let x = 0x10; return 2.5; foo",
+            "// comment\nlet m = macro(a, b) { a; // inner\n b; };\nm;",
+            "let e = macro() {};",
+            "let add = fn(a, b) { a; b; }; add;",
+            "let z = x + y;",
+            ";;x;;",
+            "-a * (b + c) == !d / 2 < e;",
+            "if (x > 1) { true } else { if (!y) { false } };",
+            "let f = fn(n) { if ((n)) { return -n; }; n - 1 };",
+            "let g = fn(n) { let m = n; return m }; g",
+            "let f: fn(int, fn() -> bool) -> int = fn(a: int, b) -> int { a };",
+            "let k = fn(a: float) { a }; let m = macro(a) { a };",
+            "while (x < 3) { if (x) { break; }; continue };",
+            "while (a) { while (b) { break } }; c",
+            "for (x in xs) { continue }; for (k, v in h + 1) { k; v };",
+            "a = b += (c) -= 1 * 2; if ((d = 1)) { (d) /= 2 }",
+            "f(); g(a, (b), 1 + 2)(h(x)); fn(x) { x }(1) * -k(2)",
+        ];
+
+        for input in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let expected = parser.parse_program();
+            assert!(parser.errors().is_empty(), "{:?} should parse", input);
+
+            let parse = parse(input);
+            let program = lower(&parse.root);
+            assert_eq!(
+                program.string(),
+                expected.string(),
+                "lowering {:?} does not match the parser",
+                input
+            );
+            assert_eq!(program.statements.len(), expected.statements.len());
+        }
+    }
+
+    // The language server lowers documents that do not parse, so lowering
+    // has to drop the same statements the parser drops.
+    #[test]
+    fn test_lower_matches_parser_after_errors() {
+        let tests = [
+            "let x = ; let y = 1; y",
+            "let f = fn() { 1 * }; f;",
+            "let x = (1 + ) * 2; let y = x;",
+            "x += ; 1 = 2; z",
+            "let a = 1; a[0] = 2;",
+            "while (x) { let = 1; x }; }; let b = 2;",
+            "if (x) { y } else",
+            "f(1, ); g(a b); h(; let c = 3;",
+        ];
+
+        for input in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let expected = parser.parse_program();
+            assert!(!parser.errors().is_empty(), "{:?} should not parse", input);
+
+            let program = lower(&parse(input).root);
+            assert_eq!(
+                program.string(),
+                expected.string(),
+                "lowering {:?} does not match the parser",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_lower_keeps_spans() {
+        let parse = parse("\n  let answer = 42;");
+        let program = lower(&parse.root);
+
+        let let_stmt = program.statements[0].as_let_statement().unwrap();
+        assert_eq!(let_stmt.token.span.line, 2);
+        assert_eq!(let_stmt.token.span.column, 3);
+        assert_eq!(let_stmt.name.token.span.start, 7);
+        assert_eq!(let_stmt.value.as_integer_literal().unwrap().value, 42);
+    }
+
+    #[test]
+    fn test_lower_drops_incomplete_nodes() {
+        let parse = parse(
+            "let = 5; let m = macro(a,) { a }; return; let t: = 1; let f = fn(a: fn(int) { a }; while (y); while () {}; for (k, in) {}; for (x in y); 1 = 2; x;",
+        );
+        let program = lower(&parse.root);
+
+        assert_eq!(program.string(), "x");
+    }
+}
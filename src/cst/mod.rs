@@ -0,0 +1,2 @@
+pub mod cst;
+pub mod lower;
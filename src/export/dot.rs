@@ -0,0 +1,416 @@
+use crate::ast::ast::{
+    BlockStatement, Expression, Identifier, Node, Program, Statement, TypeExpression,
+};
+
+// Renders the program as a Graphviz digraph, e.g. for `dot -Tsvg`. Each AST
+// node becomes a box labelled with its kind and, for leaves, its value;
+// edges are labelled with the field that holds the child.
+pub fn program_to_dot(program: &Program) -> String {
+    let mut graph = Graph {
+        lines: vec![],
+        next_id: 0,
+    };
+    let root = graph.node("Program");
+    for (i, statement) in program.statements.iter().enumerate() {
+        let child = graph.statement(statement.as_ref());
+        graph.edge(&root, &child, &format!("statements[{}]", i));
+    }
+
+    let mut out = String::from("digraph ast {\n    node [shape=box];\n");
+    for line in graph.lines {
+        out.push_str("    ");
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out.push_str("}\n");
+    out
+}
+
+struct Graph {
+    lines: Vec<String>,
+    next_id: usize,
+}
+
+impl Graph {
+    fn node(&mut self, label: &str) -> String {
+        let id = format!("n{}", self.next_id);
+        self.next_id += 1;
+        self.lines
+            .push(format!("{} [label=\"{}\"];", id, escape(label)));
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &str) {
+        self.lines
+            .push(format!("{} -> {} [label=\"{}\"];", from, to, escape(label)));
+    }
+
+    fn statement(&mut self, statement: &dyn Statement) -> String {
+        if let Some(stmt) = statement.as_let_statement() {
+            let id = self.node("LetStatement");
+            let name = self.identifier(&stmt.name);
+            self.edge(&id, &name, "name");
+            if let Some(r#type) = &stmt.r#type {
+                let r#type = self.type_expression(r#type);
+                self.edge(&id, &r#type, "type");
+            }
+            let value = self.expression(stmt.value.as_ref());
+            self.edge(&id, &value, "value");
+            id
+        } else if let Some(stmt) = statement.as_return_statement() {
+            let id = self.node("ReturnStatement");
+            let value = self.expression(stmt.return_value.as_ref());
+            self.edge(&id, &value, "return_value");
+            id
+        } else if let Some(stmt) = statement.as_expression_statement() {
+            let id = self.node("ExpressionStatement");
+            let expression = self.expression(stmt.expression.as_ref());
+            self.edge(&id, &expression, "expression");
+            id
+        } else if let Some(block) = statement.as_block_statement() {
+            self.block(block)
+        } else if let Some(stmt) = statement.as_while_statement() {
+            let id = self.node("WhileStatement");
+            let condition = self.expression(stmt.condition.as_ref());
+            self.edge(&id, &condition, "condition");
+            let body = self.block(&stmt.body);
+            self.edge(&id, &body, "body");
+            id
+        } else if let Some(stmt) = statement.as_for_statement() {
+            let id = self.node("ForStatement");
+            if let Some(key) = &stmt.key {
+                let key = self.identifier(key);
+                self.edge(&id, &key, "key");
+            }
+            let value = self.identifier(&stmt.value);
+            self.edge(&id, &value, "value");
+            let iterable = self.expression(stmt.iterable.as_ref());
+            self.edge(&id, &iterable, "iterable");
+            let body = self.block(&stmt.body);
+            self.edge(&id, &body, "body");
+            id
+        } else if statement.as_break_statement().is_some() {
+            self.node("BreakStatement")
+        } else if statement.as_continue_statement().is_some() {
+            self.node("ContinueStatement")
+        } else {
+            self.node(&statement.string())
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) -> String {
+        let id = self.node("BlockStatement");
+        for (i, statement) in block.statements.iter().enumerate() {
+            let child = self.statement(statement.as_ref());
+            self.edge(&id, &child, &format!("statements[{}]", i));
+        }
+        id
+    }
+
+    fn expression(&mut self, expression: &dyn Expression) -> String {
+        if let Some(identifier) = expression.as_identifier_expression() {
+            self.identifier(identifier)
+        } else if let Some(literal) = expression.as_integer_literal() {
+            self.node(&format!("IntegerLiteral\n{}", literal.token.literal))
+        } else if let Some(literal) = expression.as_float_literal() {
+            self.node(&format!("FloatLiteral\n{}", literal.token.literal))
+        } else if let Some(boolean) = expression.as_boolean() {
+            self.node(&format!("Boolean\n{}", boolean.value))
+        } else if let Some(expr) = expression.as_prefix_expression() {
+            let id = self.node(&format!("PrefixExpression\n{}", expr.operator));
+            let right = self.expression(expr.right.as_ref());
+            self.edge(&id, &right, "right");
+            id
+        } else if let Some(expr) = expression.as_infix_expression() {
+            let id = self.node(&format!("InfixExpression\n{}", expr.operator));
+            let left = self.expression(expr.left.as_ref());
+            self.edge(&id, &left, "left");
+            let right = self.expression(expr.right.as_ref());
+            self.edge(&id, &right, "right");
+            id
+        } else if let Some(expr) = expression.as_assign_expression() {
+            let id = self.node(&format!("AssignExpression\n{}", expr.operator));
+            let name = self.identifier(&expr.name);
+            self.edge(&id, &name, "name");
+            let value = self.expression(expr.value.as_ref());
+            self.edge(&id, &value, "value");
+            id
+        } else if let Some(expr) = expression.as_call_expression() {
+            let id = self.node("CallExpression");
+            let function = self.expression(expr.function.as_ref());
+            self.edge(&id, &function, "function");
+            for (i, argument) in expr.arguments.iter().enumerate() {
+                let argument = self.expression(argument.as_ref());
+                self.edge(&id, &argument, &format!("arguments[{}]", i));
+            }
+            id
+        } else if let Some(expr) = expression.as_if_expression() {
+            let id = self.node("IfExpression");
+            let condition = self.expression(expr.condition.as_ref());
+            self.edge(&id, &condition, "condition");
+            let consequence = self.block(&expr.consequence);
+            self.edge(&id, &consequence, "consequence");
+            if let Some(alternative) = &expr.alternative {
+                let alternative = self.block(alternative);
+                self.edge(&id, &alternative, "alternative");
+            }
+            id
+        } else if let Some(literal) = expression.as_function_literal() {
+            self.function_like(
+                "FunctionLiteral",
+                &literal.parameters,
+                &literal.parameter_types,
+                literal.return_type.as_ref(),
+                &literal.body,
+            )
+        } else if let Some(literal) = expression.as_macro_literal() {
+            self.function_like(
+                "MacroLiteral",
+                &literal.parameters,
+                &[],
+                None,
+                &literal.body,
+            )
+        } else {
+            self.node(&expression.string())
+        }
+    }
+
+    fn function_like(
+        &mut self,
+        label: &str,
+        parameters: &[Identifier],
+        parameter_types: &[Option<TypeExpression>],
+        return_type: Option<&TypeExpression>,
+        body: &BlockStatement,
+    ) -> String {
+        let id = self.node(label);
+        for (i, parameter) in parameters.iter().enumerate() {
+            let child = self.identifier(parameter);
+            self.edge(&id, &child, &format!("parameters[{}]", i));
+            if let Some(Some(r#type)) = parameter_types.get(i) {
+                let child = self.type_expression(r#type);
+                self.edge(&id, &child, &format!("parameter_types[{}]", i));
+            }
+        }
+        if let Some(r#type) = return_type {
+            let child = self.type_expression(r#type);
+            self.edge(&id, &child, "return_type");
+        }
+        let body = self.block(body);
+        self.edge(&id, &body, "body");
+        id
+    }
+
+    fn identifier(&mut self, identifier: &Identifier) -> String {
+        self.node(&format!("Identifier\n{}", identifier.value))
+    }
+
+    // Types are leaves labelled with their source form.
+    fn type_expression(&mut self, r#type: &TypeExpression) -> String {
+        let kind = match r#type {
+            TypeExpression::Named(_) => "NamedType",
+            TypeExpression::Function { .. } => "FunctionType",
+        };
+        self.node(&format!("{}\n{}", kind, r#type.string()))
+    }
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod dot_tests {
+    use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    #[test]
+    fn test_program_to_dot() {
+        let input = "let m = macro(a) { return 0x1; }; 2.5;";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program_to_dot(&program),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="Program"];
+    n1 [label="LetStatement"];
+    n2 [label="Identifier\nm"];
+    n1 -> n2 [label="name"];
+    n3 [label="MacroLiteral"];
+    n4 [label="Identifier\na"];
+    n3 -> n4 [label="parameters[0]"];
+    n5 [label="BlockStatement"];
+    n6 [label="ReturnStatement"];
+    n7 [label="IntegerLiteral\n0x1"];
+    n6 -> n7 [label="return_value"];
+    n5 -> n6 [label="statements[0]"];
+    n3 -> n5 [label="body"];
+    n1 -> n3 [label="value"];
+    n0 -> n1 [label="statements[0]"];
+    n8 [label="ExpressionStatement"];
+    n9 [label="FloatLiteral\n2.5"];
+    n8 -> n9 [label="expression"];
+    n0 -> n8 [label="statements[1]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_expressions_to_dot() {
+        let input = "if (!a) { 1 + b } else { false }";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program_to_dot(&program),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="Program"];
+    n1 [label="ExpressionStatement"];
+    n2 [label="IfExpression"];
+    n3 [label="PrefixExpression\n!"];
+    n4 [label="Identifier\na"];
+    n3 -> n4 [label="right"];
+    n2 -> n3 [label="condition"];
+    n5 [label="BlockStatement"];
+    n6 [label="ExpressionStatement"];
+    n7 [label="InfixExpression\n+"];
+    n8 [label="IntegerLiteral\n1"];
+    n7 -> n8 [label="left"];
+    n9 [label="Identifier\nb"];
+    n7 -> n9 [label="right"];
+    n6 -> n7 [label="expression"];
+    n5 -> n6 [label="statements[0]"];
+    n2 -> n5 [label="consequence"];
+    n10 [label="BlockStatement"];
+    n11 [label="ExpressionStatement"];
+    n12 [label="Boolean\nfalse"];
+    n11 -> n12 [label="expression"];
+    n10 -> n11 [label="statements[0]"];
+    n2 -> n10 [label="alternative"];
+    n1 -> n2 [label="expression"];
+    n0 -> n1 [label="statements[0]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_annotations_to_dot() {
+        let input = "let f: fn(int) -> int = fn(a: int, b) -> int { a };";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program_to_dot(&program),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="Program"];
+    n1 [label="LetStatement"];
+    n2 [label="Identifier\nf"];
+    n1 -> n2 [label="name"];
+    n3 [label="FunctionType\nfn(int) -> int"];
+    n1 -> n3 [label="type"];
+    n4 [label="FunctionLiteral"];
+    n5 [label="Identifier\na"];
+    n4 -> n5 [label="parameters[0]"];
+    n6 [label="NamedType\nint"];
+    n4 -> n6 [label="parameter_types[0]"];
+    n7 [label="Identifier\nb"];
+    n4 -> n7 [label="parameters[1]"];
+    n8 [label="NamedType\nint"];
+    n4 -> n8 [label="return_type"];
+    n9 [label="BlockStatement"];
+    n10 [label="ExpressionStatement"];
+    n11 [label="Identifier\na"];
+    n10 -> n11 [label="expression"];
+    n9 -> n10 [label="statements[0]"];
+    n4 -> n9 [label="body"];
+    n1 -> n4 [label="value"];
+    n0 -> n1 [label="statements[0]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_loops_to_dot() {
+        let input = "while (a) { break; continue; } for (x in b) { x -= 1 }";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program_to_dot(&program),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="Program"];
+    n1 [label="WhileStatement"];
+    n2 [label="Identifier\na"];
+    n1 -> n2 [label="condition"];
+    n3 [label="BlockStatement"];
+    n4 [label="BreakStatement"];
+    n3 -> n4 [label="statements[0]"];
+    n5 [label="ContinueStatement"];
+    n3 -> n5 [label="statements[1]"];
+    n1 -> n3 [label="body"];
+    n0 -> n1 [label="statements[0]"];
+    n6 [label="ForStatement"];
+    n7 [label="Identifier\nx"];
+    n6 -> n7 [label="value"];
+    n8 [label="Identifier\nb"];
+    n6 -> n8 [label="iterable"];
+    n9 [label="BlockStatement"];
+    n10 [label="ExpressionStatement"];
+    n11 [label="AssignExpression\n-="];
+    n12 [label="Identifier\nx"];
+    n11 -> n12 [label="name"];
+    n13 [label="IntegerLiteral\n1"];
+    n11 -> n13 [label="value"];
+    n10 -> n11 [label="expression"];
+    n9 -> n10 [label="statements[0]"];
+    n6 -> n9 [label="body"];
+    n0 -> n6 [label="statements[1]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_call_to_dot() {
+        let input = "f(a, 1)";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program_to_dot(&program),
+            r#"digraph ast {
+    node [shape=box];
+    n0 [label="Program"];
+    n1 [label="ExpressionStatement"];
+    n2 [label="CallExpression"];
+    n3 [label="Identifier\nf"];
+    n2 -> n3 [label="function"];
+    n4 [label="Identifier\na"];
+    n2 -> n4 [label="arguments[0]"];
+    n5 [label="IntegerLiteral\n1"];
+    n2 -> n5 [label="arguments[1]"];
+    n1 -> n2 [label="expression"];
+    n0 -> n1 [label="statements[0]"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+}
@@ -0,0 +1,500 @@
+use crate::{
+    ast::ast::{BlockStatement, Expression, Identifier, Program, Statement, TypeExpression},
+    json::json::Json,
+    lexer::lexer::Lexer,
+    parser::parser::{ParseError, Parser},
+    token::token::{Span, Token, EOF},
+};
+
+// Bumped whenever the shape of the exported documents changes.
+//
+//   1: tokens, let/return/expression statements, blocks, identifiers,
+//      integer, float and macro literals.
+//   2: NamedType and FunctionType annotations.
+//   3: WhileStatement, BreakStatement and ContinueStatement.
+//   4: ForStatement.
+//   5: AssignExpression.
+//   6: no new kinds; marks FunctionLiteral, Boolean, PrefixExpression,
+//      InfixExpression and IfExpression, exported since 1 without a bump.
+//   7: CallExpression.
+pub const SCHEMA_VERSION: i64 = 7;
+
+// The token stream of `source`, ending with the EOF token:
+//
+//   {"version": 7,
+//    "tokens": [{"type", "literal", "span": {"start", "end", "line", "column"}}],
+//    "errors": [{"message", "span"}]}
+pub fn tokens_to_json(source: &str) -> Json {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = vec![];
+    loop {
+        let tok = lexer.next_token();
+        let is_eof = tok.r#type == EOF;
+        tokens.push(token_json(&tok));
+        if is_eof {
+            break;
+        }
+    }
+    let errors: Vec<ParseError> = lexer
+        .take_errors()
+        .into_iter()
+        .map(|e| ParseError {
+            message: e.message,
+            span: e.span,
+            fix: None,
+        })
+        .collect();
+
+    Json::object(vec![
+        ("version", Json::Int(SCHEMA_VERSION)),
+        ("tokens", Json::Array(tokens)),
+        ("errors", errors_json(&errors)),
+    ])
+}
+
+// The parsed program of `source`:
+//
+//   {"version": 7, "program": {"kind": "Program", "statements": [...]},
+//    "errors": [{"message", "span"}]}
+//
+// Every other node is an object with a "kind" naming its AST type, a "span"
+// of the token that starts it (the operator, for infix and assignment
+// expressions), and one field per AST field.
+pub fn ast_to_json(source: &str) -> Json {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    let program = parser.parse_program();
+
+    Json::object(vec![
+        ("version", Json::Int(SCHEMA_VERSION)),
+        ("program", program_json(&program)),
+        ("errors", errors_json(parser.errors())),
+    ])
+}
+
+pub fn program_json(program: &Program) -> Json {
+    Json::object(vec![
+        ("kind", Json::string("Program")),
+        (
+            "statements",
+            Json::Array(
+                program
+                    .statements
+                    .iter()
+                    .map(|s| statement_json(s.as_ref()))
+                    .collect(),
+            ),
+        ),
+    ])
+}
+
+fn statement_json(statement: &dyn Statement) -> Json {
+    if let Some(stmt) = statement.as_let_statement() {
+        node("LetStatement", &stmt.token)
+            .field("name", identifier_json(&stmt.name))
+            .field("type", optional_type_json(stmt.r#type.as_ref()))
+            .field("value", expression_json(stmt.value.as_ref()))
+            .build()
+    } else if let Some(stmt) = statement.as_return_statement() {
+        node("ReturnStatement", &stmt.token)
+            .field("return_value", expression_json(stmt.return_value.as_ref()))
+            .build()
+    } else if let Some(stmt) = statement.as_expression_statement() {
+        node("ExpressionStatement", &stmt.token)
+            .field("expression", expression_json(stmt.expression.as_ref()))
+            .build()
+    } else if let Some(block) = statement.as_block_statement() {
+        block_json(block)
+    } else if let Some(stmt) = statement.as_while_statement() {
+        node("WhileStatement", &stmt.token)
+            .field("condition", expression_json(stmt.condition.as_ref()))
+            .field("body", block_json(&stmt.body))
+            .build()
+    } else if let Some(stmt) = statement.as_for_statement() {
+        let key = match &stmt.key {
+            Some(key) => identifier_json(key),
+            None => Json::Null,
+        };
+        node("ForStatement", &stmt.token)
+            .field("key", key)
+            .field("value", identifier_json(&stmt.value))
+            .field("iterable", expression_json(stmt.iterable.as_ref()))
+            .field("body", block_json(&stmt.body))
+            .build()
+    } else if let Some(stmt) = statement.as_break_statement() {
+        node("BreakStatement", &stmt.token).build()
+    } else if let Some(stmt) = statement.as_continue_statement() {
+        node("ContinueStatement", &stmt.token).build()
+    } else {
+        Json::Null
+    }
+}
+
+fn block_json(block: &BlockStatement) -> Json {
+    node("BlockStatement", &block.token)
+        .field(
+            "statements",
+            Json::Array(
+                block
+                    .statements
+                    .iter()
+                    .map(|s| statement_json(s.as_ref()))
+                    .collect(),
+            ),
+        )
+        .build()
+}
+
+fn expression_json(expression: &dyn Expression) -> Json {
+    if let Some(identifier) = expression.as_identifier_expression() {
+        identifier_json(identifier)
+    } else if let Some(literal) = expression.as_integer_literal() {
+        node("IntegerLiteral", &literal.token)
+            .field("literal", Json::string(&literal.token.literal))
+            .field("value", Json::Int(literal.value))
+            .build()
+    } else if let Some(literal) = expression.as_float_literal() {
+        node("FloatLiteral", &literal.token)
+            .field("literal", Json::string(&literal.token.literal))
+            .field("value", Json::Float(literal.value))
+            .build()
+    } else if let Some(boolean) = expression.as_boolean() {
+        node("Boolean", &boolean.token)
+            .field("value", Json::Bool(boolean.value))
+            .build()
+    } else if let Some(expr) = expression.as_prefix_expression() {
+        node("PrefixExpression", &expr.token)
+            .field("operator", Json::string(&expr.operator))
+            .field("right", expression_json(expr.right.as_ref()))
+            .build()
+    } else if let Some(expr) = expression.as_infix_expression() {
+        node("InfixExpression", &expr.token)
+            .field("left", expression_json(expr.left.as_ref()))
+            .field("operator", Json::string(&expr.operator))
+            .field("right", expression_json(expr.right.as_ref()))
+            .build()
+    } else if let Some(expr) = expression.as_assign_expression() {
+        node("AssignExpression", &expr.token)
+            .field("name", identifier_json(&expr.name))
+            .field("operator", Json::string(&expr.operator))
+            .field("value", expression_json(expr.value.as_ref()))
+            .build()
+    } else if let Some(expr) = expression.as_call_expression() {
+        node("CallExpression", &expr.token)
+            .field("function", expression_json(expr.function.as_ref()))
+            .field(
+                "arguments",
+                Json::Array(
+                    expr.arguments
+                        .iter()
+                        .map(|a| expression_json(a.as_ref()))
+                        .collect(),
+                ),
+            )
+            .build()
+    } else if let Some(expr) = expression.as_if_expression() {
+        node("IfExpression", &expr.token)
+            .field("condition", expression_json(expr.condition.as_ref()))
+            .field("consequence", block_json(&expr.consequence))
+            .field(
+                "alternative",
+                expr.alternative.as_ref().map_or(Json::Null, block_json),
+            )
+            .build()
+    } else if let Some(literal) = expression.as_function_literal() {
+        node("FunctionLiteral", &literal.token)
+            .field(
+                "parameters",
+                Json::Array(literal.parameters.iter().map(identifier_json).collect()),
+            )
+            .field(
+                "parameter_types",
+                Json::Array(
+                    literal
+                        .parameter_types
+                        .iter()
+                        .map(|t| optional_type_json(t.as_ref()))
+                        .collect(),
+                ),
+            )
+            .field(
+                "return_type",
+                optional_type_json(literal.return_type.as_ref()),
+            )
+            .field("body", block_json(&literal.body))
+            .build()
+    } else if let Some(literal) = expression.as_macro_literal() {
+        node("MacroLiteral", &literal.token)
+            .field(
+                "parameters",
+                Json::Array(literal.parameters.iter().map(identifier_json).collect()),
+            )
+            .field("body", block_json(&literal.body))
+            .build()
+    } else {
+        Json::Null
+    }
+}
+
+fn identifier_json(identifier: &Identifier) -> Json {
+    node("Identifier", &identifier.token)
+        .field("value", Json::string(&identifier.value))
+        .build()
+}
+
+fn type_json(r#type: &TypeExpression) -> Json {
+    match r#type {
+        TypeExpression::Named(token) => node("NamedType", token)
+            .field("name", Json::string(&token.literal))
+            .build(),
+        TypeExpression::Function {
+            token,
+            parameters,
+            result,
+        } => node("FunctionType", token)
+            .field(
+                "parameters",
+                Json::Array(parameters.iter().map(type_json).collect()),
+            )
+            .field("result", type_json(result))
+            .build(),
+    }
+}
+
+// Missing annotations are exported as null.
+fn optional_type_json(r#type: Option<&TypeExpression>) -> Json {
+    r#type.map_or(Json::Null, type_json)
+}
+
+struct NodeBuilder {
+    fields: Vec<(String, Json)>,
+}
+
+fn node(kind: &str, token: &Token) -> NodeBuilder {
+    NodeBuilder {
+        fields: vec![
+            ("kind".to_string(), Json::string(kind)),
+            ("span".to_string(), span_json(&token.span)),
+        ],
+    }
+}
+
+impl NodeBuilder {
+    fn field(mut self, key: &str, value: Json) -> Self {
+        self.fields.push((key.to_string(), value));
+        self
+    }
+
+    fn build(self) -> Json {
+        Json::Object(self.fields)
+    }
+}
+
+fn token_json(token: &Token) -> Json {
+    Json::object(vec![
+        ("type", Json::string(token.r#type)),
+        ("literal", Json::string(&token.literal)),
+        ("span", span_json(&token.span)),
+    ])
+}
+
+fn span_json(span: &Span) -> Json {
+    Json::object(vec![
+        ("start", Json::Int(span.start as i64)),
+        ("end", Json::Int(span.end as i64)),
+        ("line", Json::Int(span.line as i64)),
+        ("column", Json::Int(span.column as i64)),
+    ])
+}
+
+fn errors_json(errors: &[ParseError]) -> Json {
+    Json::Array(
+        errors
+            .iter()
+            .map(|e| {
+                Json::object(vec![
+                    ("message", Json::string(&e.message)),
+                    ("span", span_json(&e.span)),
+                ])
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod json_export_tests {
+    use super::*;
+
+    // Snapshot of the token schema. Changing it means bumping SCHEMA_VERSION.
+    #[test]
+    fn test_tokens_schema() {
+        assert_eq!(
+            tokens_to_json("let x = 0xZZ;").to_string(),
+            concat!(
+                r#"{"version":7,"tokens":["#,
+                r#"{"type":"LET","literal":"let","span":{"start":0,"end":3,"line":1,"column":1}},"#,
+                r#"{"type":"IDENT","literal":"x","span":{"start":4,"end":5,"line":1,"column":5}},"#,
+                r#"{"type":"=","literal":"=","span":{"start":6,"end":7,"line":1,"column":7}},"#,
+                r#"{"type":"ILLEGAL","literal":"0xZZ","span":{"start":8,"end":12,"line":1,"column":9}},"#,
+                r#"{"type":";","literal":";","span":{"start":12,"end":13,"line":1,"column":13}},"#,
+                r#"{"type":"EOF","literal":"","span":{"start":13,"end":13,"line":1,"column":14}}"#,
+                r#"],"errors":["#,
+                r#"{"message":"invalid digit 'Z' in hexadecimal literal 0xZZ","span":{"start":8,"end":12,"line":1,"column":9}}"#,
+                r#"]}"#
+            )
+        );
+    }
+
+    // Snapshot of the AST schema. Changing it means bumping SCHEMA_VERSION.
+    #[test]
+    fn test_ast_schema() {
+        let input = "let m = macro(a) { return 0x1; };\n2.5;";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"LetStatement","span":{"start":0,"end":3,"line":1,"column":1},"#,
+                r#""name":{"kind":"Identifier","span":{"start":4,"end":5,"line":1,"column":5},"value":"m"},"type":null,"#,
+                r#""value":{"kind":"MacroLiteral","span":{"start":8,"end":13,"line":1,"column":9},"#,
+                r#""parameters":[{"kind":"Identifier","span":{"start":14,"end":15,"line":1,"column":15},"value":"a"}],"#,
+                r#""body":{"kind":"BlockStatement","span":{"start":17,"end":18,"line":1,"column":18},"statements":["#,
+                r#"{"kind":"ReturnStatement","span":{"start":19,"end":25,"line":1,"column":20},"#,
+                r#""return_value":{"kind":"IntegerLiteral","span":{"start":26,"end":29,"line":1,"column":27},"literal":"0x1","value":1}}"#,
+                r#"]}}},"#,
+                r#"{"kind":"ExpressionStatement","span":{"start":34,"end":37,"line":2,"column":1},"#,
+                r#""expression":{"kind":"FloatLiteral","span":{"start":34,"end":37,"line":2,"column":1},"literal":"2.5","value":2.5}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_expressions() {
+        let input = "if (-a == b) { true }";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"ExpressionStatement","span":{"start":0,"end":2,"line":1,"column":1},"#,
+                r#""expression":{"kind":"IfExpression","span":{"start":0,"end":2,"line":1,"column":1},"#,
+                r#""condition":{"kind":"InfixExpression","span":{"start":7,"end":9,"line":1,"column":8},"#,
+                r#""left":{"kind":"PrefixExpression","span":{"start":4,"end":5,"line":1,"column":5},"operator":"-","#,
+                r#""right":{"kind":"Identifier","span":{"start":5,"end":6,"line":1,"column":6},"value":"a"}},"#,
+                r#""operator":"==","#,
+                r#""right":{"kind":"Identifier","span":{"start":10,"end":11,"line":1,"column":11},"value":"b"}},"#,
+                r#""consequence":{"kind":"BlockStatement","span":{"start":13,"end":14,"line":1,"column":14},"statements":["#,
+                r#"{"kind":"ExpressionStatement","span":{"start":15,"end":19,"line":1,"column":16},"#,
+                r#""expression":{"kind":"Boolean","span":{"start":15,"end":19,"line":1,"column":16},"value":true}}"#,
+                r#"]},"alternative":null}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_annotations() {
+        let input = "let f: fn(int) -> bool = fn(a: int, b) -> bool { true };";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"LetStatement","span":{"start":0,"end":3,"line":1,"column":1},"#,
+                r#""name":{"kind":"Identifier","span":{"start":4,"end":5,"line":1,"column":5},"value":"f"},"#,
+                r#""type":{"kind":"FunctionType","span":{"start":7,"end":9,"line":1,"column":8},"#,
+                r#""parameters":[{"kind":"NamedType","span":{"start":10,"end":13,"line":1,"column":11},"name":"int"}],"#,
+                r#""result":{"kind":"NamedType","span":{"start":18,"end":22,"line":1,"column":19},"name":"bool"}},"#,
+                r#""value":{"kind":"FunctionLiteral","span":{"start":25,"end":27,"line":1,"column":26},"#,
+                r#""parameters":[{"kind":"Identifier","span":{"start":28,"end":29,"line":1,"column":29},"value":"a"},"#,
+                r#"{"kind":"Identifier","span":{"start":36,"end":37,"line":1,"column":37},"value":"b"}],"#,
+                r#""parameter_types":[{"kind":"NamedType","span":{"start":31,"end":34,"line":1,"column":32},"name":"int"},null],"#,
+                r#""return_type":{"kind":"NamedType","span":{"start":42,"end":46,"line":1,"column":43},"name":"bool"},"#,
+                r#""body":{"kind":"BlockStatement","span":{"start":47,"end":48,"line":1,"column":48},"statements":["#,
+                r#"{"kind":"ExpressionStatement","span":{"start":49,"end":53,"line":1,"column":50},"#,
+                r#""expression":{"kind":"Boolean","span":{"start":49,"end":53,"line":1,"column":50},"value":true}}"#,
+                r#"]}}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_loops() {
+        let input = "while (x) { break; continue }";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"WhileStatement","span":{"start":0,"end":5,"line":1,"column":1},"#,
+                r#""condition":{"kind":"Identifier","span":{"start":7,"end":8,"line":1,"column":8},"value":"x"},"#,
+                r#""body":{"kind":"BlockStatement","span":{"start":10,"end":11,"line":1,"column":11},"statements":["#,
+                r#"{"kind":"BreakStatement","span":{"start":12,"end":17,"line":1,"column":13}},"#,
+                r#"{"kind":"ContinueStatement","span":{"start":19,"end":27,"line":1,"column":20}}"#,
+                r#"]}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_for_statement() {
+        let input = "for (k, v in h) {}";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"ForStatement","span":{"start":0,"end":3,"line":1,"column":1},"#,
+                r#""key":{"kind":"Identifier","span":{"start":5,"end":6,"line":1,"column":6},"value":"k"},"#,
+                r#""value":{"kind":"Identifier","span":{"start":8,"end":9,"line":1,"column":9},"value":"v"},"#,
+                r#""iterable":{"kind":"Identifier","span":{"start":13,"end":14,"line":1,"column":14},"value":"h"},"#,
+                r#""body":{"kind":"BlockStatement","span":{"start":16,"end":17,"line":1,"column":17},"statements":[]}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_assignment() {
+        let input = "x += 1";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"ExpressionStatement","span":{"start":0,"end":1,"line":1,"column":1},"#,
+                r#""expression":{"kind":"AssignExpression","span":{"start":2,"end":4,"line":1,"column":3},"#,
+                r#""name":{"kind":"Identifier","span":{"start":0,"end":1,"line":1,"column":1},"value":"x"},"#,
+                r#""operator":"+=","#,
+                r#""value":{"kind":"IntegerLiteral","span":{"start":5,"end":6,"line":1,"column":6},"literal":"1","value":1}}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_call() {
+        let input = "f(a, 2)";
+        assert_eq!(
+            ast_to_json(input).to_string(),
+            concat!(
+                r#"{"version":7,"program":{"kind":"Program","statements":["#,
+                r#"{"kind":"ExpressionStatement","span":{"start":0,"end":1,"line":1,"column":1},"#,
+                r#""expression":{"kind":"CallExpression","span":{"start":1,"end":2,"line":1,"column":2},"#,
+                r#""function":{"kind":"Identifier","span":{"start":0,"end":1,"line":1,"column":1},"value":"f"},"#,
+                r#""arguments":[{"kind":"Identifier","span":{"start":2,"end":3,"line":1,"column":3},"value":"a"},"#,
+                r#"{"kind":"IntegerLiteral","span":{"start":5,"end":6,"line":1,"column":6},"literal":"2","value":2}]}}"#,
+                r#"]},"errors":[]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_ast_errors() {
+        let json = ast_to_json("let = 1;");
+        let Json::Object(fields) = json else {
+            panic!("document is not an object");
+        };
+        assert_eq!(fields[2].0, "errors");
+        assert_eq!(
+            fields[2].1.to_string(),
+            r#"[{"message":"expected next token to be IDENT, got =","span":{"start":4,"end":5,"line":1,"column":5}}]"#
+        );
+    }
+}
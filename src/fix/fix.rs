@@ -0,0 +1,105 @@
+// Replaces the characters `start..end` of the source with `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn insert(offset: u32, text: &str) -> Self {
+        TextEdit {
+            start: offset,
+            end: offset,
+            text: text.to_string(),
+        }
+    }
+
+    pub fn delete(start: u32, end: u32) -> Self {
+        TextEdit {
+            start,
+            end,
+            text: String::new(),
+        }
+    }
+
+    pub fn replace(start: u32, end: u32, text: &str) -> Self {
+        TextEdit {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    // Two insertions at the same offset overlap too: their order would be
+    // arbitrary.
+    fn overlaps(&self, other: &TextEdit) -> bool {
+        (self.start < other.end && other.start < self.end) || self.start == other.start
+    }
+}
+
+// A machine-applicable suggestion attached to a diagnostic. Its edits are
+// applied together or not at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+// Applies `fixes` to `source`, skipping any fix with an edit that overlaps
+// an edit of an earlier fix. Returns the new source and the number of
+// fixes applied.
+pub fn apply(source: &str, fixes: &[&Fix]) -> (String, usize) {
+    let mut accepted: Vec<&TextEdit> = vec![];
+    let mut applied = 0;
+    for fix in fixes {
+        let overlaps = fix
+            .edits
+            .iter()
+            .any(|edit| accepted.iter().any(|a| a.overlaps(edit)));
+        if !overlaps {
+            accepted.extend(fix.edits.iter());
+            applied += 1;
+        }
+    }
+
+    accepted.sort_by_key(|edit| edit.start);
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::new();
+    let mut position = 0;
+    for edit in accepted {
+        out.extend(&chars[position..edit.start as usize]);
+        out.push_str(&edit.text);
+        position = edit.end as usize;
+    }
+    out.extend(&chars[position..]);
+    (out, applied)
+}
+
+#[cfg(test)]
+mod fix_tests {
+    use super::*;
+
+    fn fix(edits: Vec<TextEdit>) -> Fix {
+        Fix {
+            title: "test".to_string(),
+            edits,
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let fixes = [
+            fix(vec![TextEdit::insert(9, ";")]),
+            fix(vec![TextEdit::replace(16, 17, "==")]),
+            fix(vec![TextEdit::delete(0, 10)]),
+            fix(vec![TextEdit::insert(9, "!"), TextEdit::delete(20, 21)]),
+        ];
+        let fixes: Vec<&Fix> = fixes.iter().collect();
+
+        assert_eq!(
+            apply("let é = 1\nif (x = 1) { x }", &fixes),
+            ("let é = 1;\nif (x == 1) { x }".to_string(), 2)
+        );
+    }
+}
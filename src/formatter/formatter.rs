@@ -0,0 +1,717 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    ast::{
+        ast::{BlockStatement, CallExpression, Expression, Node, Program, Statement},
+        visit::{walk_call_expression, Visitor},
+    },
+    lexer::lexer::{Comment, Lexer},
+    parser::parser::{ParseError, Parser},
+    token::token::{Span, Token, EOF, LBRACE, LPAREN, RBRACE, RPAREN, SEMICOLON},
+};
+
+const MAX_WIDTH: usize = 80;
+const INDENT: &str = "    ";
+
+#[derive(Debug, PartialEq)]
+pub enum FormatError {
+    Parse(Vec<ParseError>),
+    // The parser skipped tokens it does not understand yet, so printing the
+    // AST would silently drop code.
+    Unsupported(Span),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FormatError::Parse(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("\n"))
+            }
+            FormatError::Unsupported(span) => write!(
+                f,
+                "{}:{}: unsupported syntax, refusing to format",
+                span.line, span.column
+            ),
+        }
+    }
+}
+
+pub fn format(source: &str) -> Result<String, FormatError> {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    let program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(FormatError::Parse(parser.errors().to_vec()));
+    }
+
+    let (tokens, comments) = lex(source);
+    let mut formatter = Formatter::new(source, comments, &tokens, &program);
+    formatter.write_program(&program);
+    let output = formatter.finish();
+
+    let (output_tokens, _) = lex(&output);
+    check_tokens(&tokens, &output_tokens)?;
+    let output_program = Parser::new(Lexer::new(output.clone())).parse_program();
+    check_statements(&program, &output_program, &tokens)?;
+
+    Ok(output)
+}
+
+fn lex(source: &str) -> (Vec<Token>, Vec<Comment>) {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = vec![];
+    loop {
+        let tok = lexer.next_token();
+        if tok.r#type == EOF {
+            break;
+        }
+        tokens.push(tok);
+    }
+    (tokens, lexer.take_comments())
+}
+
+// Semicolons are normalized by the formatter; every other token has to come
+// out exactly as it went in.
+fn check_tokens(input: &[Token], output: &[Token]) -> Result<(), FormatError> {
+    let input: Vec<&Token> = input.iter().filter(|t| t.r#type != SEMICOLON).collect();
+    let output: Vec<&Token> = output.iter().filter(|t| t.r#type != SEMICOLON).collect();
+
+    for (i, tok) in input.iter().enumerate() {
+        match output.get(i) {
+            Some(out) if out.r#type == tok.r#type && out.literal == tok.literal => {}
+            _ => return Err(FormatError::Unsupported(tok.span)),
+        }
+    }
+    if output.len() > input.len() {
+        let span = input.last().map(|t| t.span).unwrap_or_default();
+        return Err(FormatError::Unsupported(span));
+    }
+
+    Ok(())
+}
+
+// Semicolons are left out of `check_tokens`, so a misplaced one could split
+// or join statements without changing any other token. The output has to
+// parse back to the same statements.
+fn check_statements(
+    input: &Program,
+    output: &Program,
+    tokens: &[Token],
+) -> Result<(), FormatError> {
+    let mut output = output.statements.iter();
+    for statement in input.statements.iter() {
+        let matches = output
+            .next()
+            .is_some_and(|out| out.string() == statement.string());
+        if !matches {
+            let start = Formatter::statement_start(statement.as_ref()).unwrap_or_default();
+            let span = tokens
+                .iter()
+                .find(|t| t.span.start == start)
+                .map(|t| t.span)
+                .unwrap_or_default();
+            return Err(FormatError::Unsupported(span));
+        }
+    }
+    if output.next().is_some() {
+        let span = tokens.last().map(|t| t.span).unwrap_or_default();
+        return Err(FormatError::Unsupported(span));
+    }
+
+    Ok(())
+}
+
+// Start offset of every `open` token mapped to the start offset of the
+// `close` token that balances it.
+fn matching_pairs(tokens: &[Token], open: &str, close: &str) -> HashMap<u32, u32> {
+    let mut pairs = HashMap::new();
+    let mut stack = vec![];
+    for tok in tokens {
+        if tok.r#type == open {
+            stack.push(tok.span.start);
+        } else if tok.r#type == close {
+            if let Some(start) = stack.pop() {
+                pairs.insert(start, tok.span.start);
+            }
+        }
+    }
+    pairs
+}
+
+// Start offsets of the `(` that open call arguments. These belong to the
+// call, so they are never counted as grouping around its argument.
+#[derive(Default)]
+struct CallParens(HashSet<u32>);
+
+impl Visitor for CallParens {
+    fn visit_call_expression(&mut self, expression: &CallExpression) {
+        self.0.insert(expression.token.span.start);
+        walk_call_expression(self, expression);
+    }
+}
+
+struct Formatter {
+    source: Vec<char>,
+    comments: Vec<Comment>,
+    next_comment: usize,
+    tokens: Vec<Token>,
+    // Start offset of every token mapped to its index in `tokens`.
+    token_index: HashMap<u32, usize>,
+    // Start offset of every `{` mapped to the start offset of its `}`.
+    block_ends: HashMap<u32, u32>,
+    // Start offset of every `(` mapped to the start offset of its `)`.
+    paren_ends: HashMap<u32, u32>,
+    call_parens: HashSet<u32>,
+    out: String,
+    indent: usize,
+    at_block_start: bool,
+}
+
+impl Formatter {
+    fn new(source: &str, comments: Vec<Comment>, tokens: &[Token], program: &Program) -> Self {
+        let block_ends = matching_pairs(tokens, LBRACE, RBRACE);
+        let paren_ends = matching_pairs(tokens, LPAREN, RPAREN);
+        let token_index = tokens
+            .iter()
+            .enumerate()
+            .map(|(i, tok)| (tok.span.start, i))
+            .collect();
+        let mut call_parens = CallParens::default();
+        call_parens.visit_program(program);
+
+        Formatter {
+            source: source.chars().collect(),
+            comments,
+            next_comment: 0,
+            tokens: tokens.to_vec(),
+            token_index,
+            block_ends,
+            paren_ends,
+            call_parens: call_parens.0,
+            out: String::new(),
+            indent: 0,
+            at_block_start: false,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.write_comments_before(u32::MAX);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn write_program(&mut self, program: &Program) {
+        for statement in program.statements.iter() {
+            self.write_statement(statement.as_ref());
+        }
+    }
+
+    fn write_statement(&mut self, statement: &dyn Statement) {
+        let start = match Formatter::statement_start(statement) {
+            Some(start) => start,
+            None => return,
+        };
+        self.write_comments_before(start);
+        self.begin_line(start);
+
+        if let Some(stmt) = statement.as_let_statement() {
+            self.out.push_str(&format!("let {}", stmt.name.value));
+            if let Some(r#type) = &stmt.r#type {
+                self.out.push_str(&format!(": {}", r#type.string()));
+            }
+            self.out.push_str(" = ");
+            self.write_expression(stmt.value.as_ref());
+            self.out.push(';');
+        } else if let Some(stmt) = statement.as_return_statement() {
+            self.out.push_str("return ");
+            self.write_expression(stmt.return_value.as_ref());
+            self.out.push(';');
+        } else if let Some(stmt) = statement.as_expression_statement() {
+            self.write_expression(stmt.expression.as_ref());
+            self.out.push(';');
+        } else if let Some(block) = statement.as_block_statement() {
+            self.write_block(block);
+        } else if let Some(stmt) = statement.as_while_statement() {
+            self.out.push_str("while (");
+            self.write_grouped(stmt.condition.as_ref(), 1);
+            self.out.push_str(") ");
+            self.write_block(&stmt.body);
+        } else if let Some(stmt) = statement.as_for_statement() {
+            self.out.push_str("for (");
+            if let Some(key) = &stmt.key {
+                self.out.push_str(&key.value);
+                self.out.push_str(", ");
+            }
+            self.out.push_str(&stmt.value.value);
+            self.out.push_str(" in ");
+            self.write_expression(stmt.iterable.as_ref());
+            self.out.push_str(") ");
+            self.write_block(&stmt.body);
+        } else if statement.as_break_statement().is_some() {
+            self.out.push_str("break;");
+        } else if statement.as_continue_statement().is_some() {
+            self.out.push_str("continue;");
+        }
+    }
+
+    fn statement_start(statement: &dyn Statement) -> Option<u32> {
+        if let Some(stmt) = statement.as_let_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_return_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_expression_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_while_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_for_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_break_statement() {
+            Some(stmt.token.span.start)
+        } else if let Some(stmt) = statement.as_continue_statement() {
+            Some(stmt.token.span.start)
+        } else {
+            statement.as_block_statement().map(|b| b.token.span.start)
+        }
+    }
+
+    // The AST does not record grouping, so parentheses are carried over
+    // from the source: every pair found directly around the expression's
+    // tokens is written back out.
+    fn write_expression(&mut self, expression: &dyn Expression) {
+        self.write_grouped(expression, 0);
+    }
+
+    // Writes `expression` with all but `skip` of its surrounding parentheses;
+    // an `if` condition's own parentheses are written by the `if`.
+    fn write_grouped(&mut self, expression: &dyn Expression, skip: usize) {
+        let (first, last) = self.inner_range(expression);
+        let (parens, _, _) = self.parenthesized(first, last);
+        let parens = parens.saturating_sub(skip);
+
+        self.out.push_str(&"(".repeat(parens));
+        if let Some(literal) = expression.as_function_literal() {
+            let parameters = literal
+                .parameters
+                .iter()
+                .zip(literal.parameter_types.iter())
+                .map(|(p, t)| match t {
+                    Some(t) => format!("{}: {}", p.value, t.string()),
+                    None => p.value.to_string(),
+                })
+                .collect();
+            let return_type = match &literal.return_type {
+                Some(t) => format!(" -> {}", t.string()),
+                None => String::new(),
+            };
+            self.write_function_like("fn", parameters, &return_type, &literal.body);
+        } else if let Some(literal) = expression.as_macro_literal() {
+            let parameters = literal.parameters.iter().map(|p| p.value.clone()).collect();
+            self.write_function_like("macro", parameters, "", &literal.body);
+        } else if let Some(prefix) = expression.as_prefix_expression() {
+            self.out.push_str(&prefix.operator);
+            self.write_expression(prefix.right.as_ref());
+        } else if let Some(infix) = expression.as_infix_expression() {
+            self.write_expression(infix.left.as_ref());
+            self.out.push_str(&format!(" {} ", infix.operator));
+            self.write_expression(infix.right.as_ref());
+        } else if let Some(assign) = expression.as_assign_expression() {
+            self.write_expression(&assign.name);
+            self.out.push_str(&format!(" {} ", assign.operator));
+            self.write_expression(assign.value.as_ref());
+        } else if let Some(call) = expression.as_call_expression() {
+            self.write_expression(call.function.as_ref());
+            self.out.push('(');
+            for (i, argument) in call.arguments.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+                self.write_expression(argument.as_ref());
+            }
+            self.out.push(')');
+        } else if let Some(expr) = expression.as_if_expression() {
+            self.out.push_str("if (");
+            self.write_grouped(expr.condition.as_ref(), 1);
+            self.out.push_str(") ");
+            self.write_block(&expr.consequence);
+            if let Some(alternative) = &expr.alternative {
+                self.out.push_str(" else ");
+                self.write_block(alternative);
+            }
+        } else {
+            self.out.push_str(&expression.string());
+        }
+        self.out.push_str(&")".repeat(parens));
+    }
+
+    // Indexes of the first and last token of `expression`, not counting
+    // parentheses around it.
+    fn inner_range(&self, expression: &dyn Expression) -> (usize, usize) {
+        let index = |span: &Span| self.token_index[&span.start];
+        let block_end =
+            |block: &BlockStatement| self.token_index[&self.block_ends[&block.token.span.start]];
+
+        if let Some(literal) = expression.as_function_literal() {
+            (index(&literal.token.span), block_end(&literal.body))
+        } else if let Some(literal) = expression.as_macro_literal() {
+            (index(&literal.token.span), block_end(&literal.body))
+        } else if let Some(prefix) = expression.as_prefix_expression() {
+            (
+                index(&prefix.token.span),
+                self.outer_range(prefix.right.as_ref()).1,
+            )
+        } else if let Some(infix) = expression.as_infix_expression() {
+            (
+                self.outer_range(infix.left.as_ref()).0,
+                self.outer_range(infix.right.as_ref()).1,
+            )
+        } else if let Some(assign) = expression.as_assign_expression() {
+            (
+                self.outer_range(&assign.name).0,
+                self.outer_range(assign.value.as_ref()).1,
+            )
+        } else if let Some(call) = expression.as_call_expression() {
+            (
+                self.outer_range(call.function.as_ref()).0,
+                self.token_index[&self.paren_ends[&call.token.span.start]],
+            )
+        } else if let Some(expr) = expression.as_if_expression() {
+            let last = expr.alternative.as_ref().unwrap_or(&expr.consequence);
+            (index(&expr.token.span), block_end(last))
+        } else if let Some(ident) = expression.as_identifier_expression() {
+            (index(&ident.token.span), index(&ident.token.span))
+        } else if let Some(literal) = expression.as_integer_literal() {
+            (index(&literal.token.span), index(&literal.token.span))
+        } else if let Some(literal) = expression.as_float_literal() {
+            (index(&literal.token.span), index(&literal.token.span))
+        } else if let Some(literal) = expression.as_boolean() {
+            (index(&literal.token.span), index(&literal.token.span))
+        } else {
+            unreachable!("expression without a source range")
+        }
+    }
+
+    fn outer_range(&self, expression: &dyn Expression) -> (usize, usize) {
+        let (first, last) = self.inner_range(expression);
+        let (_, first, last) = self.parenthesized(first, last);
+        (first, last)
+    }
+
+    // Counts the balanced parenthesis pairs directly around the tokens
+    // `first..=last` and returns the count with the widened range.
+    fn parenthesized(&self, mut first: usize, mut last: usize) -> (usize, usize, usize) {
+        let mut count = 0;
+        while first > 0 && last + 1 < self.tokens.len() {
+            let (open, close) = (&self.tokens[first - 1], &self.tokens[last + 1]);
+            if open.r#type != LPAREN
+                || close.r#type != RPAREN
+                || self.paren_ends.get(&open.span.start) != Some(&close.span.start)
+                || self.call_parens.contains(&open.span.start)
+            {
+                break;
+            }
+            count += 1;
+            first -= 1;
+            last += 1;
+        }
+        (count, first, last)
+    }
+
+    fn write_function_like(
+        &mut self,
+        keyword: &str,
+        parameters: Vec<String>,
+        return_type: &str,
+        body: &BlockStatement,
+    ) {
+        let header = format!("{}({}){} {{", keyword, parameters.join(", "), return_type);
+
+        if parameters.is_empty() || self.column() + header.chars().count() <= MAX_WIDTH {
+            self.out.push_str(&format!(
+                "{}({}){} ",
+                keyword,
+                parameters.join(", "),
+                return_type
+            ));
+        } else {
+            self.out.push_str(keyword);
+            self.out.push('(');
+            self.indent += 1;
+            for (i, parameter) in parameters.iter().enumerate() {
+                self.newline();
+                self.out.push_str(parameter);
+                if i + 1 < parameters.len() {
+                    self.out.push(',');
+                }
+            }
+            self.indent -= 1;
+            self.newline();
+            self.out.push_str(&format!("){} ", return_type));
+        }
+
+        self.write_block(body);
+    }
+
+    fn write_block(&mut self, block: &BlockStatement) {
+        let end = self.block_ends[&block.token.span.start];
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|c| c.span.start < end);
+        if block.statements.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        self.indent += 1;
+        self.at_block_start = true;
+        for statement in block.statements.iter() {
+            self.write_statement(statement.as_ref());
+        }
+        self.write_comments_before(end);
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+        self.at_block_start = false;
+    }
+
+    fn write_comments_before(&mut self, offset: u32) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= offset {
+                break;
+            }
+            let comment = comment.clone();
+            self.next_comment += 1;
+
+            if self.is_trailing(&comment) && !self.out.is_empty() {
+                self.out.push(' ');
+            } else {
+                self.begin_line(comment.span.start);
+            }
+            self.out.push_str(&comment.text);
+        }
+    }
+
+    // Starts the line for a statement or comment found at `offset`, keeping
+    // a single blank line if the source had at least one before it.
+    fn begin_line(&mut self, offset: u32) {
+        if !self.out.is_empty() {
+            if !self.at_block_start && self.blank_line_before(offset) {
+                self.out.push('\n');
+            }
+            self.newline();
+        }
+        self.at_block_start = false;
+    }
+
+    fn newline(&mut self) {
+        self.out.push('\n');
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    fn column(&self) -> usize {
+        match self.out.rfind('\n') {
+            Some(i) => self.out[i + 1..].chars().count(),
+            None => self.out.chars().count(),
+        }
+    }
+
+    fn blank_line_before(&self, offset: u32) -> bool {
+        let mut newlines = 0;
+        for ch in self.source[..offset as usize].iter().rev() {
+            match ch {
+                '\n' => newlines += 1,
+                ' ' | '\t' | '\r' => {}
+                _ => break,
+            }
+        }
+        newlines >= 2
+    }
+
+    fn is_trailing(&self, comment: &Comment) -> bool {
+        for ch in self.source[..comment.span.start as usize].iter().rev() {
+            match ch {
+                '\n' => return false,
+                ' ' | '\t' | '\r' => {}
+                _ => return true,
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod formatter_tests {
+    use super::*;
+
+    const CORPUS: [&str; 14] = [
+        "",
+        "let x = 5;",
+        "let   x=5\n;let y = 0x1_0;",
+        "// only a comment\n",
+        "let m = macro(a, b) { a; b; };\n\n\n\nreturn m;",
+        "let m = macro() {\n\n  // nothing yet\n};",
+        "let wrap = macro(first_parameter, second_parameter, third_parameter, fourth_parameter) { first_parameter };",
+        "let sign = fn(n) { if (n < 0) { -1 } else { if (n == 0) { 0 } else { 1 } } };",
+        "(1 + 2) * 3 == 9;",
+        "let f: fn() -> int = fn(a: float, b: fn(int) -> bool) -> null { a };",
+        "while (true) { // forever\n  if (done) { break; } continue; }",
+        "for (k,v in (h)) {\n// each\nk; };",
+        "let a = 1; (a) = b = (c -= 2) ; a*=a/=2",
+        "f(g)((x), fn(y){y}(2))+ -h( );",
+    ];
+
+    #[test]
+    fn test_format() {
+        let tests = vec![
+            ("let   x=5\n;", "let x = 5;\n"),
+            ("let x = 1_000\n;let y = 2", "let x = 1_000;\nlet y = 2;\n"),
+            ("  foo\n\n\n\nbar;   return 2.5;", "foo;\n\nbar;\nreturn 2.5;\n"),
+            (
+                "let m = macro(a,b){a;\n\n b}; m;",
+                "let m = macro(a, b) {\n    a;\n\n    b;\n};\nm;\n",
+            ),
+            ("let m = macro(  ) {  };", "let m = macro() {};\n"),
+            ("let f = fn(x,y){x};", "let f = fn(x, y) {\n    x;\n};\n"),
+            ("-a*b+c==!d", "-a * b + c == !d;\n"),
+            ("((a+b))*-(c);", "((a + b)) * -(c);\n"),
+            (
+                "if((x<y)){true}else{ false }",
+                "if ((x < y)) {\n    true;\n} else {\n    false;\n};\n",
+            ),
+            ("if (x) {}", "if (x) {};\n"),
+            (
+                "while((x<3)){if(x){break};continue;};",
+                "while ((x < 3)) {\n    if (x) {\n        break;\n    };\n    continue;\n}\n",
+            ),
+            (
+                "for(k,v in h){k};for (x in (a+b)) {}",
+                "for (k, v in h) {\n    k;\n}\nfor (x in (a + b)) {}\n",
+            ),
+            (
+                "x=1;x+=y*2;if((x-=1)){x/=(y=2)}",
+                "x = 1;\nx += y * 2;\nif ((x -= 1)) {\n    x /= (y = 2);\n};\n",
+            ),
+            (
+                "let f:fn(int,int)->bool=fn(a:int,b)->bool{a<b};",
+                "let f: fn(int, int) -> bool = fn(a: int, b) -> bool {\n    a < b;\n};\n",
+            ),
+            ("f(2);x(2)+3", "f(2);\nx(2) + 3;\n"),
+            (
+                "add( (a),b*2 )(fn(x){x}(1),g())",
+                "add((a), b * 2)(fn(x) {\n    x;\n}(1), g());\n",
+            ),
+            (
+                "let m = macro(x) { let inner = macro(y) { y; }; inner; };",
+                "let m = macro(x) {\n    let inner = macro(y) {\n        y;\n    };\n    inner;\n};\n",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let output = format(input).unwrap();
+            assert_eq!(
+                output, expected,
+                "wrong formatting for {:?}. got {:?}",
+                input, output
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_wraps_long_parameter_lists() {
+        let input = "let wrap = macro(first_parameter, second_parameter, third_parameter, fourth_parameter) { first_parameter; };";
+        let expected = r"let wrap = macro(
+    first_parameter,
+    second_parameter,
+    third_parameter,
+    fourth_parameter
+) {
+    first_parameter;
+};
+";
+        assert_eq!(format(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_preserves_comments() {
+        let input = r"// header
+
+let x = 5; // five
+let m = macro(a) { // opens
+  // inside
+  a; // trailing
+
+
+  // before close
+};
+// footer";
+        let expected = r"// header
+
+let x = 5; // five
+let m = macro(a) { // opens
+    // inside
+    a; // trailing
+
+    // before close
+};
+// footer
+";
+        assert_eq!(format(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        for input in CORPUS {
+            let once = format(input).unwrap();
+            let twice = format(&once).unwrap();
+            assert_eq!(
+                once, twice,
+                "formatting {:?} is not idempotent. got {:?} then {:?}",
+                input, once, twice
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_refuses_unsupported_syntax() {
+        let result = format("let x = 5;\nx @ y;");
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("2:3: illegal character '@'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_statements() {
+        let parse = |source: &str| Parser::new(Lexer::new(source.to_string())).parse_program();
+        let (tokens, _) = lex("x;\nf(2);");
+
+        let input = parse("x;\nf(2);");
+        assert_eq!(
+            check_statements(&input, &parse("x;\nf(2);"), &tokens),
+            Ok(())
+        );
+        assert_eq!(
+            check_statements(&input, &parse("x;\nf;\n(2);"), &tokens).map_err(|e| e.to_string()),
+            Err("2:1: unsupported syntax, refusing to format".to_string())
+        );
+        assert_eq!(
+            check_statements(&input, &parse("x;"), &tokens).map_err(|e| e.to_string()),
+            Err("2:1: unsupported syntax, refusing to format".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_reports_parse_errors() {
+        let result = format("let = 5;");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "1:5: expected next token to be IDENT, got ="
+        );
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{
+        ast::{Program, TypeExpression},
+        visit::{walk_type_expression, Visitor},
+    },
+    lexer::lexer::Lexer,
+    parser::parser::Parser,
+    resolver::resolver::{resolve, BindingKind, Resolution},
+    token::token::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    Keyword,
+    Identifier,
+    Parameter,
+    Function,
+    Type,
+    Number,
+    Operator,
+    Punctuation,
+    Comment,
+}
+
+impl Category {
+    // The name used for HTML classes and editor token types.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Keyword => "keyword",
+            Category::Identifier => "identifier",
+            Category::Parameter => "parameter",
+            Category::Function => "function",
+            Category::Type => "type",
+            Category::Number => "number",
+            Category::Operator => "operator",
+            Category::Punctuation => "punctuation",
+            Category::Comment => "comment",
+        }
+    }
+
+    // SGR parameters for terminal output. Plain identifiers and
+    // punctuation keep the default color.
+    fn ansi(&self) -> Option<&'static str> {
+        match self {
+            Category::Keyword => Some("35"),
+            Category::Identifier | Category::Punctuation => None,
+            Category::Parameter => Some("36"),
+            Category::Function => Some("34"),
+            Category::Type => Some("96"),
+            Category::Number => Some("33"),
+            Category::Operator => Some("31"),
+            Category::Comment => Some("90"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub span: Span,
+    pub category: Category,
+}
+
+// Classifies every token and comment of `source`, in source order.
+// Whitespace and illegal tokens are left unclassified. Identifiers are
+// classified by what they are bound to: a parameter, a let bound to a
+// function literal, or anything else. Names in type annotations are types.
+pub fn classify(source: &str) -> Vec<Highlight> {
+    let mut parser = Parser::new(Lexer::new(source.to_string()));
+    let program = parser.parse_program();
+    let mut identifiers = identifier_categories(&resolve(source, &program));
+    identifiers.extend(
+        type_names(&program)
+            .into_iter()
+            .map(|start| (start, Category::Type)),
+    );
+
+    let mut lexer = Lexer::new(source.to_string());
+    let mut highlights = vec![];
+    loop {
+        let tok = lexer.next_token();
+        if tok.r#type == EOF {
+            break;
+        }
+        let category = match tok.r#type {
+            IDENT => identifiers
+                .get(&tok.span.start)
+                .copied()
+                .or(Some(Category::Identifier)),
+            INT | FLOAT => Some(Category::Number),
+            FUNCTION | LET | TRUE | FALSE | IF | ELSE | RETURN | MACRO | WHILE | BREAK
+            | CONTINUE | FOR | IN => Some(Category::Keyword),
+            ASSIGN | PLUS | MINUS | BANG | ASTERISK | SLASH | LT | GT | EQ | NEQ | ARROW
+            | PLUS_ASSIGN | MINUS_ASSIGN | ASTERISK_ASSIGN | SLASH_ASSIGN => {
+                Some(Category::Operator)
+            }
+            COMMA | SEMICOLON | COLON | LPAREN | RPAREN | LBRACE | RBRACE => {
+                Some(Category::Punctuation)
+            }
+            _ => None,
+        };
+        if let Some(category) = category {
+            highlights.push(Highlight {
+                span: tok.span,
+                category,
+            });
+        }
+    }
+
+    for comment in lexer.take_comments() {
+        highlights.push(Highlight {
+            span: comment.span,
+            category: Category::Comment,
+        });
+    }
+    highlights.sort_by_key(|h| h.span.start);
+    highlights
+}
+
+// Category of every bound identifier, keyed by its start offset.
+fn identifier_categories(resolution: &Resolution) -> HashMap<u32, Category> {
+    let binding_category = |index: usize| {
+        let binding = &resolution.bindings[index];
+        match binding.kind {
+            BindingKind::Parameter => Category::Parameter,
+            BindingKind::Let if binding.is_function => Category::Function,
+            BindingKind::Let | BindingKind::LoopVariable => Category::Identifier,
+        }
+    };
+
+    let mut categories = HashMap::new();
+    for (index, binding) in resolution.bindings.iter().enumerate() {
+        categories.insert(binding.span.start, binding_category(index));
+    }
+    for reference in resolution.references.iter() {
+        if let Some(index) = reference.binding {
+            categories.insert(reference.span.start, binding_category(index));
+        }
+    }
+    categories
+}
+
+// Start offsets of the type names in the program's annotations.
+fn type_names(program: &Program) -> Vec<u32> {
+    struct TypeNames(Vec<u32>);
+
+    impl Visitor for TypeNames {
+        fn visit_type_expression(&mut self, r#type: &TypeExpression) {
+            if let TypeExpression::Named(token) = r#type {
+                self.0.push(token.span.start);
+            }
+            walk_type_expression(self, r#type);
+        }
+    }
+
+    let mut names = TypeNames(vec![]);
+    names.visit_program(program);
+    names.0
+}
+
+// `source` with every highlighted range wrapped in ANSI color escapes.
+pub fn to_ansi(source: &str, highlights: &[Highlight]) -> String {
+    render(
+        source,
+        highlights,
+        str::to_string,
+        |category, text| match category.ansi() {
+            Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+            None => text.to_string(),
+        },
+    )
+}
+
+// `source` as an HTML `<pre>` block with one `<span>` per highlighted range,
+// classed by category name.
+pub fn to_html(source: &str, highlights: &[Highlight]) -> String {
+    let body = render(source, highlights, escape_html, |category, text| {
+        format!(
+            "<span class=\"{}\">{}</span>",
+            category.name(),
+            escape_html(text)
+        )
+    });
+    format!("<pre class=\"monkey\">{}</pre>\n", body)
+}
+
+// Rebuilds `source`, passing text between ranges through `plain` and each
+// highlighted range through `wrap`.
+fn render<P, W>(source: &str, highlights: &[Highlight], plain: P, wrap: W) -> String
+where
+    P: Fn(&str) -> String,
+    W: Fn(Category, &str) -> String,
+{
+    let chars: Vec<char> = source.chars().collect();
+    let mut out = String::new();
+    let mut position = 0;
+    for highlight in highlights {
+        let (start, end) = (highlight.span.start as usize, highlight.span.end as usize);
+        let gap: String = chars[position..start].iter().collect();
+        let text: String = chars[start..end].iter().collect();
+        out.push_str(&plain(&gap));
+        out.push_str(&wrap(highlight.category, &text));
+        position = end;
+    }
+    let rest: String = chars[position..].iter().collect();
+    out.push_str(&plain(&rest));
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::new();
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            ch => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod highlight_tests {
+    use super::*;
+
+    fn describe(source: &str) -> Vec<String> {
+        let chars: Vec<char> = source.chars().collect();
+        classify(source)
+            .iter()
+            .map(|h| {
+                let text: String = chars[h.span.start as usize..h.span.end as usize]
+                    .iter()
+                    .collect();
+                format!("{} {}", h.category.name(), text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        let input = "let add = fn(x, y) { x; }; // sum\nadd; let n = 1.5; n != z; @";
+
+        assert_eq!(
+            describe(input),
+            vec![
+                "keyword let",
+                "function add",
+                "operator =",
+                "keyword fn",
+                "punctuation (",
+                "parameter x",
+                "punctuation ,",
+                "parameter y",
+                "punctuation )",
+                "punctuation {",
+                "parameter x",
+                "punctuation ;",
+                "punctuation }",
+                "punctuation ;",
+                "comment // sum",
+                "function add",
+                "punctuation ;",
+                "keyword let",
+                "identifier n",
+                "operator =",
+                "number 1.5",
+                "punctuation ;",
+                "identifier n",
+                "operator !=",
+                "identifier z",
+                "punctuation ;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_loops() {
+        let input = "while (x) { break; continue; } for (k in h) { k *= 2 }";
+
+        assert_eq!(
+            describe(input),
+            vec![
+                "keyword while",
+                "punctuation (",
+                "identifier x",
+                "punctuation )",
+                "punctuation {",
+                "keyword break",
+                "punctuation ;",
+                "keyword continue",
+                "punctuation ;",
+                "punctuation }",
+                "keyword for",
+                "punctuation (",
+                "identifier k",
+                "keyword in",
+                "identifier h",
+                "punctuation )",
+                "punctuation {",
+                "identifier k",
+                "operator *=",
+                "number 2",
+                "punctuation }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_classify_annotations() {
+        let input = "let f: fn(int) -> bool = fn(n: int) { n };";
+
+        assert_eq!(
+            describe(input),
+            vec![
+                "keyword let",
+                "function f",
+                "punctuation :",
+                "keyword fn",
+                "punctuation (",
+                "type int",
+                "punctuation )",
+                "operator ->",
+                "type bool",
+                "operator =",
+                "keyword fn",
+                "punctuation (",
+                "parameter n",
+                "punctuation :",
+                "type int",
+                "punctuation )",
+                "punctuation {",
+                "parameter n",
+                "punctuation }",
+                "punctuation ;",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_ansi() {
+        let input = "let x = 1; // c";
+        assert_eq!(
+            to_ansi(input, &classify(input)),
+            "\x1b[35mlet\x1b[0m x \x1b[31m=\x1b[0m \x1b[33m1\x1b[0m; \x1b[90m// c\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_to_html() {
+        let input = "x < 1 // <&>\n";
+        assert_eq!(
+            to_html(input, &classify(input)),
+            "<pre class=\"monkey\"><span class=\"identifier\">x</span> \
+             <span class=\"operator\">&lt;</span> <span class=\"number\">1</span> \
+             <span class=\"comment\">// &lt;&amp;&gt;</span>\n</pre>\n"
+        );
+    }
+}
@@ -0,0 +1,447 @@
+use std::fmt;
+
+// A JSON value. Objects keep their fields in insertion order so that
+// serialized output is stable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    pub fn string(value: &str) -> Json {
+        Json::String(value.to_string())
+    }
+
+    // Parses a complete JSON document. Numbers without a fraction or
+    // exponent that fit in an i64 become `Int`, all others `Float`.
+    pub fn parse(input: &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars: input.chars().collect(),
+            position: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    // Returns the field `key` of an object, or None for anything else.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    // Serializes with two-space indentation, one field or element per line.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+        match self {
+            Json::Array(values) if !values.is_empty() => {
+                out.push_str("[\n");
+                for (i, value) in values.iter().enumerate() {
+                    out.push_str(&indent);
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if i + 1 < values.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(depth));
+                out.push(']');
+            }
+            Json::Object(fields) if !fields.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(&indent);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_pretty(out, depth + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(depth));
+                out.push('}');
+            }
+            _ => out.push_str(&self.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Int(value) => write!(f, "{}", value),
+            // Debug formatting keeps a fractional part, so floats stay floats.
+            Json::Float(value) => write!(f, "{:?}", value),
+            Json::String(value) => {
+                let mut out = String::new();
+                write_string(&mut out, value);
+                write!(f, "{}", out)
+            }
+            Json::Array(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", values.join(","))
+            }
+            Json::Object(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", Json::string(key), value))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(","))
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl JsonParser {
+    fn error(&self, msg: &str) -> String {
+        format!("{} at offset {}", msg, self.position)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), String> {
+        if self.peek() == Some(ch) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", ch)))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for ch in word.chars() {
+            self.expect(ch)?;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(values));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(fields));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut out = String::new();
+        loop {
+            let ch = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.position += 1;
+            match ch {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.position += 1;
+                    match escape {
+                        '"' => out.push('"'),
+                        '\\' => out.push('\\'),
+                        '/' => out.push('/'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                ch if (ch as u32) < 0x20 => return Err(self.error("control character in string")),
+                ch => out.push(ch),
+            }
+        }
+    }
+
+    // Decodes the digits after `\u`, combining UTF-16 surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        self.expect('\\')?;
+        self.expect('u')?;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(self.error("invalid surrogate pair"));
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|ch| ch.to_digit(16))
+                .ok_or_else(|| self.error("invalid unicode escape"))?;
+            value = value * 16 + digit;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        let mut is_float = false;
+        while let Some(ch) = self.peek() {
+            match ch {
+                '0'..='9' | '-' | '+' => {}
+                '.' | 'e' | 'E' => is_float = true,
+                _ => break,
+            }
+            self.position += 1;
+        }
+        let text: String = self.chars[start..self.position].iter().collect();
+        if !is_float {
+            if let Ok(value) = text.parse::<i64>() {
+                return Ok(Json::Int(value));
+            }
+        }
+        text.parse::<f64>()
+            .map(Json::Float)
+            .map_err(|_| format!("invalid number {} at offset {}", text, start))
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let value = Json::object(vec![
+            ("null", Json::Null),
+            ("bool", Json::Bool(true)),
+            ("int", Json::Int(-42)),
+            ("float", Json::Float(2.5)),
+            ("string", Json::string("a \"quoted\"\\ line\n\t\u{1}é")),
+            (
+                "array",
+                Json::Array(vec![Json::Int(1), Json::Array(vec![])]),
+            ),
+            ("object", Json::object(vec![])),
+        ]);
+
+        assert_eq!(
+            value.to_string(),
+            r#"{"null":null,"bool":true,"int":-42,"float":2.5,"string":"a \"quoted\"\\ line\n\t\u0001é","array":[1,[]],"object":{}}"#
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        let value = Json::object(vec![
+            ("a", Json::Array(vec![Json::Int(1), Json::Int(2)])),
+            ("b", Json::object(vec![("c", Json::Null)])),
+            ("d", Json::Array(vec![])),
+        ]);
+
+        assert_eq!(
+            value.pretty(),
+            r#"{
+  "a": [
+    1,
+    2
+  ],
+  "b": {
+    "c": null
+  },
+  "d": []
+}"#
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = r#" {"null": null, "bool": [true, false], "int": -42, "float": 2.5e1,
+            "big": 18446744073709551616, "string": "a \"q\"\\ \n\u00e9\ud83d\ude00",
+            "object": {}} "#;
+
+        let value = Json::parse(input).expect("parse failed");
+        assert_eq!(
+            value,
+            Json::object(vec![
+                ("null", Json::Null),
+                (
+                    "bool",
+                    Json::Array(vec![Json::Bool(true), Json::Bool(false)])
+                ),
+                ("int", Json::Int(-42)),
+                ("float", Json::Float(25.0)),
+                ("big", Json::Float(18446744073709551616.0)),
+                ("string", Json::string("a \"q\"\\ \né😀")),
+                ("object", Json::object(vec![])),
+            ])
+        );
+        assert_eq!(value.get("int").and_then(Json::as_i64), Some(-42));
+        assert_eq!(value.get("missing"), None);
+
+        let round_trip = Json::parse(&value.to_string()).expect("round trip failed");
+        assert_eq!(round_trip, value);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = vec![
+            ("", "unexpected end of input at offset 0"),
+            ("[1,]", "unexpected character at offset 3"),
+            ("{\"a\" 1}", "expected ':' at offset 5"),
+            ("\"abc", "unterminated string at offset 4"),
+            ("\"\\x\"", "invalid escape at offset 3"),
+            ("tru", "expected 'e' at offset 3"),
+            ("1 2", "unexpected trailing characters at offset 2"),
+            ("1-2", "invalid number 1-2 at offset 0"),
+        ];
+
+        for (input, expected) in tests {
+            match Json::parse(input) {
+                Ok(value) => panic!("expected error for {:?}, got {}", input, value),
+                Err(msg) => assert_eq!(msg, expected, "wrong error for {:?}", input),
+            }
+        }
+    }
+}
@@ -1,29 +1,57 @@
 use crate::token::token::*;
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub message: String,
+    pub span: Span,
+}
+
+// A `//` line comment. Comments are not tokens; the lexer skips them like
+// whitespace and keeps them aside for tools that need to reproduce them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub text: String,
+    pub span: Span,
+}
+
 pub struct Lexer {
-    input: String,
+    input: Vec<char>,
     position: u32,
     read_position: u32,
     ch: char,
+    line: u32,
+    column: u32,
+    errors: Vec<LexError>,
+    comments: Vec<Comment>,
 }
 
 impl Lexer {
     pub fn new(input: String) -> Self {
         let mut l = Lexer {
-            input,
+            input: input.chars().collect(),
             position: 0,
             read_position: 0,
             ch: '\0',
+            line: 1,
+            column: 0,
+            errors: vec![],
+            comments: vec![],
         };
         l.read_char();
         return l;
     }
 
     pub fn read_char(&mut self) {
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
         if self.read_position as usize >= self.input.len() {
             self.ch = '\0';
         } else {
-            self.ch = self.input.chars().nth(self.read_position as usize).unwrap();
+            self.ch = self.input[self.read_position as usize];
         }
         self.position = self.read_position;
         self.read_position += 1;
@@ -33,64 +61,102 @@ impl Lexer {
         if self.read_position as usize >= self.input.len() {
             '\0'
         } else {
-            self.input.chars().nth(self.read_position as usize).unwrap()
+            self.input[self.read_position as usize]
         }
     }
 
     pub fn next_token(&mut self) -> Token {
         self.skip_whitespace();
+        while self.ch == '/' && self.peak_char() == '/' {
+            self.read_comment();
+            self.skip_whitespace();
+        }
+        let start = self.position;
+        let line = self.line;
+        let column = self.column;
+
+        let mut tok = self.read_token();
+        tok.span = Span {
+            start,
+            end: if tok.r#type == EOF {
+                start
+            } else {
+                self.position
+            },
+            line,
+            column,
+        };
+        tok
+    }
+
+    fn read_token(&mut self) -> Token {
         let tok = match self.ch {
-            '=' => {
-                if self.peak_char() == '=' {
-                    let fch = self.ch;
-                    self.read_char();
-                    Token {
-                        r#type: EQ,
-                        literal: format!("{}{}", fch, self.ch),
-                    }
-                } else {
-                    Lexer::new_token(ASSIGN, self.ch)
-                }
-            }
-            '+' => Lexer::new_token(PLUS, self.ch),
-            '-' => Lexer::new_token(MINUS, self.ch),
-            '!' => {
-                if self.peak_char() == '=' {
-                    let fch = self.ch;
-                    self.read_char();
-                    Token {
-                        r#type: NEQ,
-                        literal: format!("{}{}", fch, self.ch),
-                    }
-                } else {
-                    Lexer::new_token(BANG, self.ch)
-                }
-            }
-            '*' => Lexer::new_token(ASTERISK, self.ch),
-            '/' => Lexer::new_token(SLASH, self.ch),
+            '=' => match self.peak_char() {
+                '=' => self.two_char_token(EQ),
+                _ => Lexer::new_token(ASSIGN, self.ch),
+            },
+            '+' => match self.peak_char() {
+                '=' => self.two_char_token(PLUS_ASSIGN),
+                _ => Lexer::new_token(PLUS, self.ch),
+            },
+            '-' => match self.peak_char() {
+                '>' => self.two_char_token(ARROW),
+                '=' => self.two_char_token(MINUS_ASSIGN),
+                _ => Lexer::new_token(MINUS, self.ch),
+            },
+            '!' => match self.peak_char() {
+                '=' => self.two_char_token(NEQ),
+                _ => Lexer::new_token(BANG, self.ch),
+            },
+            '*' => match self.peak_char() {
+                '=' => self.two_char_token(ASTERISK_ASSIGN),
+                _ => Lexer::new_token(ASTERISK, self.ch),
+            },
+            '/' => match self.peak_char() {
+                '=' => self.two_char_token(SLASH_ASSIGN),
+                _ => Lexer::new_token(SLASH, self.ch),
+            },
             '<' => Lexer::new_token(LT, self.ch),
             '>' => Lexer::new_token(GT, self.ch),
             ';' => Lexer::new_token(SEMICOLON, self.ch),
+            ':' => Lexer::new_token(COLON, self.ch),
             '(' => Lexer::new_token(LPAREN, self.ch),
             ')' => Lexer::new_token(RPAREN, self.ch),
             '{' => Lexer::new_token(LBRACE, self.ch),
             '}' => Lexer::new_token(RBRACE, self.ch),
             ',' => Lexer::new_token(COMMA, self.ch),
-            '\0' => Lexer::new_token(EOF, self.ch),
+            '\0' if self.at_end() => Lexer::new_token(EOF, self.ch),
             _ => {
                 if Lexer::is_letter(self.ch) {
                     let literal = self.read_identifier();
                     return Token {
                         r#type: lookup_ident(literal.clone()),
                         literal,
+                        span: Span::default(),
                     };
-                } else if self.ch.is_numeric() {
-                    let literal = self.read_number();
+                } else if self.ch.is_ascii_digit() {
+                    let (r#type, literal) = self.read_number();
                     return Token {
-                        r#type: INT,
+                        r#type,
                         literal,
+                        span: Span::default(),
                     };
                 }
+                // Control characters such as a NUL byte are escaped.
+                let shown: String = if self.ch.is_control() {
+                    self.ch.escape_default().collect()
+                } else {
+                    self.ch.to_string()
+                };
+                self.errors.push(LexError {
+                    message: format!("illegal character '{}'", shown),
+                    span: Span {
+                        start: self.position,
+                        end: self.position + 1,
+                        line: self.line,
+                        column: self.column,
+                    },
+                });
                 Lexer::new_token(ILLEGAL, self.ch)
             }
         };
@@ -98,20 +164,168 @@ impl Lexer {
         return tok;
     }
 
+    // Consumes the current character and the next one as a single token.
+    fn two_char_token(&mut self, r#type: TokenType) -> Token {
+        let fch = self.ch;
+        self.read_char();
+        Token {
+            r#type,
+            literal: format!("{}{}", fch, self.ch),
+            span: Span::default(),
+        }
+    }
+
     fn read_identifier(&mut self) -> String {
         let position = self.position;
         while Lexer::is_letter(self.ch) {
             self.read_char();
         }
-        return self.input[position as usize..self.position as usize].to_string();
+        return self.input[position as usize..self.position as usize]
+            .iter()
+            .collect();
+    }
+
+    fn read_number(&mut self) -> (TokenType, String) {
+        let position = self.position;
+        let line = self.line;
+        let column = self.column;
+        let mut token_type = INT;
+
+        if self.ch == '0' && matches!(self.peak_char(), 'x' | 'b' | 'o') {
+            self.read_char();
+            self.read_char();
+            // Read every alphanumeric character so that malformed literals
+            // such as `0xZZ` are reported as a whole.
+            while self.ch.is_ascii_alphanumeric() || self.ch == '_' {
+                self.read_char();
+            }
+        } else {
+            self.read_digits();
+
+            if self.ch == '.' && self.peak_char().is_ascii_digit() {
+                token_type = FLOAT;
+                self.read_char();
+                self.read_digits();
+            }
+
+            if (self.ch == 'e' || self.ch == 'E') && self.is_exponent_start() {
+                token_type = FLOAT;
+                self.read_char();
+                if self.ch == '+' || self.ch == '-' {
+                    self.read_char();
+                }
+                self.read_digits();
+            }
+        }
+
+        let literal: String = self.input[position as usize..self.position as usize]
+            .iter()
+            .collect();
+        if let Err(message) = Lexer::check_number(&literal) {
+            self.errors.push(LexError {
+                message,
+                span: Span {
+                    start: position,
+                    end: self.position,
+                    line,
+                    column,
+                },
+            });
+            return (ILLEGAL, literal);
+        }
+        (token_type, literal)
+    }
+
+    fn read_digits(&mut self) {
+        while self.ch.is_ascii_digit() || self.ch == '_' {
+            self.read_char();
+        }
+    }
+
+    // An exponent marker only belongs to the number when digits follow it,
+    // optionally after a sign, so `2else` still lexes as INT and IDENT.
+    fn is_exponent_start(&self) -> bool {
+        let mut rest = self.input[self.read_position as usize..].iter().copied();
+        match rest.next() {
+            Some('+') | Some('-') => rest.next().is_some_and(|ch| ch.is_ascii_digit()),
+            Some(ch) => ch.is_ascii_digit(),
+            None => false,
+        }
+    }
+
+    fn check_number(literal: &str) -> Result<(), String> {
+        let (name, radix, digits) = match literal.get(..2) {
+            Some("0x") => ("hexadecimal", 16, &literal[2..]),
+            Some("0b") => ("binary", 2, &literal[2..]),
+            Some("0o") => ("octal", 8, &literal[2..]),
+            _ => ("decimal", 10, literal),
+        };
+
+        if digits.is_empty() {
+            return Err(format!("{} literal {} has no digits", name, literal));
+        }
+        if radix != 10 {
+            if let Some(ch) = digits.chars().find(|ch| *ch != '_' && !ch.is_digit(radix)) {
+                return Err(format!(
+                    "invalid digit '{}' in {} literal {}",
+                    ch, name, literal
+                ));
+            }
+        }
+
+        let chars: Vec<char> = literal.chars().collect();
+        for (i, ch) in chars.iter().enumerate() {
+            if *ch != '_' {
+                continue;
+            }
+            if i + 1 == chars.len() {
+                return Err(format!("trailing '_' in number literal {}", literal));
+            }
+            let is_digit = |ch: char| ch.is_digit(radix);
+            if !is_digit(chars[i - 1]) || !is_digit(chars[i + 1]) {
+                return Err(format!(
+                    "'_' must separate digits in number literal {}",
+                    literal
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn take_errors(&mut self) -> Vec<LexError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        std::mem::take(&mut self.comments)
     }
 
-    fn read_number(&mut self) -> String {
+    fn read_comment(&mut self) {
         let position = self.position;
-        while self.ch.is_numeric() {
+        let line = self.line;
+        let column = self.column;
+        while self.ch != '\n' && !self.at_end() {
             self.read_char();
         }
-        return self.input[position as usize..self.position as usize].to_string();
+        let text: String = self.input[position as usize..self.position as usize]
+            .iter()
+            .collect();
+        self.comments.push(Comment {
+            text: text.trim_end().to_string(),
+            span: Span {
+                start: position,
+                end: self.position,
+                line,
+                column,
+            },
+        });
+    }
+
+    // Whether every character has been read. `ch` is also '\0' then, but a
+    // NUL byte in the input is an illegal character, not the end.
+    fn at_end(&self) -> bool {
+        self.position as usize >= self.input.len()
     }
 
     fn skip_whitespace(&mut self) {
@@ -121,7 +335,7 @@ impl Lexer {
     }
 
     fn new_token(token_type: TokenType, ch: char) -> Token {
-        let literal = if ch == '\0' {
+        let literal = if token_type == EOF {
             "".to_string()
         } else {
             ch.to_string()
@@ -129,6 +343,7 @@ impl Lexer {
         Token {
             r#type: token_type,
             literal,
+            span: Span::default(),
         }
     }
 
@@ -163,6 +378,9 @@ mod lexer_tests {
 
         10 == 10;
         10 != 9;
+        macro(x, y) { x + y; };
+        while (x) { break; continue; }
+        for (k, v in h) {}
         ";
         let tests = vec![
             (LET, "let"),
@@ -238,6 +456,65 @@ mod lexer_tests {
             (NEQ, "!="),
             (INT, "9"),
             (SEMICOLON, ";"),
+            (MACRO, "macro"),
+            (LPAREN, "("),
+            (IDENT, "x"),
+            (COMMA, ","),
+            (IDENT, "y"),
+            (RPAREN, ")"),
+            (LBRACE, "{"),
+            (IDENT, "x"),
+            (PLUS, "+"),
+            (IDENT, "y"),
+            (SEMICOLON, ";"),
+            (RBRACE, "}"),
+            (SEMICOLON, ";"),
+            (WHILE, "while"),
+            (LPAREN, "("),
+            (IDENT, "x"),
+            (RPAREN, ")"),
+            (LBRACE, "{"),
+            (BREAK, "break"),
+            (SEMICOLON, ";"),
+            (CONTINUE, "continue"),
+            (SEMICOLON, ";"),
+            (RBRACE, "}"),
+            (FOR, "for"),
+            (LPAREN, "("),
+            (IDENT, "k"),
+            (COMMA, ","),
+            (IDENT, "v"),
+            (IN, "in"),
+            (IDENT, "h"),
+            (RPAREN, ")"),
+            (LBRACE, "{"),
+            (RBRACE, "}"),
+            (EOF, ""),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_type, expected_literal) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, expected_type);
+            assert_eq!(tok.literal, expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_next_token_numbers() {
+        let input = r"5 3.14 0.5 1e10 2.5E-3 6e+2 7.foo 8else;";
+        let tests = vec![
+            (INT, "5"),
+            (FLOAT, "3.14"),
+            (FLOAT, "0.5"),
+            (FLOAT, "1e10"),
+            (FLOAT, "2.5E-3"),
+            (FLOAT, "6e+2"),
+            (INT, "7"),
+            (ILLEGAL, "."),
+            (IDENT, "foo"),
+            (INT, "8"),
+            (ELSE, "else"),
+            (SEMICOLON, ";"),
             (EOF, ""),
         ];
         let mut l = Lexer::new(input.to_string());
@@ -247,4 +524,269 @@ mod lexer_tests {
             assert_eq!(tok.literal, expected_literal);
         }
     }
+
+    #[test]
+    fn test_next_token_assignments() {
+        let input = r"x = 1; x += 2; x -= -3; x *= 4; x /= 5; x-=>";
+        let tests = vec![
+            (IDENT, "x"),
+            (ASSIGN, "="),
+            (INT, "1"),
+            (SEMICOLON, ";"),
+            (IDENT, "x"),
+            (PLUS_ASSIGN, "+="),
+            (INT, "2"),
+            (SEMICOLON, ";"),
+            (IDENT, "x"),
+            (MINUS_ASSIGN, "-="),
+            (MINUS, "-"),
+            (INT, "3"),
+            (SEMICOLON, ";"),
+            (IDENT, "x"),
+            (ASTERISK_ASSIGN, "*="),
+            (INT, "4"),
+            (SEMICOLON, ";"),
+            (IDENT, "x"),
+            (SLASH_ASSIGN, "/="),
+            (INT, "5"),
+            (SEMICOLON, ";"),
+            (IDENT, "x"),
+            (MINUS_ASSIGN, "-="),
+            (GT, ">"),
+            (EOF, ""),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_type, expected_literal) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, expected_type);
+            assert_eq!(tok.literal, expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_next_token_annotations() {
+        let input = r"let f: fn(int) -> int = fn(x: int) -> int { x-1 - -x };";
+        let tests = vec![
+            (LET, "let"),
+            (IDENT, "f"),
+            (COLON, ":"),
+            (FUNCTION, "fn"),
+            (LPAREN, "("),
+            (IDENT, "int"),
+            (RPAREN, ")"),
+            (ARROW, "->"),
+            (IDENT, "int"),
+            (ASSIGN, "="),
+            (FUNCTION, "fn"),
+            (LPAREN, "("),
+            (IDENT, "x"),
+            (COLON, ":"),
+            (IDENT, "int"),
+            (RPAREN, ")"),
+            (ARROW, "->"),
+            (IDENT, "int"),
+            (LBRACE, "{"),
+            (IDENT, "x"),
+            (MINUS, "-"),
+            (INT, "1"),
+            (MINUS, "-"),
+            (MINUS, "-"),
+            (IDENT, "x"),
+            (RBRACE, "}"),
+            (SEMICOLON, ";"),
+            (EOF, ""),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_type, expected_literal) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, expected_type);
+            assert_eq!(tok.literal, expected_literal);
+        }
+    }
+
+    #[test]
+    fn test_next_token_prefixed_numbers() {
+        let input = r"0xff 0XFF 0b1010 0o17 1_000_000 0xdead_beef 3.141_592 ٣;";
+        let tests = vec![
+            (INT, "0xff"),
+            (INT, "0"),
+            (IDENT, "XFF"),
+            (INT, "0b1010"),
+            (INT, "0o17"),
+            (INT, "1_000_000"),
+            (INT, "0xdead_beef"),
+            (FLOAT, "3.141_592"),
+            (ILLEGAL, "٣"),
+            (SEMICOLON, ";"),
+            (EOF, ""),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_type, expected_literal) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, expected_type);
+            assert_eq!(tok.literal, expected_literal);
+        }
+        let errors: Vec<String> = l.take_errors().into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            errors,
+            vec!["illegal character '٣'"],
+            "lexer should only report the non-ASCII digit"
+        );
+    }
+
+    #[test]
+    fn test_malformed_number_errors() {
+        let tests = vec![
+            ("0xZZ", "invalid digit 'Z' in hexadecimal literal 0xZZ"),
+            ("0b102", "invalid digit '2' in binary literal 0b102"),
+            ("0o", "octal literal 0o has no digits"),
+            ("1_000_", "trailing '_' in number literal 1_000_"),
+            ("1__0", "'_' must separate digits in number literal 1__0"),
+            ("0x_ff", "'_' must separate digits in number literal 0x_ff"),
+            ("1_.5", "'_' must separate digits in number literal 1_.5"),
+        ];
+        for (input, expected_message) in tests {
+            let mut l = Lexer::new(format!("  {};", input));
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, ILLEGAL, "{} should be illegal", input);
+            assert_eq!(tok.literal, input);
+            assert_eq!(l.next_token().r#type, SEMICOLON);
+
+            let errors = l.take_errors();
+            assert_eq!(
+                errors,
+                vec![LexError {
+                    message: expected_message.to_string(),
+                    span: Span {
+                        start: 2,
+                        end: 2 + input.len() as u32,
+                        line: 1,
+                        column: 3
+                    },
+                }],
+                "wrong errors for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_illegal_character_errors() {
+        let mut l = Lexer::new("x @ \"é\n[".to_string());
+        while l.next_token().r#type != EOF {}
+
+        let errors: Vec<(String, u32, u32)> = l
+            .take_errors()
+            .into_iter()
+            .map(|e| (e.message, e.span.line, e.span.column))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("illegal character '@'".to_string(), 1, 3),
+                ("illegal character '\"'".to_string(), 1, 5),
+                ("illegal character '['".to_string(), 2, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nul_byte() {
+        let mut l = Lexer::new("let x\0 = 1; // a\0b\ny".to_string());
+        let mut tokens = vec![];
+        loop {
+            let tok = l.next_token();
+            if tok.r#type == EOF {
+                break;
+            }
+            tokens.push((tok.r#type, tok.literal));
+        }
+
+        let expected: Vec<(TokenType, String)> = [
+            (LET, "let"),
+            (IDENT, "x"),
+            (ILLEGAL, "\0"),
+            (ASSIGN, "="),
+            (INT, "1"),
+            (SEMICOLON, ";"),
+            (IDENT, "y"),
+        ]
+        .into_iter()
+        .map(|(t, l)| (t, l.to_string()))
+        .collect();
+        assert_eq!(tokens, expected);
+        let comments: Vec<String> = l.take_comments().into_iter().map(|c| c.text).collect();
+        assert_eq!(comments, vec!["// a\0b"]);
+        let errors: Vec<String> = l.take_errors().into_iter().map(|e| e.message).collect();
+        assert_eq!(errors, vec!["illegal character '\\u{0}'"]);
+    }
+
+    #[test]
+    fn test_comments() {
+        let input = "// leading\nlet x = 5; // trailing\n10 / 2;\n//";
+        let tests = vec![
+            (LET, "let"),
+            (IDENT, "x"),
+            (ASSIGN, "="),
+            (INT, "5"),
+            (SEMICOLON, ";"),
+            (INT, "10"),
+            (SLASH, "/"),
+            (INT, "2"),
+            (SEMICOLON, ";"),
+            (EOF, ""),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_type, expected_literal) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.r#type, expected_type);
+            assert_eq!(tok.literal, expected_literal);
+        }
+
+        let comments = l.take_comments();
+        let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["// leading", "// trailing", "//"]);
+        assert_eq!(
+            comments[1].span,
+            Span {
+                start: 22,
+                end: 33,
+                line: 2,
+                column: 12
+            }
+        );
+    }
+
+    #[test]
+    fn test_next_token_spans() {
+        let input = "let x = 10;\n  x != 5;";
+        let tests = vec![
+            ("let", 0, 3, 1, 1),
+            ("x", 4, 5, 1, 5),
+            ("=", 6, 7, 1, 7),
+            ("10", 8, 10, 1, 9),
+            (";", 10, 11, 1, 11),
+            ("x", 14, 15, 2, 3),
+            ("!=", 16, 18, 2, 5),
+            ("5", 19, 20, 2, 8),
+            (";", 20, 21, 2, 9),
+            ("", 21, 21, 2, 10),
+        ];
+        let mut l = Lexer::new(input.to_string());
+        for (expected_literal, start, end, line, column) in tests {
+            let tok = l.next_token();
+            assert_eq!(tok.literal, expected_literal);
+            assert_eq!(
+                tok.span,
+                Span {
+                    start,
+                    end,
+                    line,
+                    column
+                },
+                "wrong span for {:?}",
+                tok.literal
+            );
+        }
+    }
 }
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use super::lint::{Severity, RULES};
+
+// Name of the project file that overrides rule severities. `lint` looks for
+// it next to the linted file and then in every parent directory.
+pub const CONFIG_FILE: &str = ".monkeylint";
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+// The severity of every rule. Rules not mentioned in a config file keep
+// their default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    severities: HashMap<&'static str, Severity>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            severities: RULES.iter().map(|r| (r.id, r.severity)).collect(),
+        }
+    }
+}
+
+impl Config {
+    // Parses one `rule-id = severity` setting per line, where severity is
+    // `off`, `warning` or `error`. Blank lines and lines starting with `#`
+    // are ignored.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| ConfigError {
+                line: i + 1,
+                message,
+            };
+
+            let (id, severity) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected rule = severity, got {}", line)))?;
+            let (id, severity) = (id.trim(), severity.trim());
+            let rule = RULES
+                .iter()
+                .find(|r| r.id == id)
+                .ok_or_else(|| error(format!("unknown rule {}", id)))?;
+            let severity = Severity::from_name(severity)
+                .ok_or_else(|| error(format!("unknown severity {}", severity)))?;
+            config.severities.insert(rule.id, severity);
+        }
+        Ok(config)
+    }
+
+    // Loads the nearest config file in `dir` or one of its parents, or the
+    // defaults when there is none. Errors are prefixed with the file name.
+    pub fn load(dir: &Path) -> Result<Config, String> {
+        match find(dir) {
+            Some(file) => {
+                let text = fs::read_to_string(&file)
+                    .map_err(|err| format!("{}: {}", file.display(), err))?;
+                Config::parse(&text).map_err(|err| format!("{}:{}", file.display(), err))
+            }
+            None => Ok(Config::default()),
+        }
+    }
+
+    pub fn severity(&self, rule: &str) -> Severity {
+        self.severities.get(rule).copied().unwrap_or(Severity::Off)
+    }
+}
+
+fn find(dir: &Path) -> Option<PathBuf> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    dir.canonicalize()
+        .ok()?
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|file| file.is_file())
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            "# project settings\n\nunused-parameter = off\n  shadowed-name=error  \n",
+        )
+        .unwrap();
+
+        assert_eq!(config.severity("unused-parameter"), Severity::Off);
+        assert_eq!(config.severity("shadowed-name"), Severity::Error);
+        assert_eq!(config.severity("unused-let"), Severity::Warning);
+        assert_eq!(config.severity("no-such-rule"), Severity::Off);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tests = vec![
+            ("unused-let", "1: expected rule = severity, got unused-let"),
+            ("\nunused-lets = off", "2: unknown rule unused-lets"),
+            ("empty-block = loud", "1: unknown severity loud"),
+        ];
+
+        for (input, expected) in tests {
+            match Config::parse(input) {
+                Err(err) => assert_eq!(err.to_string(), expected),
+                Ok(config) => panic!("expected error for {:?}, got {:?}", input, config),
+            }
+        }
+    }
+}
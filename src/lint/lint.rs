@@ -0,0 +1,708 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    ast::{
+        ast::{
+            BlockStatement, Expression, FunctionLiteral, IfExpression, InfixExpression,
+            LetStatement, MacroLiteral, Program, Statement,
+        },
+        visit::{
+            walk_block_statement, walk_if_expression, walk_infix_expression, walk_let_statement,
+            Visitor,
+        },
+    },
+    fix::fix::{Fix, TextEdit},
+    lexer::lexer::{Comment, Lexer},
+    resolver::resolver::{Binding, BindingKind, Resolution},
+    token::token::{Span, Token, EOF, EQ, GT, LBRACE, LPAREN, LT, NEQ, RBRACE, RPAREN, SEMICOLON},
+};
+
+use super::config::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Severity> {
+        match name {
+            "off" => Some(Severity::Off),
+            "warning" => Some(Severity::Warning),
+            "error" => Some(Severity::Error),
+            _ => None,
+        }
+    }
+}
+
+pub struct Rule {
+    pub id: &'static str,
+    pub description: &'static str,
+    // Severity used when the config file does not mention the rule.
+    pub severity: Severity,
+}
+
+pub const UNUSED_LET: &str = "unused-let";
+pub const UNUSED_PARAMETER: &str = "unused-parameter";
+pub const SHADOWED_NAME: &str = "shadowed-name";
+pub const UNREACHABLE_CODE: &str = "unreachable-code";
+pub const CONSTANT_CONDITION: &str = "constant-condition";
+pub const SELF_COMPARISON: &str = "self-comparison";
+pub const EMPTY_BLOCK: &str = "empty-block";
+
+pub const RULES: [Rule; 7] = [
+    Rule {
+        id: UNUSED_LET,
+        description: "a let binding is never used",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: UNUSED_PARAMETER,
+        description: "a parameter is never used",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: SHADOWED_NAME,
+        description: "a binding hides an earlier one with the same name",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: UNREACHABLE_CODE,
+        description: "a statement follows a return, break or continue in the same block",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: CONSTANT_CONDITION,
+        description: "an if condition only involves literals",
+        severity: Severity::Warning,
+    },
+    Rule {
+        id: SELF_COMPARISON,
+        description: "both sides of a comparison are the same expression",
+        severity: Severity::Error,
+    },
+    Rule {
+        id: EMPTY_BLOCK,
+        description: "a block other than a function body has no statements",
+        severity: Severity::Warning,
+    },
+];
+
+// Comments starting with this suppress diagnostics. A trailing comment
+// covers its own line, a comment on a line of its own covers the next one.
+// It may be followed by rule IDs to only suppress those rules.
+pub const SUPPRESSION: &str = "lint-ignore";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    pub fix: Option<Fix>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {} [{}]",
+            self.span.line,
+            self.span.column,
+            self.severity.name(),
+            self.message,
+            self.rule
+        )
+    }
+}
+
+// Runs every rule that is not turned off over `program`, which was parsed
+// from `source` without errors and resolved into `resolution`. Names
+// starting with `_` are exempt from the unused rules. Diagnostics are
+// returned in source order.
+pub fn lint(
+    source: &str,
+    program: &Program,
+    resolution: &Resolution,
+    config: &Config,
+) -> Vec<Diagnostic> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut tokens = vec![];
+    loop {
+        let tok = lexer.next_token();
+        if tok.r#type == EOF {
+            break;
+        }
+        tokens.push(tok);
+    }
+    let comments = lexer.take_comments();
+    let suppressions = suppressions(&tokens, &comments);
+
+    let mut linter = Linter {
+        config,
+        source: source.chars().collect(),
+        tokens,
+        block_ends: resolution.scopes.iter().map(|s| (s.start, s.end)).collect(),
+        comments,
+        removable_lets: HashMap::new(),
+        diagnostics: vec![],
+    };
+
+    linter.check_statements(&program.statements);
+    linter.visit_program(program);
+    linter.check_bindings(resolution);
+
+    let mut diagnostics: Vec<Diagnostic> = linter
+        .diagnostics
+        .into_iter()
+        .filter(|d| !is_suppressed(&suppressions, d))
+        .collect();
+    diagnostics.sort_by_key(|d| d.span.start);
+    diagnostics
+}
+
+// Rule IDs suppressed on each line; an empty list suppresses every rule.
+fn suppressions(tokens: &[Token], comments: &[Comment]) -> HashMap<u32, Vec<String>> {
+    let mut suppressions = HashMap::new();
+    for comment in comments.iter() {
+        let text = comment.text.trim_start_matches('/').trim();
+        let rules = match text.strip_prefix(SUPPRESSION) {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest,
+            _ => continue,
+        };
+        let rules: Vec<String> = rules
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|r| !r.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let line = comment.span.line;
+        let trailing = tokens
+            .iter()
+            .any(|t| t.span.line == line && t.span.start < comment.span.start);
+        let target = if trailing { line } else { line + 1 };
+        suppressions.insert(target, rules);
+    }
+    suppressions
+}
+
+fn is_suppressed(suppressions: &HashMap<u32, Vec<String>>, diagnostic: &Diagnostic) -> bool {
+    suppressions
+        .get(&diagnostic.span.line)
+        .is_some_and(|rules| rules.is_empty() || rules.iter().any(|r| r == diagnostic.rule))
+}
+
+struct Linter<'a> {
+    config: &'a Config,
+    source: Vec<char>,
+    tokens: Vec<Token>,
+    // Offset of every block's `{` mapped to the offset of its `}`.
+    block_ends: HashMap<u32, u32>,
+    comments: Vec<Comment>,
+    // Start offset of the `let` keyword of every let statement whose value
+    // has no side effects and is not a function, keyed by the offset of the
+    // bound name.
+    removable_lets: HashMap<u32, u32>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, rule: &'static str, span: Span, message: String) {
+        self.report_with_fix(rule, span, message, None);
+    }
+
+    fn report_with_fix(
+        &mut self,
+        rule: &'static str,
+        span: Span,
+        message: String,
+        fix: Option<Fix>,
+    ) {
+        let severity = self.config.severity(rule);
+        if severity != Severity::Off {
+            self.diagnostics.push(Diagnostic {
+                rule,
+                severity,
+                message,
+                span,
+                fix,
+            });
+        }
+    }
+
+    fn check_bindings(&mut self, resolution: &Resolution) {
+        for (index, binding) in resolution.bindings.iter().enumerate() {
+            // Assigning to a binding does not use it.
+            let used = !resolution.references_to(index).is_empty();
+            if !used && !binding.name.starts_with('_') {
+                match binding.kind {
+                    BindingKind::Let => {
+                        // Top-level bindings may be meant for whoever
+                        // includes the file, so they are only reported, as
+                        // are bindings whose assignments would be left
+                        // without a declaration.
+                        let removable =
+                            binding.scope != 0 && resolution.writes_to(index).is_empty();
+                        let fix = self
+                            .removable_lets
+                            .get(&binding.span.start)
+                            .filter(|_| removable)
+                            .map(|&start| Fix {
+                                title: format!("remove unused binding {}", binding.name),
+                                edits: vec![self.statement_removal(start)],
+                            });
+                        self.report_with_fix(
+                            UNUSED_LET,
+                            binding.span,
+                            format!("let binding {} is never used", binding.name),
+                            fix,
+                        );
+                    }
+                    BindingKind::Parameter => self.report(
+                        UNUSED_PARAMETER,
+                        binding.span,
+                        format!("parameter {} is never used", binding.name),
+                    ),
+                    // Loops are often run for their effects alone.
+                    BindingKind::LoopVariable => {}
+                }
+            }
+
+            if let Some(shadowed) = shadowed_binding(resolution, index) {
+                // Repeated parameters are already a resolve error.
+                let duplicate_parameter = binding.kind == BindingKind::Parameter
+                    && shadowed.kind == BindingKind::Parameter
+                    && shadowed.scope == binding.scope;
+                if !duplicate_parameter {
+                    let message = format!(
+                        "{} shadows the binding declared on line {}",
+                        binding.name, shadowed.span.line
+                    );
+                    self.report(SHADOWED_NAME, binding.span, message);
+                }
+            }
+        }
+    }
+
+    // Reports the first statement after a return, break or continue, once
+    // per statement list.
+    fn check_statements(&mut self, statements: &[Box<dyn Statement>]) {
+        let exit = statements
+            .iter()
+            .enumerate()
+            .find_map(|(i, s)| exit_keyword(s.as_ref()).map(|keyword| (i, keyword)));
+        if let Some((i, keyword)) = exit {
+            if let Some(span) = statements
+                .get(i + 1)
+                .and_then(|s| statement_span(s.as_ref()))
+            {
+                self.report(
+                    UNREACHABLE_CODE,
+                    span,
+                    format!("statement is unreachable after {}", keyword),
+                );
+            }
+        }
+    }
+
+    fn check_empty_block(&mut self, block: &BlockStatement) {
+        let start = block.token.span.start;
+        let end = self.block_ends.get(&start).copied().unwrap_or(u32::MAX);
+        // A comment inside usually explains why the block is empty.
+        let has_comment = self
+            .comments
+            .iter()
+            .any(|c| start < c.span.start && c.span.start < end);
+        if block.statements.is_empty() && !has_comment {
+            self.report(EMPTY_BLOCK, block.token.span, "block is empty".to_string());
+        }
+    }
+
+    // Deletes the statement starting at `start` up to and including its
+    // `;`, along with its line when nothing else is on it.
+    fn statement_removal(&self, start: u32) -> TextEdit {
+        let first = self
+            .tokens
+            .iter()
+            .position(|t| t.span.start == start)
+            .expect("statement should start at a token");
+        let mut end = start;
+        let mut depth = 0;
+        for tok in self.tokens[first..].iter() {
+            match tok.r#type {
+                LPAREN | LBRACE => depth += 1,
+                RPAREN | RBRACE if depth == 0 => break,
+                RPAREN | RBRACE => depth -= 1,
+                _ => {}
+            }
+            end = tok.span.end;
+            if tok.r#type == SEMICOLON && depth == 0 {
+                break;
+            }
+        }
+
+        let is_blank = |ch: &char| *ch == ' ' || *ch == '\t' || *ch == '\r';
+        let mut line_start = start as usize;
+        while line_start > 0 && is_blank(&self.source[line_start - 1]) {
+            line_start -= 1;
+        }
+        let mut after = end as usize;
+        while after < self.source.len() && is_blank(&self.source[after]) {
+            after += 1;
+        }
+
+        let alone_before = line_start == 0 || self.source[line_start - 1] == '\n';
+        let alone_after = after == self.source.len() || self.source[after] == '\n';
+        if alone_before && alone_after {
+            let line_end = (after + 1).min(self.source.len());
+            TextEdit::delete(line_start as u32, line_end as u32)
+        } else {
+            TextEdit::delete(start, after as u32)
+        }
+    }
+
+    fn function_body(&mut self, body: &BlockStatement) {
+        self.check_statements(&body.statements);
+        walk_block_statement(self, body);
+    }
+}
+
+impl Visitor for Linter<'_> {
+    fn visit_let_statement(&mut self, statement: &LetStatement) {
+        // Functions are often kept around unused while they are written.
+        let value = statement.value.as_ref();
+        let is_function =
+            value.as_function_literal().is_some() || value.as_macro_literal().is_some();
+        if is_pure(value) && !is_function {
+            self.removable_lets
+                .insert(statement.name.token.span.start, statement.token.span.start);
+        }
+        walk_let_statement(self, statement);
+    }
+
+    fn visit_block_statement(&mut self, block: &BlockStatement) {
+        self.check_empty_block(block);
+        self.check_statements(&block.statements);
+        walk_block_statement(self, block);
+    }
+
+    fn visit_if_expression(&mut self, expression: &IfExpression) {
+        if is_constant(expression.condition.as_ref()) {
+            self.report(
+                CONSTANT_CONDITION,
+                expression_span(expression.condition.as_ref()),
+                "if condition is constant".to_string(),
+            );
+        }
+        walk_if_expression(self, expression);
+    }
+
+    fn visit_infix_expression(&mut self, expression: &InfixExpression) {
+        // Each side of `(x += 1) == (x += 1)` sees a different value.
+        let is_comparison = matches!(expression.token.r#type, EQ | NEQ | LT | GT);
+        if is_comparison
+            && expression.left.string() == expression.right.string()
+            && is_pure(expression.left.as_ref())
+        {
+            self.report(
+                SELF_COMPARISON,
+                expression.token.span,
+                format!(
+                    "comparing {} with itself is always {}",
+                    expression.left.string(),
+                    matches!(expression.token.r#type, EQ)
+                ),
+            );
+        }
+        walk_infix_expression(self, expression);
+    }
+
+    // Function and macro bodies may be empty on purpose.
+    fn visit_function_literal(&mut self, literal: &FunctionLiteral) {
+        self.function_body(&literal.body);
+    }
+
+    fn visit_macro_literal(&mut self, literal: &MacroLiteral) {
+        self.function_body(&literal.body);
+    }
+}
+
+// The closest earlier binding of the same name that is visible where
+// binding `index` is declared.
+fn shadowed_binding(resolution: &Resolution, index: usize) -> Option<&Binding> {
+    let binding = &resolution.bindings[index];
+    let mut scope = Some(binding.scope);
+    while let Some(current) = scope {
+        let earlier = resolution.bindings[..index]
+            .iter()
+            .rev()
+            .find(|b| b.scope == current && b.name == binding.name);
+        if earlier.is_some() {
+            return earlier;
+        }
+        scope = resolution.scopes[current].parent;
+    }
+    None
+}
+
+// Whether evaluating `expression` can have no effect besides producing its
+// value. Conditionals are left out since their blocks may hold anything,
+// assignments since they update a binding, and calls since the function
+// may do either.
+fn is_pure(expression: &dyn Expression) -> bool {
+    if let Some(prefix) = expression.as_prefix_expression() {
+        is_pure(prefix.right.as_ref())
+    } else if let Some(infix) = expression.as_infix_expression() {
+        is_pure(infix.left.as_ref()) && is_pure(infix.right.as_ref())
+    } else {
+        expression.as_if_expression().is_none()
+            && expression.as_assign_expression().is_none()
+            && expression.as_call_expression().is_none()
+    }
+}
+
+// Whether `expression` is built from literals only.
+fn is_constant(expression: &dyn Expression) -> bool {
+    if let Some(prefix) = expression.as_prefix_expression() {
+        is_constant(prefix.right.as_ref())
+    } else if let Some(infix) = expression.as_infix_expression() {
+        is_constant(infix.left.as_ref()) && is_constant(infix.right.as_ref())
+    } else {
+        expression.as_boolean().is_some()
+            || expression.as_integer_literal().is_some()
+            || expression.as_float_literal().is_some()
+    }
+}
+
+// The keyword of a statement that leaves its block, if it is one.
+fn exit_keyword(statement: &dyn Statement) -> Option<&'static str> {
+    if statement.as_return_statement().is_some() {
+        Some("return")
+    } else if statement.as_break_statement().is_some() {
+        Some("break")
+    } else if statement.as_continue_statement().is_some() {
+        Some("continue")
+    } else {
+        None
+    }
+}
+
+fn statement_span(statement: &dyn Statement) -> Option<Span> {
+    if let Some(stmt) = statement.as_let_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_return_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_expression_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_while_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_for_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_break_statement() {
+        Some(stmt.token.span)
+    } else if let Some(stmt) = statement.as_continue_statement() {
+        Some(stmt.token.span)
+    } else {
+        statement.as_block_statement().map(|b| b.token.span)
+    }
+}
+
+// Span of the first token of `expression`.
+fn expression_span(expression: &dyn Expression) -> Span {
+    if let Some(infix) = expression.as_infix_expression() {
+        expression_span(infix.left.as_ref())
+    } else if let Some(call) = expression.as_call_expression() {
+        expression_span(call.function.as_ref())
+    } else if let Some(expr) = expression.as_identifier_expression() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_integer_literal() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_float_literal() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_boolean() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_prefix_expression() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_if_expression() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_function_literal() {
+        expr.token.span
+    } else if let Some(expr) = expression.as_macro_literal() {
+        expr.token.span
+    } else {
+        Span::default()
+    }
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use crate::{fix::fix::apply, parser::parser::Parser, resolver::resolver::resolve};
+
+    use super::*;
+
+    fn lint_source(input: &str, config: &Config) -> Vec<String> {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?} should parse", input);
+        let resolution = resolve(input, &program);
+        lint(input, &program, &resolution, config)
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_rules() {
+        let tests = vec![
+            (
+                "let x = 1; let _y = 2;",
+                vec!["1:5: warning: let binding x is never used [unused-let]"],
+            ),
+            (
+                "let x = 1; x = 2; let y = 0; y += 1; let z = 0; z = z + 1; z;",
+                vec![
+                    "1:5: warning: let binding x is never used [unused-let]",
+                    "1:23: warning: let binding y is never used [unused-let]",
+                ],
+            ),
+            (
+                "let f = fn(a, _b) { 1 }; f;",
+                vec!["1:12: warning: parameter a is never used [unused-parameter]"],
+            ),
+            (
+                "let x = 1; let f = fn(x) { x }; f(x);",
+                vec!["1:23: warning: x shadows the binding declared on line 1 [shadowed-name]"],
+            ),
+            (
+                "let f = fn() { return 1; 2; 3 }; f;",
+                vec!["1:26: warning: statement is unreachable after return [unreachable-code]"],
+            ),
+            (
+                "let x = 1; while (x) { if (x) { break; x; }; continue; while (x) { x } };",
+                vec![
+                    "1:40: warning: statement is unreachable after break [unreachable-code]",
+                    "1:56: warning: statement is unreachable after continue [unreachable-code]",
+                ],
+            ),
+            (
+                "let x = 1; if (!(1 < 2)) { x }; if (x > 1) { x };",
+                vec!["1:16: warning: if condition is constant [constant-condition]"],
+            ),
+            (
+                "let x = 1; x == x; x != x; x < -x;",
+                vec![
+                    "1:14: error: comparing x with itself is always true [self-comparison]",
+                    "1:22: error: comparing x with itself is always false [self-comparison]",
+                ],
+            ),
+            (
+                "let x = 1; (x += 1) == (x += 1); let f = fn(a) { a }; f(x) != f(x);",
+                vec![],
+            ),
+            (
+                "let x = 1; if (x) {} else { // later\n}; let f = fn() {}; f;",
+                vec!["1:19: warning: block is empty [empty-block]"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let diagnostics = lint_source(input, &Config::default());
+            assert_eq!(
+                diagnostics, expected,
+                "wrong diagnostics for {:?}. got {:?}",
+                input, diagnostics
+            );
+        }
+    }
+
+    #[test]
+    fn test_unused_let_fixes() {
+        let tests = vec![
+            (
+                "let f = fn() {\n    let a = 1;\n    let b = 2;\n    b;\n}; f;",
+                "let f = fn() {\n    let b = 2;\n    b;\n}; f;",
+            ),
+            (
+                "let f = fn(x) { x; let a = -1;  // note\n}; f;",
+                "let f = fn(x) { x; // note\n}; f;",
+            ),
+            ("let f = fn() { let a = 1 }; f;", "let f = fn() { }; f;"),
+            (
+                "let f = fn() {\n    let a = fn(x) { x; };\n}; f;",
+                "let f = fn() {\n    let a = fn(x) { x; };\n}; f;",
+            ),
+            (
+                "let add = fn(a, b) { a + b }; let helper = fn(x) { add }; let config = 5;",
+                "let add = fn(a, b) { a + b }; let helper = fn(x) { add }; let config = 5;",
+            ),
+            (
+                "let x = 1; let a = if (x) { 1 };",
+                "let x = 1; let a = if (x) { 1 };",
+            ),
+            (
+                "let x = 1; let a = x += 1; x;",
+                "let x = 1; let a = x += 1; x;",
+            ),
+            (
+                "let f = fn() { let x = 1; x = 2; }; f;",
+                "let f = fn() { let x = 1; x = 2; }; f;",
+            ),
+            (
+                "let f = fn() { let a = f(); let b = 2; }; f;",
+                "let f = fn() { let a = f(); }; f;",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program();
+            let resolution = resolve(input, &program);
+            let diagnostics = lint(input, &program, &resolution, &Config::default());
+            let fixes: Vec<&Fix> = diagnostics.iter().filter_map(|d| d.fix.as_ref()).collect();
+
+            let (output, _) = apply(input, &fixes);
+            assert_eq!(
+                output, expected,
+                "wrong fix for {:?}. got {:?}",
+                input, output
+            );
+        }
+    }
+
+    #[test]
+    fn test_config_severities() {
+        let config = Config::parse("unused-let = error\nempty-block = off").unwrap();
+        assert_eq!(
+            lint_source("let x = 1; let y = 2; if (y) {};", &config),
+            vec!["1:5: error: let binding x is never used [unused-let]"]
+        );
+    }
+
+    #[test]
+    fn test_suppression_comments() {
+        let input = r"let a = 1; // lint-ignore
+// lint-ignore unused-let, shadowed-name
+let b = 2;
+let c = 3; // lint-ignore unused-parameter
+// lint-ignored is not a suppression
+let d = 4;";
+
+        assert_eq!(
+            lint_source(input, &Config::default()),
+            vec![
+                "4:5: warning: let binding c is never used [unused-let]",
+                "6:5: warning: let binding d is never used [unused-let]",
+            ]
+        );
+    }
+}
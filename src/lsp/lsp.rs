@@ -0,0 +1,834 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+    path::Path,
+};
+
+use crate::{
+    cst::{
+        cst::{parse, SyntaxElement, SyntaxNode, BLOCK_STATEMENT, COMMENT},
+        lower::lower,
+    },
+    fix::fix::Fix,
+    highlight::highlight::{classify, Category},
+    json::json::Json,
+    lint::{
+        config::Config,
+        lint::{lint, Diagnostic, Severity},
+    },
+    lsp::transport::{read_message, write_message},
+    parser::parser::ParseError,
+    resolver::resolver::{resolve, BindingKind, Resolution},
+    token::token::{Span, KEYWORDS},
+};
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// LSP enum values.
+const TEXT_DOCUMENT_SYNC_FULL: i64 = 1;
+const SEVERITY_ERROR: i64 = 1;
+const SEVERITY_WARNING: i64 = 2;
+const SYMBOL_FUNCTION: i64 = 12;
+const SYMBOL_VARIABLE: i64 = 13;
+const COMPLETION_FUNCTION: i64 = 3;
+const COMPLETION_VARIABLE: i64 = 6;
+const COMPLETION_KEYWORD: i64 = 14;
+const FOLDING_COMMENT: &str = "comment";
+const SEMANTIC_TOKEN_TYPES: [&str; 8] = [
+    "keyword",
+    "variable",
+    "parameter",
+    "function",
+    "number",
+    "operator",
+    "comment",
+    "type",
+];
+
+// Serves the language server protocol over `reader` and `writer` until the
+// client sends `exit` or closes the input. Returns the process exit code:
+// 0 after an orderly shutdown, 1 otherwise.
+pub fn serve<R: BufRead, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<i32> {
+    let mut server = Server::default();
+    while let Some(body) = read_message(reader)? {
+        let outgoing = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(msg) => vec![error_response(Json::Null, PARSE_ERROR, &msg)],
+        };
+        for message in outgoing {
+            write_message(writer, &message.to_string())?;
+        }
+        if let Some(code) = server.exit {
+            return Ok(code);
+        }
+    }
+    Ok(1)
+}
+
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    exit: Option<i32>,
+}
+
+impl Server {
+    // Handles one incoming message and returns the messages to send back:
+    // the response to a request, or notifications such as diagnostics.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let method = match message.get("method").and_then(Json::as_str) {
+            Some(method) => method,
+            // A response to a request we never send.
+            None if message.get("id").is_some() => return vec![],
+            None => {
+                return vec![error_response(
+                    Json::Null,
+                    INVALID_REQUEST,
+                    "message has no method",
+                )]
+            }
+        };
+
+        match message.get("id") {
+            Some(id) => {
+                let response = match self.request(method, params) {
+                    Ok(result) => Json::object(vec![
+                        ("jsonrpc", Json::string("2.0")),
+                        ("id", id.clone()),
+                        ("result", result),
+                    ]),
+                    Err((code, msg)) => error_response(id.clone(), code, &msg),
+                };
+                vec![response]
+            }
+            None => self.notification(method, params),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: &Json) -> Result<Json, (i64, String)> {
+        match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/references" => self.references(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/codeAction" => self.code_actions(params),
+            "textDocument/foldingRange" => {
+                let (_, document) = self.document(params)?;
+                Ok(Json::Array(document.folding_ranges()))
+            }
+            "textDocument/semanticTokens/full" => {
+                let (_, document) = self.document(params)?;
+                Ok(Json::object(vec![(
+                    "data",
+                    Json::Array(document.semantic_tokens()),
+                )]))
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .map(str::to_string);
+
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params
+                    .get("textDocument")
+                    .and_then(|d| d.get("text"))
+                    .and_then(Json::as_str)
+                    .unwrap_or_default();
+                let document = Document::new(text, &config_for(&uri));
+                self.documents.insert(uri.clone(), document);
+                vec![self.diagnostics(&uri)]
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // Full sync: the last change holds the whole new text.
+                let text = params
+                    .get("contentChanges")
+                    .and_then(Json::as_array)
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change.get("text"))
+                    .and_then(Json::as_str);
+                match text {
+                    Some(text) => {
+                        let document = Document::new(text, &config_for(&uri));
+                        self.documents.insert(uri.clone(), document);
+                        vec![self.diagnostics(&uri)]
+                    }
+                    None => vec![],
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+                vec![publish_diagnostics(&uri, vec![])]
+            }
+            ("exit", _) => {
+                self.exit = Some(if self.shutdown { 0 } else { 1 });
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    // Parse errors, or when there are none, the resolver's errors followed
+    // by the lints. Statements with parse errors are dropped, so their names
+    // would show up as undefined.
+    fn diagnostics(&self, uri: &str) -> Json {
+        let document = &self.documents[uri];
+        let diagnostic = |span: Span, severity: i64, message: String| {
+            Json::object(vec![
+                ("range", document.range(span)),
+                ("severity", Json::Int(severity)),
+                ("source", Json::string("monkey")),
+                ("message", Json::String(message)),
+            ])
+        };
+
+        let mut diagnostics: Vec<Json> = document
+            .errors
+            .iter()
+            .map(|e| diagnostic(e.span, SEVERITY_ERROR, e.message.clone()))
+            .collect();
+        if diagnostics.is_empty() {
+            for error in document.resolution.errors.iter() {
+                let message = match &error.suggestion {
+                    Some(suggestion) => format!("{}; did you mean {}?", error.message, suggestion),
+                    None => error.message.clone(),
+                };
+                diagnostics.push(diagnostic(error.span, SEVERITY_ERROR, message));
+            }
+            for lint in document.lints.iter() {
+                let severity = match lint.severity {
+                    Severity::Error => SEVERITY_ERROR,
+                    _ => SEVERITY_WARNING,
+                };
+                let message = format!("{} [{}]", lint.message, lint.rule);
+                diagnostics.push(diagnostic(lint.span, severity, message));
+            }
+        }
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    // Quick fixes for the diagnostics that touch the requested range.
+    fn code_actions(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (uri, document) = self.document(params)?;
+        let range = params
+            .get("range")
+            .ok_or((INVALID_PARAMS, "missing range".to_string()))?;
+        let (start, end) = match (range.get("start"), range.get("end")) {
+            (Some(start), Some(end)) => (document.offset(start), document.offset(end)),
+            _ => (None, None),
+        };
+        let (start, end) = start
+            .zip(end)
+            .ok_or((INVALID_PARAMS, "invalid range".to_string()))?;
+
+        let actions = document
+            .fixes()
+            .into_iter()
+            .filter(|(span, _)| span.start <= end && start <= span.end)
+            .map(|(_, fix)| {
+                let edits = fix
+                    .edits
+                    .iter()
+                    .map(|edit| {
+                        Json::object(vec![
+                            (
+                                "range",
+                                Json::object(vec![
+                                    ("start", document.position(edit.start)),
+                                    ("end", document.position(edit.end)),
+                                ]),
+                            ),
+                            ("newText", Json::string(&edit.text)),
+                        ])
+                    })
+                    .collect();
+                Json::object(vec![
+                    ("title", Json::string(&fix.title)),
+                    ("kind", Json::string("quickfix")),
+                    (
+                        "edit",
+                        Json::object(vec![(
+                            "changes",
+                            Json::object(vec![(uri, Json::Array(edits))]),
+                        )]),
+                    ),
+                ])
+            })
+            .collect();
+        Ok(Json::Array(actions))
+    }
+
+    fn hover(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (_, document, offset) = self.document_position(params)?;
+        let (index, span) = match document.resolution.binding_at(offset) {
+            Some(found) => found,
+            None => return Ok(Json::Null),
+        };
+
+        let binding = &document.resolution.bindings[index];
+        let kind = match binding.kind {
+            BindingKind::Let => "let binding",
+            BindingKind::Parameter => "parameter",
+            BindingKind::LoopVariable => "loop variable",
+        };
+        let value = format!(
+            "```monkey\n{}\n```\n{} `{}`, declared on line {}",
+            document.line_text(binding.span.line),
+            kind,
+            binding.name,
+            binding.span.line
+        );
+
+        Ok(Json::object(vec![
+            (
+                "contents",
+                Json::object(vec![
+                    ("kind", Json::string("markdown")),
+                    ("value", Json::String(value)),
+                ]),
+            ),
+            ("range", document.range(span)),
+        ]))
+    }
+
+    fn definition(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (uri, document, offset) = self.document_position(params)?;
+        Ok(match document.resolution.binding_at(offset) {
+            Some((index, _)) => location(uri, document, document.resolution.bindings[index].span),
+            None => Json::Null,
+        })
+    }
+
+    fn references(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (uri, document, offset) = self.document_position(params)?;
+        let index = match document.resolution.binding_at(offset) {
+            Some((index, _)) => index,
+            None => return Ok(Json::Null),
+        };
+
+        let include_declaration = params
+            .get("context")
+            .and_then(|c| c.get("includeDeclaration"))
+            .and_then(Json::as_bool)
+            .unwrap_or(false);
+        // Assignments count with the declaration, as places the binding is
+        // given a value.
+        let mut spans = vec![];
+        if include_declaration {
+            spans.push(document.resolution.bindings[index].span);
+            spans.extend(document.resolution.writes_to(index));
+        }
+        spans.extend(document.resolution.references_to(index));
+        spans.sort_by_key(|span| span.start);
+
+        Ok(Json::Array(
+            spans
+                .into_iter()
+                .map(|span| location(uri, document, span))
+                .collect(),
+        ))
+    }
+
+    fn document_symbols(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (_, document) = self.document(params)?;
+        Ok(Json::Array(document.symbols(0)))
+    }
+
+    fn completion(&self, params: &Json) -> Result<Json, (i64, String)> {
+        let (_, document, offset) = self.document_position(params)?;
+
+        let mut items = vec![];
+        for binding in document.resolution.visible_at(offset) {
+            let (kind, detail) = match binding.kind {
+                BindingKind::Let if binding.is_function => (COMPLETION_FUNCTION, "let"),
+                BindingKind::Let => (COMPLETION_VARIABLE, "let"),
+                BindingKind::Parameter => (COMPLETION_VARIABLE, "parameter"),
+                BindingKind::LoopVariable => (COMPLETION_VARIABLE, "loop variable"),
+            };
+            items.push(Json::object(vec![
+                ("label", Json::string(&binding.name)),
+                ("kind", Json::Int(kind)),
+                ("detail", Json::string(detail)),
+            ]));
+        }
+        for (keyword, _) in KEYWORDS {
+            items.push(Json::object(vec![
+                ("label", Json::string(keyword)),
+                ("kind", Json::Int(COMPLETION_KEYWORD)),
+            ]));
+        }
+        Ok(Json::Array(items))
+    }
+
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a Document), (i64, String)> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .ok_or((INVALID_PARAMS, "missing textDocument.uri".to_string()))?;
+        let document = self
+            .documents
+            .get(uri)
+            .ok_or_else(|| (INVALID_PARAMS, format!("unknown document {}", uri)))?;
+        Ok((uri, document))
+    }
+
+    fn document_position<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a Document, u32), (i64, String)> {
+        let (uri, document) = self.document(params)?;
+        let offset = params
+            .get("position")
+            .and_then(|p| document.offset(p))
+            .ok_or((INVALID_PARAMS, "missing position".to_string()))?;
+        Ok((uri, document, offset))
+    }
+}
+
+// An open text document with the results of analysing it.
+struct Document {
+    chars: Vec<char>,
+    // Offset of the first character of every line.
+    line_starts: Vec<u32>,
+    errors: Vec<ParseError>,
+    tree: SyntaxNode,
+    resolution: Resolution,
+    // Empty when there are parse errors.
+    lints: Vec<Diagnostic>,
+}
+
+impl Document {
+    fn new(text: &str, config: &Config) -> Self {
+        let parse = parse(text);
+        let program = lower(&parse.root);
+        let resolution = resolve(text, &program);
+        let lints = if parse.errors.is_empty() {
+            lint(text, &program, &resolution, config)
+        } else {
+            vec![]
+        };
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut line_starts = vec![0];
+        for (i, ch) in chars.iter().enumerate() {
+            if *ch == '\n' {
+                line_starts.push(i as u32 + 1);
+            }
+        }
+
+        Document {
+            chars,
+            line_starts,
+            errors: parse.errors,
+            tree: parse.root,
+            resolution,
+            lints,
+        }
+    }
+
+    // The fixes of all parse errors and lints, with the span of the
+    // diagnostic each belongs to.
+    fn fixes(&self) -> Vec<(Span, &Fix)> {
+        let errors = self
+            .errors
+            .iter()
+            .filter_map(|e| Some((e.span, e.fix.as_ref()?)));
+        let lints = self
+            .lints
+            .iter()
+            .filter_map(|d| Some((d.span, d.fix.as_ref()?)));
+        errors.chain(lints).collect()
+    }
+
+    // LSP positions count UTF-16 code units from the start of the line,
+    // while spans count characters from the start of the document.
+    fn position(&self, offset: u32) -> Json {
+        let (line, character) = self.line_character(offset);
+        Json::object(vec![
+            ("line", Json::Int(line as i64)),
+            ("character", Json::Int(character as i64)),
+        ])
+    }
+
+    fn line_character(&self, offset: u32) -> (usize, usize) {
+        let offset = offset.min(self.chars.len() as u32);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line] as usize;
+        (line, utf16_len(&self.chars[start..offset as usize]))
+    }
+
+    fn offset(&self, position: &Json) -> Option<u32> {
+        let line = position.get("line")?.as_i64()? as usize;
+        let character = position.get("character")?.as_i64()? as usize;
+        let mut offset = match self.line_starts.get(line) {
+            Some(start) => *start as usize,
+            None => return Some(self.chars.len() as u32),
+        };
+
+        let mut units = 0;
+        while offset < self.chars.len() && self.chars[offset] != '\n' && units < character {
+            units += self.chars[offset].len_utf16();
+            offset += 1;
+        }
+        Some(offset as u32)
+    }
+
+    fn range(&self, span: Span) -> Json {
+        Json::object(vec![
+            ("start", self.position(span.start)),
+            ("end", self.position(span.end)),
+        ])
+    }
+
+    // Highlights encoded as LSP semantic tokens: five integers per token,
+    // holding the line and start delta from the previous token, the length,
+    // the index into SEMANTIC_TOKEN_TYPES and an empty modifier set.
+    fn semantic_tokens(&self) -> Vec<Json> {
+        let text: String = self.chars.iter().collect();
+        let mut data = vec![];
+        let (mut previous_line, mut previous_start) = (0, 0);
+        for highlight in classify(&text) {
+            let token_type = match semantic_token_type(highlight.category) {
+                Some(token_type) => token_type,
+                None => continue,
+            };
+            let (line, start) = self.line_character(highlight.span.start);
+            let span = &self.chars[highlight.span.start as usize..highlight.span.end as usize];
+            let delta_start = if line == previous_line {
+                start - previous_start
+            } else {
+                start
+            };
+            for value in [
+                line - previous_line,
+                delta_start,
+                utf16_len(span),
+                token_type,
+                0,
+            ] {
+                data.push(Json::Int(value as i64));
+            }
+            (previous_line, previous_start) = (line, start);
+        }
+        data
+    }
+
+    // Blocks spanning several lines fold up to the line before their `}`,
+    // and comments on consecutive lines fold into the first of them.
+    fn folding_ranges(&self) -> Vec<Json> {
+        let mut folds = vec![];
+        let mut comments = None;
+        fold_node(&self.tree, &mut folds, &mut comments);
+        folds.extend(comments);
+        folds.sort_by_key(|(start, _, _)| *start);
+
+        folds
+            .into_iter()
+            .filter(|(start, end, _)| end > start)
+            .map(|(start, end, kind)| {
+                let mut fields = vec![
+                    ("startLine", Json::Int(start as i64)),
+                    ("endLine", Json::Int(end as i64)),
+                ];
+                if let Some(kind) = kind {
+                    fields.push(("kind", Json::string(kind)));
+                }
+                Json::object(fields)
+            })
+            .collect()
+    }
+
+    // The trimmed text of the 1-based `line`.
+    fn line_text(&self, line: u32) -> String {
+        let start = self.line_starts[line as usize - 1] as usize;
+        let end = self
+            .line_starts
+            .get(line as usize)
+            .map_or(self.chars.len(), |&next| next as usize);
+        let text: String = self.chars[start..end].iter().collect();
+        text.trim().to_string()
+    }
+
+    // The let bindings declared in `scope`, each with the bindings of the
+    // function assigned to it as children.
+    fn symbols(&self, scope: usize) -> Vec<Json> {
+        let resolution = &self.resolution;
+        let mut symbols = vec![];
+        for (index, binding) in resolution.bindings.iter().enumerate() {
+            if binding.scope != scope || binding.kind != BindingKind::Let {
+                continue;
+            }
+            let children: Vec<Json> = resolution
+                .scopes
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.owner == Some(index))
+                .flat_map(|(child, _)| self.symbols(child))
+                .collect();
+            let kind = if binding.is_function {
+                SYMBOL_FUNCTION
+            } else {
+                SYMBOL_VARIABLE
+            };
+            symbols.push(Json::object(vec![
+                ("name", Json::string(&binding.name)),
+                ("kind", Json::Int(kind)),
+                ("range", self.range(binding.span)),
+                ("selectionRange", self.range(binding.span)),
+                ("children", Json::Array(children)),
+            ]));
+        }
+        symbols
+    }
+}
+
+type Fold = (u32, u32, Option<&'static str>);
+
+// Collects the folds of `node` as 0-based line ranges. `comments` is the
+// run of comments being extended; any other token ends it.
+fn fold_node(node: &SyntaxNode, folds: &mut Vec<Fold>, comments: &mut Option<Fold>) {
+    if node.kind == BLOCK_STATEMENT {
+        if let (Some(first), Some(last)) = (node.first_token(), node.last_token()) {
+            folds.push((first.span.line - 1, last.span.line.saturating_sub(2), None));
+        }
+    }
+    for child in node.children.iter() {
+        match child {
+            SyntaxElement::Node(child) => fold_node(child, folds, comments),
+            SyntaxElement::Token(token) if token.kind == COMMENT => {
+                let line = token.span.line - 1;
+                match comments {
+                    Some((_, end, _)) if *end + 1 == line => *end = line,
+                    _ => {
+                        folds.extend(comments.take());
+                        *comments = Some((line, line, Some(FOLDING_COMMENT)));
+                    }
+                }
+            }
+            SyntaxElement::Token(token) if !token.is_trivia() => folds.extend(comments.take()),
+            SyntaxElement::Token(_) => {}
+        }
+    }
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                ("textDocumentSync", Json::Int(TEXT_DOCUMENT_SYNC_FULL)),
+                ("hoverProvider", Json::Bool(true)),
+                ("definitionProvider", Json::Bool(true)),
+                ("referencesProvider", Json::Bool(true)),
+                ("documentSymbolProvider", Json::Bool(true)),
+                ("completionProvider", Json::object(vec![])),
+                ("codeActionProvider", Json::Bool(true)),
+                ("foldingRangeProvider", Json::Bool(true)),
+                (
+                    "semanticTokensProvider",
+                    Json::object(vec![
+                        (
+                            "legend",
+                            Json::object(vec![
+                                (
+                                    "tokenTypes",
+                                    Json::Array(
+                                        SEMANTIC_TOKEN_TYPES
+                                            .iter()
+                                            .map(|t| Json::string(t))
+                                            .collect(),
+                                    ),
+                                ),
+                                ("tokenModifiers", Json::Array(vec![])),
+                            ]),
+                        ),
+                        ("full", Json::Bool(true)),
+                    ]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            Json::object(vec![("name", Json::string("monkey-lsp"))]),
+        ),
+    ])
+}
+
+// Plain identifiers are variables; punctuation has no standard token type
+// and is left to the editor's grammar.
+fn semantic_token_type(category: Category) -> Option<usize> {
+    let name = match category {
+        Category::Identifier => "variable",
+        Category::Punctuation => return None,
+        category => category.name(),
+    };
+    SEMANTIC_TOKEN_TYPES.iter().position(|t| *t == name)
+}
+
+// The lint config for a document: the project file found from a `file://`
+// URI's directory, or the defaults.
+fn config_for(uri: &str) -> Config {
+    uri.strip_prefix("file://")
+        .and_then(|path| Path::new(path).parent())
+        .and_then(|dir| Config::load(dir).ok())
+        .unwrap_or_default()
+}
+
+fn utf16_len(chars: &[char]) -> usize {
+    chars.iter().map(|ch| ch.len_utf16()).sum()
+}
+
+fn location(uri: &str, document: &Document, span: Span) -> Json {
+    Json::object(vec![
+        ("uri", Json::string(uri)),
+        ("range", document.range(span)),
+    ])
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("method", Json::string("textDocument/publishDiagnostics")),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", Json::string(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ),
+    ])
+}
+
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", Json::string("2.0")),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![
+                ("code", Json::Int(code)),
+                ("message", Json::string(message)),
+            ]),
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod lsp_tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let document = Document::new("let a = 1;\n// é😀\nlet b = a;", &Config::default());
+
+        let tests = vec![(0, 0, 0), (11, 1, 0), (15, 1, 4), (16, 1, 6), (17, 2, 0)];
+        for (offset, line, character) in tests {
+            let position = document.position(offset);
+            assert_eq!(
+                position.to_string(),
+                format!(r#"{{"line":{},"character":{}}}"#, line, character),
+                "wrong position for offset {}",
+                offset
+            );
+            assert_eq!(
+                document.offset(&position),
+                Some(offset),
+                "wrong offset for {}",
+                position
+            );
+        }
+
+        // Characters past the end of a line clamp to the line break.
+        let position = Json::object(vec![("line", Json::Int(0)), ("character", Json::Int(99))]);
+        assert_eq!(document.offset(&position), Some(10));
+    }
+
+    #[test]
+    fn test_semantic_tokens() {
+        let document = Document::new("let f = fn(x) {\n  x; // é\n};", &Config::default());
+        let data: Vec<i64> = document
+            .semantic_tokens()
+            .iter()
+            .filter_map(Json::as_i64)
+            .collect();
+        assert_eq!(
+            data.chunks(5).collect::<Vec<_>>(),
+            vec![
+                &[0, 0, 3, 0, 0][..],
+                &[0, 4, 1, 3, 0],
+                &[0, 2, 1, 5, 0],
+                &[0, 2, 2, 0, 0],
+                &[0, 3, 1, 2, 0],
+                &[1, 2, 1, 2, 0],
+                &[0, 3, 4, 6, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbols() {
+        let document = Document::new(
+            "let f = fn(x) { let y = x; y; };\nlet z = 1;",
+            &Config::default(),
+        );
+        let names: Vec<String> = document
+            .symbols(0)
+            .iter()
+            .map(|s| {
+                let children: Vec<&str> = s
+                    .get("children")
+                    .and_then(Json::as_array)
+                    .unwrap()
+                    .iter()
+                    .filter_map(|c| c.get("name").and_then(Json::as_str))
+                    .collect();
+                format!(
+                    "{} {} {:?}",
+                    s.get("name").and_then(Json::as_str).unwrap(),
+                    s.get("kind").and_then(Json::as_i64).unwrap(),
+                    children
+                )
+            })
+            .collect();
+        assert_eq!(names, vec![r#"f 12 ["y"]"#, r#"z 13 []"#]);
+    }
+
+    #[test]
+    fn test_folding_ranges() {
+        let document = Document::new(
+            "// one\n// two\nlet f = fn(x) {\n  if (x) { 1 } else {\n    2\n  }\n};\nx; // three",
+            &Config::default(),
+        );
+        let folds: Vec<String> = document
+            .folding_ranges()
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(
+            folds,
+            vec![
+                r#"{"startLine":0,"endLine":1,"kind":"comment"}"#,
+                r#"{"startLine":2,"endLine":5}"#,
+                r#"{"startLine":3,"endLine":4}"#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,2 @@
+pub mod lsp;
+pub mod transport;
@@ -0,0 +1,92 @@
+use std::io::{self, BufRead, Write};
+
+// Reads one `Content-Length` framed message body. Returns None once the
+// input is closed between messages.
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return match content_length {
+                None => Ok(None),
+                Some(_) => Err(invalid_data("input closed inside message header")),
+            };
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let length = value
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| invalid_data("invalid Content-Length header"))?;
+                content_length = Some(length);
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| invalid_data("missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| invalid_data("message body is not UTF-8"))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, body: &str) -> io::Result<()> {
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod transport_tests {
+    use super::*;
+
+    #[test]
+    fn test_read_write_message() {
+        let mut out = vec![];
+        write_message(&mut out, r#"{"a":"é"}"#).unwrap();
+        write_message(&mut out, "{}").unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "Content-Length: 10\r\n\r\n{\"a\":\"é\"}Content-Length: 2\r\n\r\n{}"
+        );
+
+        let mut reader = out.as_slice();
+        assert_eq!(
+            read_message(&mut reader).unwrap().as_deref(),
+            Some(r#"{"a":"é"}"#)
+        );
+        assert_eq!(read_message(&mut reader).unwrap().as_deref(), Some("{}"));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_message_errors() {
+        let tests = vec![
+            ("Content-Type: x\r\n\r\n{}", "missing Content-Length header"),
+            (
+                "Content-Length: two\r\n\r\n{}",
+                "invalid Content-Length header",
+            ),
+            (
+                "Content-Length: 2\r\n",
+                "input closed inside message header",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut reader = input.as_bytes();
+            match read_message(&mut reader) {
+                Err(err) => assert_eq!(err.to_string(), expected),
+                Ok(body) => panic!("expected error for {:?}, got {:?}", input, body),
+            }
+        }
+    }
+}
@@ -1,13 +1,39 @@
+use std::{env, process::ExitCode};
+
 use crate::repl::repl::start;
 
 mod ast;
+mod cli;
+mod cst;
+mod export;
+mod fix;
+mod formatter;
+mod highlight;
+mod json;
 mod lexer;
+mod lint;
+mod lsp;
 mod parser;
 mod repl;
+mod resolver;
 mod token;
+mod types;
 
-fn main() {
-    println!("Hello! This is the Monkey programming language!");
-    println!("Feel free to type in commands");
-    start()
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("ast") => cli::ast::run(&args[1..]),
+        Some("check") => cli::check::run(&args[1..]),
+        Some("fmt") => cli::fmt::run(&args[1..]),
+        Some("highlight") => cli::highlight::run(&args[1..]),
+        Some("lint") => cli::lint::run(&args[1..]),
+        Some("lsp") => cli::lsp::run(&args[1..]),
+        Some("tokens") => cli::tokens::run(&args[1..]),
+        _ => {
+            println!("Hello! This is the Monkey programming language!");
+            println!("Feel free to type in commands");
+            start();
+            ExitCode::SUCCESS
+        }
+    }
 }
@@ -1,37 +1,100 @@
 use core::panic;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use crate::{
     ast::ast::{
-        Expression, ExpressionStatement, Identifier, LetStatement, Program, ReturnStatement,
-        Statement,
+        AssignExpression, BlockStatement, Boolean, BreakStatement, CallExpression,
+        ContinueStatement, Expression, ExpressionStatement, FloatLiteral, ForStatement,
+        FunctionLiteral, Identifier, IfExpression, InfixExpression, IntegerLiteral, LetStatement,
+        MacroLiteral, PrefixExpression, Program, ReturnStatement, Statement, TypeExpression,
+        WhileStatement,
     },
+    cst::cst::{
+        Event, SyntaxKind, ARGUMENT_LIST, ASSIGN_EXPRESSION, BLOCK_STATEMENT, BOOLEAN_LITERAL,
+        BREAK_STATEMENT, CALL_EXPRESSION, CONTINUE_STATEMENT, EMPTY_STATEMENT,
+        EXPRESSION_STATEMENT, FLOAT_LITERAL, FOR_STATEMENT, FUNCTION_LITERAL, FUNCTION_TYPE,
+        IDENTIFIER, IF_EXPRESSION, INFIX_EXPRESSION, INTEGER_LITERAL, LET_STATEMENT, MACRO_LITERAL,
+        NAMED_TYPE, PARAMETER_LIST, PAREN_EXPRESSION, PREFIX_EXPRESSION, RETURN_STATEMENT,
+        WHILE_STATEMENT,
+    },
+    fix::fix::{Fix, TextEdit},
     lexer::lexer::Lexer,
-    token::token::{Token, TokenType, ASSIGN, EOF, IDENT, LET, RETURN, SEMICOLON},
+    token::token::{
+        Span, Token, TokenType, ARROW, ASSIGN, ASTERISK, ASTERISK_ASSIGN, BANG, BREAK, COLON,
+        COMMA, CONTINUE, ELSE, EOF, EQ, FALSE, FLOAT, FOR, FUNCTION, GT, IDENT, IF, ILLEGAL, IN,
+        INT, LBRACE, LET, LPAREN, LT, MACRO, MINUS, MINUS_ASSIGN, NEQ, PLUS, PLUS_ASSIGN, RBRACE,
+        RETURN, RPAREN, SEMICOLON, SLASH, SLASH_ASSIGN, TRUE, WHILE,
+    },
 };
 
-type PrefixParseFn = fn(&mut Parser) -> Box<dyn Expression>;
-type InfixParseFn = fn(&mut Parser, dyn Expression) -> Box<dyn Expression>;
+type PrefixParseFn = fn(&mut Parser) -> Option<Box<dyn Expression>>;
+type InfixParseFn = fn(&mut Parser, Box<dyn Expression>) -> Option<Box<dyn Expression>>;
+
+pub const LOWEST: u8 = 1;
+pub const ASSIGNMENT: u8 = 2;
+const EQUALS: u8 = 3;
+const LESSGREATER: u8 = 4;
+const SUM: u8 = 5;
+const PRODUCT: u8 = 6;
+pub const PREFIX: u8 = 7;
+const CALL: u8 = 8;
+
+pub const ASSIGNMENT_OPERATORS: [TokenType; 5] = [
+    ASSIGN,
+    PLUS_ASSIGN,
+    MINUS_ASSIGN,
+    ASTERISK_ASSIGN,
+    SLASH_ASSIGN,
+];
+
+pub fn precedence_of(token_type: TokenType) -> u8 {
+    match token_type {
+        ASSIGN | PLUS_ASSIGN | MINUS_ASSIGN | ASTERISK_ASSIGN | SLASH_ASSIGN => ASSIGNMENT,
+        EQ | NEQ => EQUALS,
+        LT | GT => LESSGREATER,
+        PLUS | MINUS => SUM,
+        SLASH | ASTERISK => PRODUCT,
+        LPAREN => CALL,
+        _ => LOWEST,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+    pub fix: Option<Fix>,
+}
 
-const LOWEST: u8 = 1;
-const EQUALS: u8 = 2;
-const LESSGREATER: u8 = 3;
-const SUM: u8 = 4;
-const PRODUCT: u8 = 5;
-const PREFIX: u8 = 6;
-const CALL: u8 = 7;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
 
-struct Parser {
+pub struct Parser {
     lexer: Box<Lexer>,
     cur_token: Option<Token>,
     peek_token: Option<Token>,
-    errors: Vec<String>,
-    prefix_parse_fns: HashMap<TokenType, PrefixParseFn>,
-    infix_parse_fns: HashMap<TokenType, InfixParseFn>,
+    errors: Vec<ParseError>,
+    // Each parse function with the kind of syntax tree node it builds.
+    prefix_parse_fns: HashMap<TokenType, (SyntaxKind, PrefixParseFn)>,
+    infix_parse_fns: HashMap<TokenType, (SyntaxKind, InfixParseFn)>,
+    // Number of loops around the current token within the innermost
+    // function, where `break` and `continue` are allowed.
+    loop_depth: usize,
+    // Tokens taken from the lexer so far, two more than the index of the
+    // current token.
+    tokens_read: usize,
+    events: Vec<Event>,
 }
 
 impl Parser {
-    fn new(lexer: Lexer) -> Self {
+    pub fn new(lexer: Lexer) -> Self {
         let mut parser = Parser {
             lexer: Box::new(lexer),
             cur_token: None,
@@ -39,22 +102,56 @@ impl Parser {
             errors: vec![],
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
+            loop_depth: 0,
+            tokens_read: 0,
+            events: vec![],
         };
 
         parser.next_token();
         parser.next_token();
 
-        parser.register_prefix(IDENT, Parser::parse_identifier);
+        parser.register_prefix(IDENT, IDENTIFIER, Parser::parse_identifier);
+        parser.register_prefix(INT, INTEGER_LITERAL, Parser::parse_integer_literal);
+        parser.register_prefix(FLOAT, FLOAT_LITERAL, Parser::parse_float_literal);
+        parser.register_prefix(FUNCTION, FUNCTION_LITERAL, Parser::parse_function_literal);
+        parser.register_prefix(MACRO, MACRO_LITERAL, Parser::parse_macro_literal);
+        parser.register_prefix(TRUE, BOOLEAN_LITERAL, Parser::parse_boolean);
+        parser.register_prefix(FALSE, BOOLEAN_LITERAL, Parser::parse_boolean);
+        parser.register_prefix(BANG, PREFIX_EXPRESSION, Parser::parse_prefix_expression);
+        parser.register_prefix(MINUS, PREFIX_EXPRESSION, Parser::parse_prefix_expression);
+        parser.register_prefix(LPAREN, PAREN_EXPRESSION, Parser::parse_grouped_expression);
+        parser.register_prefix(IF, IF_EXPRESSION, Parser::parse_if_expression);
+
+        for operator in [PLUS, MINUS, SLASH, ASTERISK, EQ, NEQ, LT, GT] {
+            parser.register_infix(operator, INFIX_EXPRESSION, Parser::parse_infix_expression);
+        }
+        for operator in ASSIGNMENT_OPERATORS {
+            parser.register_infix(operator, ASSIGN_EXPRESSION, Parser::parse_assign_expression);
+        }
+        parser.register_infix(LPAREN, CALL_EXPRESSION, Parser::parse_call_expression);
 
         parser
     }
 
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    // The syntax tree events recorded by `parse_program`.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
     fn next_token(&mut self) {
         self.cur_token = self.peek_token.take();
         self.peek_token = Some(self.lexer.next_token());
+        self.tokens_read += 1;
+        for error in self.lexer.take_errors() {
+            self.error(error.message, error.span);
+        }
     }
 
-    fn parse_program(&mut self) -> Program {
+    pub fn parse_program(&mut self) -> Program {
         let mut program = Program { statements: vec![] };
 
         while !self.cur_token_is(EOF) {
@@ -73,11 +170,99 @@ impl Parser {
             Some(ct) => ct,
             None => panic!("cur_token is none"),
         };
-        match current_token.r#type {
-            LET => Some(self.parse_let_statement()?),
-            RETURN => Some(self.parse_return_statement()?),
-            _ => Some(self.parse_expression_statement()?),
+        let r#type = current_token.r#type;
+        let kind = match r#type {
+            LET => LET_STATEMENT,
+            RETURN => RETURN_STATEMENT,
+            WHILE => WHILE_STATEMENT,
+            FOR => FOR_STATEMENT,
+            BREAK => BREAK_STATEMENT,
+            CONTINUE => CONTINUE_STATEMENT,
+            SEMICOLON => {
+                self.node(EMPTY_STATEMENT, |_| Some(()));
+                return None;
+            }
+            _ => EXPRESSION_STATEMENT,
+        };
+
+        let errors = self.errors.len();
+        self.node(kind, |parser| {
+            let statement = match r#type {
+                LET => parser
+                    .parse_let_statement()
+                    .map(|s| s as Box<dyn Statement>),
+                RETURN => parser.parse_return_statement().map(|s| s as _),
+                WHILE => parser.parse_while_statement().map(|s| s as _),
+                FOR => parser.parse_for_statement().map(|s| s as _),
+                BREAK | CONTINUE => parser.parse_loop_control(),
+                _ => parser.parse_expression_statement().map(|s| s as _),
+            };
+            if statement.is_none() && parser.errors.len() > errors {
+                parser.skip_statement();
+            }
+            statement
+        })
+    }
+
+    // Moves to the last token of a statement that failed to parse: its
+    // `;` outside of any braces, or the token before the `}` that closes
+    // the enclosing block. Parsing the rest of the statement as statements
+    // of its own would only bury the first error under follow-up ones.
+    fn skip_statement(&mut self) {
+        let mut depth = 0;
+        loop {
+            match self.cur_token.as_ref().map(|ct| ct.r#type) {
+                Some(SEMICOLON) if depth == 0 => return,
+                Some(RBRACE) if depth == 0 => return,
+                Some(EOF) => return,
+                Some(LBRACE) => depth += 1,
+                Some(RBRACE) => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 && (self.peek_token_is(RBRACE) || self.peek_token_is(EOF)) {
+                return;
+            }
+            self.next_token();
+        }
+    }
+
+    // Index of the current token, and of the next event.
+    fn checkpoint(&self) -> (usize, usize) {
+        (self.tokens_read - 2, self.events.len())
+    }
+
+    // Records a syntax tree node of `kind` around `parse`, starting at the
+    // current token.
+    fn node<T>(
+        &mut self,
+        kind: SyntaxKind,
+        parse: impl FnOnce(&mut Parser) -> Option<T>,
+    ) -> Option<T> {
+        let checkpoint = self.checkpoint();
+        self.node_at(checkpoint, kind, parse)
+    }
+
+    // Like `node`, but starts the node at an earlier checkpoint, so that it
+    // contains the nodes recorded since then.
+    fn node_at<T>(
+        &mut self,
+        (token, event): (usize, usize),
+        kind: SyntaxKind,
+        parse: impl FnOnce(&mut Parser) -> Option<T>,
+    ) -> Option<T> {
+        self.events.insert(event, Event::Start { kind, token });
+        let result = parse(self);
+        // A node that failed on a `}` did not consume it; it closes an
+        // enclosing block.
+        let mut end = self.tokens_read - 2;
+        if result.is_none() && self.cur_token_is(RBRACE) && end > token {
+            end -= 1;
         }
+        self.events.push(Event::Finish {
+            token: end,
+            ok: result.is_some(),
+        });
+        result
     }
 
     fn parse_let_statement(&mut self) -> Option<Box<LetStatement>> {
@@ -102,18 +287,21 @@ impl Parser {
             token: identifier_token.clone(),
             value: identifier_token.literal,
         };
+        let r#type = self.parse_annotation(COLON)?;
 
         if !self.expect_peek(ASSIGN) {
             return None;
         }
 
-        while !self.cur_token_is(SEMICOLON) {
-            self.next_token();
-        }
+        self.next_token();
+        let value = self.parse_expression(LOWEST);
+        self.end_statement(value.is_some());
 
         Some(Box::new(LetStatement {
             token: let_token,
             name: Box::new(identifier),
+            r#type,
+            value: value?,
         }))
     }
 
@@ -129,16 +317,101 @@ impl Parser {
         };
 
         self.next_token();
-
-        while !self.cur_token_is(SEMICOLON) {
-            self.next_token();
-        }
+        let return_value = self.parse_expression(LOWEST);
+        self.end_statement(return_value.is_some());
 
         Some(Box::new(ReturnStatement {
             token: return_token,
+            return_value: return_value?,
+        }))
+    }
+
+    fn parse_while_statement(&mut self) -> Option<Box<WhileStatement>> {
+        let token = self.cur_token.clone()?;
+        let condition = self.parse_condition()?;
+        let body = self.parse_loop_body()?;
+        Some(Box::new(WhileStatement {
+            token,
+            condition,
+            body,
+        }))
+    }
+
+    fn parse_for_statement(&mut self) -> Option<Box<ForStatement>> {
+        let token = self.cur_token.clone()?;
+        if !self.expect_peek(LPAREN) {
+            return None;
+        }
+        let mut key = None;
+        let mut value = self.parse_loop_variable()?;
+        if self.peek_token_is(COMMA) {
+            self.next_token();
+            key = Some(value);
+            value = self.parse_loop_variable()?;
+        }
+        if !self.expect_peek(IN) {
+            return None;
+        }
+        self.next_token();
+        let iterable = self.parse_expression(LOWEST)?;
+        if !self.expect_peek(RPAREN) {
+            return None;
+        }
+        let body = self.parse_loop_body()?;
+        Some(Box::new(ForStatement {
+            token,
+            key,
+            value,
+            iterable,
+            body,
         }))
     }
 
+    fn parse_loop_variable(&mut self) -> Option<Identifier> {
+        if !self.expect_peek(IDENT) {
+            return None;
+        }
+        let token = self.cur_token.clone()?;
+        Some(Identifier {
+            value: token.literal.clone(),
+            token,
+        })
+    }
+
+    // The block of a loop, in which `break` and `continue` are allowed,
+    // and the optional `;` after it.
+    fn parse_loop_body(&mut self) -> Option<BlockStatement> {
+        if !self.expect_peek(LBRACE) {
+            return None;
+        }
+        self.loop_depth += 1;
+        let body = self.parse_block_statement();
+        self.loop_depth -= 1;
+
+        if self.peek_token_is(SEMICOLON) {
+            self.next_token();
+        }
+        body
+    }
+
+    // `break` and `continue` are dropped with an error outside of a loop.
+    fn parse_loop_control(&mut self) -> Option<Box<dyn Statement>> {
+        let token = self.cur_token.clone()?;
+        let in_loop = self.loop_depth > 0;
+        if !in_loop {
+            self.error(format!("{} outside of a loop", token.literal), token.span);
+        }
+        self.end_statement(true);
+
+        if !in_loop {
+            None
+        } else if token.r#type == BREAK {
+            Some(Box::new(BreakStatement { token }))
+        } else {
+            Some(Box::new(ContinueStatement { token }))
+        }
+    }
+
     fn parse_expression_statement(&mut self) -> Option<Box<ExpressionStatement>> {
         let token = match self.cur_token.as_ref() {
             Some(ct) => ct.clone(),
@@ -157,6 +430,34 @@ impl Parser {
         Some(Box::new(ExpressionStatement { token, expression }))
     }
 
+    // Let and return statements end with `;`, which may only be left out
+    // before `}` or the end of the input. A missing `;` is reported with a
+    // fix and parsing resumes at the next token. After an invalid value,
+    // everything up to the next `;` is skipped.
+    fn end_statement(&mut self, parsed: bool) {
+        if !parsed {
+            while !self.cur_token_is(SEMICOLON) && !self.cur_token_is(EOF) {
+                self.next_token();
+            }
+        } else if self.peek_token_is(SEMICOLON) {
+            self.next_token();
+        } else if !self.peek_token_is(RBRACE) && !self.peek_token_is(EOF) {
+            let end = self.cur_token.as_ref().map_or(0, |ct| ct.span.end);
+            self.peek_error(SEMICOLON);
+            self.attach_fix("insert `;`", TextEdit::insert(end, ";"));
+        }
+    }
+
+    // Attaches a single-edit fix to the last reported error.
+    fn attach_fix(&mut self, title: &str, edit: TextEdit) {
+        if let Some(error) = self.errors.last_mut() {
+            error.fix = Some(Fix {
+                title: title.to_string(),
+                edits: vec![edit],
+            });
+        }
+    }
+
     fn cur_token_is(&self, token: TokenType) -> bool {
         match self.cur_token.as_ref() {
             Some(ct) => ct.r#type == token,
@@ -182,23 +483,32 @@ impl Parser {
     }
 
     fn peek_error(&mut self, token: TokenType) {
+        let peek_token = self
+            .peek_token
+            .as_ref()
+            .expect("peek token should not be None");
         let msg = format!(
             "expected next token to be {}, got {}",
-            token,
-            self.peek_token
-                .as_ref()
-                .expect("peek token should not be None")
-                .r#type
+            token, peek_token.r#type
         );
-        self.errors.push(msg);
+        let span = peek_token.span;
+        self.error(msg, span);
+    }
+
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(ParseError {
+            message,
+            span,
+            fix: None,
+        });
     }
 
-    fn register_prefix(&mut self, token_type: TokenType, fun: PrefixParseFn) {
-        self.prefix_parse_fns.insert(token_type, fun);
+    fn register_prefix(&mut self, token_type: TokenType, kind: SyntaxKind, fun: PrefixParseFn) {
+        self.prefix_parse_fns.insert(token_type, (kind, fun));
     }
 
-    fn register_infix(&mut self, token_type: TokenType, fun: InfixParseFn) {
-        self.infix_parse_fns.insert(token_type, fun);
+    fn register_infix(&mut self, token_type: TokenType, kind: SyntaxKind, fun: InfixParseFn) {
+        self.infix_parse_fns.insert(token_type, (kind, fun));
     }
 
     fn parse_expression(&mut self, precedence: u8) -> Option<Box<dyn Expression>> {
@@ -207,143 +517,1563 @@ impl Parser {
             .as_ref()
             .expect("cur token should not be None")
             .r#type;
-        if !self.prefix_parse_fns.contains_key(cur_type) {
+        let Some(&(kind, prefix)) = self.prefix_parse_fns.get(cur_type) else {
+            self.no_prefix_parse_fn_error();
             return None;
-        }
-        let prefix = self.prefix_parse_fns[cur_type];
+        };
+
+        let start = self.checkpoint();
+        let mut left_exp = self.node(kind, prefix)?;
 
-        let left_exp = prefix(self);
+        while !self.peek_token_is(SEMICOLON) && precedence < self.peek_precedence() {
+            let (kind, infix) = match self
+                .peek_token
+                .as_ref()
+                .and_then(|pt| self.infix_parse_fns.get(pt.r#type))
+            {
+                Some(infix) => *infix,
+                None => return Some(left_exp),
+            };
+            self.next_token();
+            left_exp = self.node_at(start, kind, |parser| infix(parser, left_exp))?;
+        }
 
         Some(left_exp)
     }
 
-    fn parse_identifier(&mut self) -> Box<dyn Expression> {
+    // ILLEGAL tokens have already been reported by the lexer.
+    fn no_prefix_parse_fn_error(&mut self) {
+        let token = self
+            .cur_token
+            .as_ref()
+            .expect("cur token should not be None");
+        if token.r#type != ILLEGAL {
+            let message = format!("no prefix parse function for {} found", token.r#type);
+            self.error(message, token.span);
+        }
+    }
+
+    fn peek_precedence(&self) -> u8 {
+        self.peek_token
+            .as_ref()
+            .map_or(LOWEST, |pt| precedence_of(pt.r#type))
+    }
+
+    fn cur_precedence(&self) -> u8 {
+        self.cur_token
+            .as_ref()
+            .map_or(LOWEST, |ct| precedence_of(ct.r#type))
+    }
+
+    fn parse_identifier(&mut self) -> Option<Box<dyn Expression>> {
         let identifier = match self.cur_token.clone() {
             Some(ct) => ct,
             None => panic!("invalid!"),
         };
-        Box::new(Identifier {
+        Some(Box::new(Identifier {
             token: identifier.clone(),
             value: identifier.literal,
-        })
+        }))
     }
-}
 
-#[cfg(test)]
-mod parser_tests {
-    use crate::ast::ast::{Node, Statement};
+    fn parse_integer_literal(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
 
-    use super::*;
+        let value = match integer_value(&token.literal) {
+            Ok(value) => value,
+            Err(msg) => {
+                self.error(msg, token.span);
+                return None;
+            }
+        };
 
-    #[test]
-    fn test_let_statements() {
-        let input = r"
-        let x = 5;
-        let y = 10;
-        let foobar = 838383;
-        ";
+        Some(Box::new(IntegerLiteral { token, value }))
+    }
 
-        let lexer = Lexer::new(input.to_string());
-        let mut parser = Parser::new(lexer);
+    fn parse_float_literal(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
 
-        let program = parser.parse_program();
-        check_parser_errors(&parser);
-        assert_eq!(
-            program.statements.len(),
-            3,
-            "program.statements does not contain 3 statements. got {}",
-            program.statements.len()
-        );
-        let tests = vec![("x"), ("y"), ("foobar")];
+        let value = match float_value(&token.literal) {
+            Ok(value) => value,
+            Err(msg) => {
+                self.error(msg, token.span);
+                return None;
+            }
+        };
 
-        for (i, identifier) in tests.iter().enumerate() {
-            let stmt = &program.statements[i];
-            test_let_statement(stmt, identifier);
-        }
+        Some(Box::new(FloatLiteral { token, value }))
     }
 
-    #[test]
-    fn test_return_statements() {
-        let input = r"
-        return 5;
-        return 10;
-        return 993322;
-        ";
+    fn parse_boolean(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+        let value = token.r#type == TRUE;
+        Some(Box::new(Boolean { token, value }))
+    }
 
-        let lexer = Lexer::new(input.to_string());
-        let mut parser = Parser::new(lexer);
+    fn parse_prefix_expression(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
 
-        let program = parser.parse_program();
-        check_parser_errors(&parser);
+        self.next_token();
+        let right = self.parse_expression(PREFIX)?;
 
-        assert_eq!(
-            program.statements.len(),
-            3,
-            "program statements does not contain 3 statements. got {}",
-            program.statements.len()
-        );
+        Some(Box::new(PrefixExpression {
+            operator: token.literal.clone(),
+            token,
+            right,
+        }))
+    }
 
-        for stmt in program.statements {
-            let return_stmt_option = stmt.as_return_statement();
-            assert!(
-                return_stmt_option.is_some(),
-                "the statement is not a return statement"
-            );
-            let return_stmt = return_stmt_option.unwrap();
-            assert_eq!(
-                return_stmt.token_literal(),
-                "return",
-                "return stmt token literal it not 'return'. got {}",
-                return_stmt.token_literal()
-            )
+    fn parse_infix_expression(&mut self, left: Box<dyn Expression>) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+
+        let precedence = self.cur_precedence();
+        self.next_token();
+        let right = self.parse_expression(precedence)?;
+
+        Some(Box::new(InfixExpression {
+            operator: token.literal.clone(),
+            token,
+            left,
+            right,
+        }))
+    }
+
+    // Assignment is right-associative, so `a = b = 1` assigns to `b` first.
+    fn parse_assign_expression(
+        &mut self,
+        left: Box<dyn Expression>,
+    ) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+        let Some(name) = left.as_identifier_expression() else {
+            self.error(format!("cannot assign to {}", left.string()), token.span);
+            return None;
+        };
+        let name = Identifier {
+            token: name.token.clone(),
+            value: name.value.clone(),
+        };
+
+        self.next_token();
+        let value = self.parse_expression(ASSIGNMENT - 1)?;
+
+        Some(Box::new(AssignExpression {
+            operator: token.literal.clone(),
+            token,
+            name,
+            value,
+        }))
+    }
+
+    fn parse_call_expression(
+        &mut self,
+        function: Box<dyn Expression>,
+    ) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+        let arguments = self.node(ARGUMENT_LIST, Parser::parse_call_arguments)?;
+        Some(Box::new(CallExpression {
+            token,
+            function,
+            arguments,
+        }))
+    }
+
+    fn parse_call_arguments(&mut self) -> Option<Vec<Box<dyn Expression>>> {
+        let mut arguments = vec![];
+
+        if self.peek_token_is(RPAREN) {
+            self.next_token();
+            return Some(arguments);
+        }
+
+        loop {
+            self.next_token();
+            arguments.push(self.parse_expression(LOWEST)?);
+
+            if !self.peek_token_is(COMMA) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(RPAREN) {
+            return None;
         }
+
+        Some(arguments)
     }
 
-    #[test]
-    fn test_identifier_expression() {
-        let input = r"foobar;";
+    fn parse_grouped_expression(&mut self) -> Option<Box<dyn Expression>> {
+        self.next_token();
+        let expression = self.parse_expression(LOWEST)?;
 
-        let lexer = Lexer::new(input.to_string());
-        let mut parser = Parser::new(lexer);
+        if !self.expect_peek(RPAREN) {
+            return None;
+        }
+        Some(expression)
+    }
 
-        let program = parser.parse_program();
-        check_parser_errors(&parser);
+    fn parse_if_expression(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+        let condition = self.parse_condition()?;
 
-        assert_eq!(
-            program.statements.len(),
-            1,
-            "program has not enough statements. got {}",
-            program.statements.len()
-        );
+        if !self.expect_peek(LBRACE) {
+            return None;
+        }
+        let consequence = self.parse_block_statement()?;
 
-        let expression_stmt_option = program.statements[0].as_expression_statement();
-        assert!(
-            expression_stmt_option.is_some(),
-            "the statement is not an expression statement"
-        );
-        let expression_stmt = expression_stmt_option.unwrap();
+        let mut alternative = None;
+        if self.peek_token_is(ELSE) {
+            self.next_token();
+            if !self.expect_peek(LBRACE) {
+                return None;
+            }
+            alternative = Some(self.parse_block_statement()?);
+        }
 
-        let identifier_expression_option = expression_stmt.expression.as_identifier_expression();
-        assert!(
-            identifier_expression_option.is_some(),
-            "the expression is not an identifier expression"
-        );
-        let identifier_expression = identifier_expression_option.unwrap();
-        assert_eq!(
-            identifier_expression.value, "foobar",
-            "identifier expression value is not {}. got {}",
-            "foobar", identifier_expression.value
-        );
-        assert_eq!(
-            identifier_expression.token_literal(),
-            "foobar",
-            "identifier expression token literal is not {}. got {}",
-            "foobar",
-            identifier_expression.token_literal()
-        );
+        Some(Box::new(IfExpression {
+            token,
+            condition,
+            consequence,
+            alternative,
+        }))
+    }
+
+    // Parses the parenthesized condition after `if` or `while`.
+    fn parse_condition(&mut self) -> Option<Box<dyn Expression>> {
+        if !self.expect_peek(LPAREN) {
+            return None;
+        }
+        self.next_token();
+        // An assignment needs parentheses of its own here, so that a
+        // mistyped comparison stays an error.
+        let condition = self.parse_expression(ASSIGNMENT)?;
+        if !self.expect_peek(RPAREN) {
+            // `if (x = 1)` is almost always meant as a comparison.
+            if let Some(pt) = self.peek_token.as_ref().filter(|pt| pt.r#type == ASSIGN) {
+                let span = pt.span;
+                self.attach_fix(
+                    "replace `=` with `==`",
+                    TextEdit::replace(span.start, span.end, "=="),
+                );
+            }
+            return None;
+        }
+        Some(condition)
+    }
+
+    fn parse_function_literal(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+
+        if !self.expect_peek(LPAREN) {
+            return None;
+        }
+        let (parameters, parameter_types) =
+            self.node(PARAMETER_LIST, |parser| parser.parse_parameters(true))?;
+        let return_type = self.parse_annotation(ARROW)?;
+
+        if !self.expect_peek(LBRACE) {
+            return None;
+        }
+        let body = self.parse_function_body()?;
+
+        Some(Box::new(FunctionLiteral {
+            token,
+            parameters,
+            parameter_types,
+            return_type,
+            body,
+        }))
+    }
+
+    fn parse_macro_literal(&mut self) -> Option<Box<dyn Expression>> {
+        let token = self.cur_token.clone()?;
+
+        if !self.expect_peek(LPAREN) {
+            return None;
+        }
+        let (parameters, _) = self.node(PARAMETER_LIST, |parser| parser.parse_parameters(false))?;
+
+        if !self.expect_peek(LBRACE) {
+            return None;
+        }
+        let body = self.parse_function_body()?;
+
+        Some(Box::new(MacroLiteral {
+            token,
+            parameters,
+            body,
+        }))
+    }
+
+    // Parses the parameter list after `(`. Function parameters may be
+    // annotated; macro parameters (`typed` false) may not.
+    fn parse_parameters(
+        &mut self,
+        typed: bool,
+    ) -> Option<(Vec<Identifier>, Vec<Option<TypeExpression>>)> {
+        let mut identifiers = vec![];
+        let mut types = vec![];
+
+        if self.peek_token_is(RPAREN) {
+            self.next_token();
+            return Some((identifiers, types));
+        }
+
+        loop {
+            if !self.expect_peek(IDENT) {
+                return None;
+            }
+            let token = self.cur_token.clone()?;
+            identifiers.push(Identifier {
+                token: token.clone(),
+                value: token.literal,
+            });
+            types.push(if typed {
+                self.parse_annotation(COLON)?
+            } else {
+                None
+            });
+
+            if !self.peek_token_is(COMMA) {
+                break;
+            }
+            self.next_token();
+        }
+
+        if !self.expect_peek(RPAREN) {
+            return None;
+        }
+
+        Some((identifiers, types))
+    }
+
+    // Parses `marker type` if the next token is `marker`. The outer `None`
+    // means the type after the marker is invalid.
+    fn parse_annotation(&mut self, marker: TokenType) -> Option<Option<TypeExpression>> {
+        if !self.peek_token_is(marker) {
+            return Some(None);
+        }
+        self.next_token();
+        self.next_token();
+        Some(Some(self.parse_type()?))
+    }
+
+    fn parse_type(&mut self) -> Option<TypeExpression> {
+        let kind = if self.cur_token_is(FUNCTION) {
+            FUNCTION_TYPE
+        } else {
+            NAMED_TYPE
+        };
+        self.node(kind, Parser::parse_type_expression)
+    }
+
+    fn parse_type_expression(&mut self) -> Option<TypeExpression> {
+        let token = self.cur_token.clone()?;
+        match token.r#type {
+            IDENT => Some(TypeExpression::Named(token)),
+            FUNCTION => {
+                if !self.expect_peek(LPAREN) {
+                    return None;
+                }
+                let mut parameters = vec![];
+                if self.peek_token_is(RPAREN) {
+                    self.next_token();
+                } else {
+                    loop {
+                        self.next_token();
+                        parameters.push(self.parse_type()?);
+                        if !self.peek_token_is(COMMA) {
+                            break;
+                        }
+                        self.next_token();
+                    }
+                    if !self.expect_peek(RPAREN) {
+                        return None;
+                    }
+                }
+                if !self.expect_peek(ARROW) {
+                    return None;
+                }
+                self.next_token();
+                let result = self.parse_type()?;
+                Some(TypeExpression::Function {
+                    token,
+                    parameters,
+                    result: Box::new(result),
+                })
+            }
+            _ => {
+                self.error(format!("expected a type, got {}", token.r#type), token.span);
+                None
+            }
+        }
+    }
+
+    // A loop around a function does not extend into its body.
+    fn parse_function_body(&mut self) -> Option<BlockStatement> {
+        let loop_depth = std::mem::take(&mut self.loop_depth);
+        let body = self.parse_block_statement();
+        self.loop_depth = loop_depth;
+        body
+    }
+
+    fn parse_block_statement(&mut self) -> Option<BlockStatement> {
+        self.node(BLOCK_STATEMENT, Parser::parse_block)
+    }
+
+    fn parse_block(&mut self) -> Option<BlockStatement> {
+        let token = self.cur_token.clone()?;
+        let mut statements = vec![];
+
+        self.next_token();
+
+        while !self.cur_token_is(RBRACE) && !self.cur_token_is(EOF) {
+            match self.parse_statement() {
+                Some(stmt) => statements.push(stmt),
+                // The statement stopped at the `}` closing this block.
+                None if self.cur_token_is(RBRACE) => break,
+                None => {}
+            }
+            self.next_token();
+        }
+
+        if !self.cur_token_is(RBRACE) {
+            let span = self.cur_token.as_ref()?.span;
+            self.error(format!("expected {} to close block", RBRACE), span);
+            return None;
+        }
+
+        Some(BlockStatement { token, statements })
+    }
+}
+
+// Converts the literal of an INT token, which may carry a radix prefix and
+// `_` separators, into its value.
+pub fn integer_value(literal: &str) -> Result<i64, String> {
+    let digits = literal.replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        _ => (10, &digits[..]),
+    };
+
+    i64::from_str_radix(digits, radix).map_err(|_| {
+        format!(
+            "integer literal {} is out of range (max {})",
+            literal,
+            i64::MAX
+        )
+    })
+}
+
+pub fn float_value(literal: &str) -> Result<f64, String> {
+    match literal.replace('_', "").parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("could not parse {} as float", literal)),
+    }
+}
+
+#[cfg(test)]
+mod parser_tests {
+    use crate::ast::ast::{Node, Statement};
+
+    use super::*;
+
+    #[test]
+    fn test_let_statements() {
+        let input = r"
+        let x = 5;
+        let y = 10;
+        let foobar = 838383;
+        ";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+        assert_eq!(
+            program.statements.len(),
+            3,
+            "program.statements does not contain 3 statements. got {}",
+            program.statements.len()
+        );
+        let tests = ["x", "y", "foobar"];
+
+        for (i, identifier) in tests.iter().enumerate() {
+            let stmt = &program.statements[i];
+            test_let_statement(stmt.as_ref(), identifier);
+        }
+    }
+
+    #[test]
+    fn test_return_statements() {
+        let input = r"
+        return 5;
+        return 10;
+        return 993322;
+        ";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            3,
+            "program statements does not contain 3 statements. got {}",
+            program.statements.len()
+        );
+
+        for stmt in program.statements {
+            let return_stmt_option = stmt.as_return_statement();
+            assert!(
+                return_stmt_option.is_some(),
+                "the statement is not a return statement"
+            );
+            let return_stmt = return_stmt_option.unwrap();
+            assert_eq!(
+                return_stmt.token_literal(),
+                "return",
+                "return stmt token literal it not 'return'. got {}",
+                return_stmt.token_literal()
+            );
+            assert!(
+                return_stmt.return_value.as_integer_literal().is_some(),
+                "return value is not an integer literal. got {}",
+                return_stmt.return_value.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_identifier_expression() {
+        let input = r"foobar;";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program has not enough statements. got {}",
+            program.statements.len()
+        );
+
+        let expression_stmt_option = program.statements[0].as_expression_statement();
+        assert!(
+            expression_stmt_option.is_some(),
+            "the statement is not an expression statement"
+        );
+        let expression_stmt = expression_stmt_option.unwrap();
+
+        let identifier_expression_option = expression_stmt.expression.as_identifier_expression();
+        assert!(
+            identifier_expression_option.is_some(),
+            "the expression is not an identifier expression"
+        );
+        let identifier_expression = identifier_expression_option.unwrap();
+        assert_eq!(
+            identifier_expression.value, "foobar",
+            "identifier expression value is not {}. got {}",
+            "foobar", identifier_expression.value
+        );
+        assert_eq!(
+            identifier_expression.token_literal(),
+            "foobar",
+            "identifier expression token literal is not {}. got {}",
+            "foobar",
+            identifier_expression.token_literal()
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_expression() {
+        let input = r"5;";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program has not enough statements. got {}",
+            program.statements.len()
+        );
+
+        let expression_stmt_option = program.statements[0].as_expression_statement();
+        assert!(
+            expression_stmt_option.is_some(),
+            "the statement is not an expression statement"
+        );
+        let expression_stmt = expression_stmt_option.unwrap();
+
+        let integer_literal_option = expression_stmt.expression.as_integer_literal();
+        assert!(
+            integer_literal_option.is_some(),
+            "the expression is not an integer literal"
+        );
+        let integer_literal = integer_literal_option.unwrap();
+        assert_eq!(
+            integer_literal.value, 5,
+            "integer literal value is not {}. got {}",
+            5, integer_literal.value
+        );
+        assert_eq!(
+            integer_literal.token_literal(),
+            "5",
+            "integer literal token literal is not {}. got {}",
+            "5",
+            integer_literal.token_literal()
+        );
+    }
+
+    #[test]
+    fn test_prefixed_integer_literals() {
+        let tests = vec![
+            ("0xff;", 255),
+            ("0xDEAD_BEEF;", 3735928559),
+            ("0b1010;", 10),
+            ("0o17;", 15),
+            ("1_000_000;", 1000000),
+            ("0x7fff_ffff_ffff_ffff;", i64::MAX),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let integer_literal = expression_stmt
+                .expression
+                .as_integer_literal()
+                .expect("the expression is not an integer literal");
+            assert_eq!(
+                integer_literal.value, expected,
+                "integer literal value is not {}. got {}",
+                expected, integer_literal.value
+            );
+        }
+    }
+
+    #[test]
+    fn test_malformed_number_literal() {
+        let lexer = Lexer::new("let x = 0xZZ;".to_string());
+        let mut parser = Parser::new(lexer);
+
+        parser.parse_program();
+
+        assert_eq!(
+            parser.errors,
+            vec![ParseError {
+                message: "invalid digit 'Z' in hexadecimal literal 0xZZ".to_string(),
+                span: Span {
+                    start: 8,
+                    end: 12,
+                    line: 1,
+                    column: 9
+                },
+                fix: None,
+            }],
+            "unexpected parser errors. got {:?}",
+            parser.errors
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_out_of_range() {
+        let input = "9223372036854775807;\n  99999999999999999999;";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program should only contain the in-range literal. got {}",
+            program.statements.len()
+        );
+        assert_eq!(
+            parser.errors,
+            vec![ParseError {
+                message:
+                    "integer literal 99999999999999999999 is out of range (max 9223372036854775807)"
+                        .to_string(),
+                span: Span {
+                    start: 23,
+                    end: 43,
+                    line: 2,
+                    column: 3
+                },
+                fix: None,
+            }],
+            "unexpected parser errors. got {:?}",
+            parser.errors
+        );
+        assert_eq!(
+            parser.errors[0].to_string(),
+            "2:3: integer literal 99999999999999999999 is out of range (max 9223372036854775807)"
+        );
+    }
+
+    #[test]
+    fn test_float_literal_expression() {
+        let tests = vec![
+            ("2.75;", 2.75),
+            ("0.5;", 0.5),
+            ("2.5e-3;", 0.0025),
+            ("1E3;", 1000.0),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program has not enough statements. got {}",
+                program.statements.len()
+            );
+
+            let expression_stmt_option = program.statements[0].as_expression_statement();
+            assert!(
+                expression_stmt_option.is_some(),
+                "the statement is not an expression statement"
+            );
+            let expression_stmt = expression_stmt_option.unwrap();
+
+            let float_literal_option = expression_stmt.expression.as_float_literal();
+            assert!(
+                float_literal_option.is_some(),
+                "the expression is not a float literal"
+            );
+            let float_literal = float_literal_option.unwrap();
+            assert_eq!(
+                float_literal.value, expected,
+                "float literal value is not {}. got {}",
+                expected, float_literal.value
+            );
+            assert_eq!(
+                float_literal.token_literal(),
+                input.trim_end_matches(';'),
+                "float literal token literal is not {}. got {}",
+                input.trim_end_matches(';'),
+                float_literal.token_literal()
+            );
+        }
+    }
+
+    #[test]
+    fn test_float_literal_out_of_range() {
+        let lexer = Lexer::new("1e999;".to_string());
+        let mut parser = Parser::new(lexer);
+
+        parser.parse_program();
+
+        assert_eq!(
+            parser.errors,
+            vec![ParseError {
+                message: "could not parse 1e999 as float".to_string(),
+                span: Span {
+                    start: 0,
+                    end: 5,
+                    line: 1,
+                    column: 1
+                },
+                fix: None,
+            }],
+            "unexpected parser errors. got {:?}",
+            parser.errors
+        );
+    }
+
+    #[test]
+    fn test_let_statement_values() {
+        let tests = vec![
+            ("let x = 5;", "x", "5"),
+            ("let y = 2.5;", "y", "2.5"),
+            ("let foobar = y;", "foobar", "y"),
+            ("let last = x", "last", "x"),
+        ];
+
+        for (input, expected_identifier, expected_value) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(
+                program.statements.len(),
+                1,
+                "program.statements does not contain 1 statement. got {}",
+                program.statements.len()
+            );
+            let stmt = &program.statements[0];
+            test_let_statement(stmt.as_ref(), expected_identifier);
+
+            let let_stmt = stmt.as_let_statement().unwrap();
+            assert_eq!(
+                let_stmt.value.string(),
+                expected_value,
+                "let statement value is not {}. got {}",
+                expected_value,
+                let_stmt.value.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_function_literal_parsing() {
+        let tests = vec![
+            ("fn() {};", vec![], ""),
+            ("fn(x) { x; };", vec!["x"], "x"),
+            (
+                "fn(x, y, z) { return z; };",
+                vec!["x", "y", "z"],
+                "return z;",
+            ),
+        ];
+
+        for (input, expected_parameters, expected_body) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let function_literal = expression_stmt
+                .expression
+                .as_function_literal()
+                .expect("the expression is not a function literal");
+
+            let parameters: Vec<&str> = function_literal
+                .parameters
+                .iter()
+                .map(|p| p.value.as_str())
+                .collect();
+            assert_eq!(
+                parameters, expected_parameters,
+                "function literal parameters wrong. got {:?}",
+                parameters
+            );
+            assert_eq!(
+                function_literal.body.string(),
+                expected_body,
+                "function literal body wrong. got {}",
+                function_literal.body.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_macro_literal_parsing() {
+        let input = r"let unless = macro(x, y) { x; y; };";
+
+        let lexer = Lexer::new(input.to_string());
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program.statements does not contain 1 statement. got {}",
+            program.statements.len()
+        );
+        let stmt = &program.statements[0];
+        test_let_statement(stmt.as_ref(), "unless");
+
+        let let_stmt = stmt.as_let_statement().unwrap();
+        let macro_literal_option = let_stmt.value.as_macro_literal();
+        assert!(
+            macro_literal_option.is_some(),
+            "the let value is not a macro literal"
+        );
+        let macro_literal = macro_literal_option.unwrap();
+
+        let parameters: Vec<&str> = macro_literal
+            .parameters
+            .iter()
+            .map(|p| p.value.as_str())
+            .collect();
+        assert_eq!(
+            parameters,
+            vec!["x", "y"],
+            "macro literal parameters wrong. got {:?}",
+            parameters
+        );
+
+        assert_eq!(
+            macro_literal.body.statements.len(),
+            2,
+            "macro body does not contain 2 statements. got {}",
+            macro_literal.body.statements.len()
+        );
+        for (i, expected) in ["x", "y"].iter().enumerate() {
+            let body_stmt = macro_literal.body.statements[i]
+                .as_expression_statement()
+                .expect("macro body statement is not an expression statement");
+            assert_eq!(
+                body_stmt.expression.string(),
+                *expected,
+                "macro body statement is not {}. got {}",
+                expected,
+                body_stmt.expression.string()
+            );
+        }
+
+        assert_eq!(macro_literal.string(), "macro(x, y) xy");
+    }
+
+    #[test]
+    fn test_macro_literal_parameter_errors() {
+        let tests = vec![
+            (
+                "macro(x, 5) { x }",
+                "expected next token to be IDENT, got INT",
+            ),
+            ("macro(x y) { x }", "expected next token to be ), got IDENT"),
+            ("macro(x) { x", "expected } to close block"),
+        ];
+
+        for (input, expected_message) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            parser.parse_program();
+
+            let messages: Vec<&str> = parser.errors.iter().map(|e| e.message.as_str()).collect();
+            assert_eq!(
+                messages,
+                vec![expected_message],
+                "unexpected parser errors for {}. got {:?}",
+                input,
+                messages
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        let tests = vec![
+            ("let x: int = 5;", "let x: int = 5;"),
+            (
+                "let f: fn(int, fn() -> bool) -> null = fn(a: int, b) -> null { a };",
+                "let f: fn(int, fn() -> bool) -> null = fn(a: int, b) -> null a;",
+            ),
+            ("fn(a, b: float) { b }", "fn(a, b: float) b"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            assert_eq!(
+                program.string(),
+                expected,
+                "wrong program for {:?}. got {:?}",
+                input,
+                program.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_annotation_errors() {
+        let tests = vec![
+            ("let x: = 5;", "expected a type, got ="),
+            ("let x: fn(int) = 5;", "expected next token to be ->, got ="),
+            ("let x: fn(int, 1) -> int = 5;", "expected a type, got INT"),
+            ("fn(a: int) -> { a }", "expected a type, got {"),
+            ("macro(a: int) { a }", "expected next token to be ), got :"),
+        ];
+
+        for (input, expected_message) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let message = parser.errors.first().map(|e| e.message.as_str());
+            assert_eq!(
+                message,
+                Some(expected_message),
+                "unexpected first parser error for {}. got {:?}",
+                input,
+                message
+            );
+        }
+    }
+
+    #[test]
+    fn test_boolean_expression() {
+        let tests = vec![("true;", true), ("false;", false)];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let boolean = expression_stmt
+                .expression
+                .as_boolean()
+                .expect("the expression is not a boolean");
+            assert_eq!(
+                boolean.value, expected,
+                "boolean value wrong. got {}",
+                boolean.value
+            );
+        }
+    }
+
+    #[test]
+    fn test_prefix_expressions() {
+        let tests = vec![
+            ("!5;", "!", "5"),
+            ("-15;", "-", "15"),
+            ("!true;", "!", "true"),
+        ];
+
+        for (input, expected_operator, expected_right) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let prefix = expression_stmt
+                .expression
+                .as_prefix_expression()
+                .expect("the expression is not a prefix expression");
+            assert_eq!(
+                prefix.operator, expected_operator,
+                "prefix operator wrong. got {}",
+                prefix.operator
+            );
+            assert_eq!(
+                prefix.right.string(),
+                expected_right,
+                "prefix operand wrong. got {}",
+                prefix.right.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_infix_expressions() {
+        let tests = vec![
+            ("5 + 5;", "5", "+", "5"),
+            ("5 - 5;", "5", "-", "5"),
+            ("5 * 5;", "5", "*", "5"),
+            ("5 / 5;", "5", "/", "5"),
+            ("5 > 5;", "5", ">", "5"),
+            ("5 < 5;", "5", "<", "5"),
+            ("a == a;", "a", "==", "a"),
+            ("true != false;", "true", "!=", "false"),
+        ];
+
+        for (input, expected_left, expected_operator, expected_right) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let infix = expression_stmt
+                .expression
+                .as_infix_expression()
+                .expect("the expression is not an infix expression");
+            assert_eq!(
+                (
+                    infix.left.string(),
+                    infix.operator.as_str(),
+                    infix.right.string()
+                ),
+                (
+                    expected_left.to_string(),
+                    expected_operator,
+                    expected_right.to_string()
+                ),
+                "infix expression wrong. got {}",
+                infix.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence_parsing() {
+        let tests = vec![
+            ("-a * b", "((-a) * b)"),
+            ("!-a", "(!(-a))"),
+            ("a + b + c", "((a + b) + c)"),
+            ("a + b - c", "((a + b) - c)"),
+            ("a * b * c", "((a * b) * c)"),
+            ("a + b / c", "(a + (b / c))"),
+            ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
+            ("3 + 4; -5 * 5", "(3 + 4)((-5) * 5)"),
+            ("5 > 4 == 3 < 4", "((5 > 4) == (3 < 4))"),
+            (
+                "3 + 4 * 5 == 3 * 1 + 4 * 5",
+                "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
+            ),
+            ("true != !false", "(true != (!false))"),
+            ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
+            ("-(5 + 5)", "(-(5 + 5))"),
+            ("!(true == true)", "(!(true == true))"),
+            ("a + add(b * c) + d", "((a + add((b * c))) + d)"),
+            (
+                "add(a, b, 1, 2 * 3, 4 + 5, add(6, 7 * 8))",
+                "add(a, b, 1, (2 * 3), (4 + 5), add(6, (7 * 8)))",
+            ),
+            (
+                "add(a + b + c * d / f + g)",
+                "add((((a + b) + ((c * d) / f)) + g))",
+            ),
+            ("-f(x)", "(-f(x))"),
+            ("f(1)(2); x(2) + 3;", "f(1)(2)(x(2) + 3)"),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(
+                program.string(),
+                expected,
+                "program string wrong. got {}",
+                program.string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_expression() {
+        let tests = vec![
+            ("if (x < y) { x }", "(x < y)", "x", None),
+            ("if (x) { x } else { y; z }", "x", "x", Some("yz")),
+        ];
+
+        for (input, expected_condition, expected_consequence, expected_alternative) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let if_expression = expression_stmt
+                .expression
+                .as_if_expression()
+                .expect("the expression is not an if expression");
+            assert_eq!(
+                if_expression.condition.string(),
+                expected_condition,
+                "if condition wrong. got {}",
+                if_expression.condition.string()
+            );
+            assert_eq!(
+                if_expression.consequence.string(),
+                expected_consequence,
+                "if consequence wrong. got {}",
+                if_expression.consequence.string()
+            );
+            let alternative = if_expression.alternative.as_ref().map(|a| a.string());
+            assert_eq!(
+                alternative.as_deref(),
+                expected_alternative,
+                "if alternative wrong. got {:?}",
+                alternative
+            );
+        }
+    }
+
+    #[test]
+    fn test_if_expression_errors() {
+        let tests = vec![
+            ("if x { x }", "expected next token to be (, got IDENT"),
+            ("if (x { x }", "expected next token to be ), got {"),
+            ("if (x) x", "expected next token to be {, got IDENT"),
+            (
+                "if (x) { x } else y",
+                "expected next token to be {, got IDENT",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input.to_string());
+            let mut parser = Parser::new(lexer);
+            parser.parse_program();
+
+            let errors: Vec<&str> = parser.errors().iter().map(|e| e.message.as_str()).collect();
+            assert_eq!(
+                errors.first().copied(),
+                Some(expected),
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let input = "while (x < 10) { if (x == 5) { break; }; continue }; x";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(program.statements.len(), 2, "got {}", program.string());
+        let stmt = program.statements[0]
+            .as_while_statement()
+            .expect("statement is not a WhileStatement");
+        assert_eq!(stmt.condition.string(), "(x < 10)");
+        assert_eq!(stmt.body.statements.len(), 2);
+        assert!(stmt.body.statements[1].as_continue_statement().is_some());
+        assert_eq!(
+            program.string(),
+            "while(x < 10) if(x == 5) break;continue;x"
+        );
+    }
+
+    #[test]
+    fn test_for_statement() {
+        let tests = vec![
+            (
+                "for (x in xs) { if (x) { break; }; x }",
+                None,
+                "x",
+                "xs",
+                "for(x in xs) ifx break;x",
+            ),
+            (
+                "for (k, v in h) { continue; };",
+                Some("k"),
+                "v",
+                "h",
+                "for(k, v in h) continue;",
+            ),
+            (
+                "for (c in -n + 1) {}",
+                None,
+                "c",
+                "((-n) + 1)",
+                "for(c in ((-n) + 1)) ",
+            ),
+        ];
+
+        for (input, key, value, iterable, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(program.statements.len(), 1, "got {}", program.string());
+            let stmt = program.statements[0]
+                .as_for_statement()
+                .expect("statement is not a ForStatement");
+            assert_eq!(stmt.key.as_ref().map(|k| k.value.as_str()), key);
+            assert_eq!(stmt.value.value, value);
+            assert_eq!(stmt.iterable.string(), iterable);
+            assert_eq!(program.string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_call_expression_parsing() {
+        let input = "add(1, 2 * 3, 4 + 5);";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        assert_eq!(program.statements.len(), 1);
+        let expression_stmt = program.statements[0]
+            .as_expression_statement()
+            .expect("the statement is not an expression statement");
+        let call = expression_stmt
+            .expression
+            .as_call_expression()
+            .expect("the expression is not a call expression");
+        assert_eq!(call.function.string(), "add");
+        let arguments: Vec<String> = call.arguments.iter().map(|a| a.string()).collect();
+        assert_eq!(arguments, vec!["1", "(2 * 3)", "(4 + 5)"]);
+
+        let tests = vec![
+            ("f(1, 2", "1:7: expected next token to be ), got EOF"),
+            ("f(1 2)", "1:5: expected next token to be ), got INT"),
+            ("f(1, )", "1:6: no prefix parse function for ) found"),
+            ("f(2) = 3;", "1:6: cannot assign to f(2)"),
+        ];
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors.first().map(|e| e.as_str()),
+                Some(expected),
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_assign_expressions() {
+        let tests = vec![
+            ("x = 5;", "x", "=", "5"),
+            ("x += y * 2;", "x", "+=", "(y * 2)"),
+            ("total -= -1", "total", "-=", "(-1)"),
+            ("x *= y == z;", "x", "*=", "(y == z)"),
+            ("x /= y /= 2;", "x", "/=", "(y /= 2)"),
+        ];
+
+        for (input, name, operator, value) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            let expression_stmt = program.statements[0]
+                .as_expression_statement()
+                .expect("the statement is not an expression statement");
+            let assign = expression_stmt
+                .expression
+                .as_assign_expression()
+                .expect("the expression is not an assign expression");
+            assert_eq!(assign.name.value, name);
+            assert_eq!(assign.operator, operator);
+            assert_eq!(assign.value.string(), value);
+        }
+
+        let tests = vec![
+            ("a = b = c + 1", "(a = (b = (c + 1)))"),
+            ("let x = y = 2;", "let x = (y = 2);"),
+            ("if ((x = 1)) { x += 1 }", "if(x = 1) (x += 1)"),
+        ];
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+            assert_eq!(program.string(), expected, "wrong program for {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_assign_errors() {
+        let tests = vec![
+            ("1 = 2;", "1:3: cannot assign to 1"),
+            ("a + b += 1;", "1:7: cannot assign to (a + b)"),
+            ("-x = 1;", "1:4: cannot assign to (-x)"),
+            (
+                "if (x = 1) { x }",
+                "1:7: expected next token to be ), got =",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors.first().map(|e| e.as_str()),
+                Some(expected),
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_expression_errors() {
+        let tests = vec![
+            (
+                "let x = ;",
+                vec!["1:9: no prefix parse function for ; found"],
+            ),
+            ("return;", vec!["1:7: no prefix parse function for ; found"]),
+            (
+                "let x = 5 +;",
+                vec!["1:12: no prefix parse function for ; found"],
+            ),
+            ("x += ;", vec!["1:6: no prefix parse function for ; found"]),
+            (
+                "let f = fn() { 1 * }; f;",
+                vec!["1:20: no prefix parse function for } found"],
+            ),
+            (
+                "}; let y = 1;",
+                vec!["1:1: no prefix parse function for } found"],
+            ),
+            (
+                "let a = 1; a[0] = 2;",
+                vec![
+                    "1:13: illegal character '['",
+                    "1:15: illegal character ']'",
+                    "1:17: no prefix parse function for = found",
+                ],
+            ),
+            (
+                "\"text\";",
+                vec!["1:1: illegal character '\"'", "1:6: illegal character '\"'"],
+            ),
+            // Once a statement fails, the rest of it is skipped.
+            (
+                "let x = (1 + ) * 2; let y = x;",
+                vec!["1:14: no prefix parse function for ) found"],
+            ),
+            (";; let z = 1;;", vec![]),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors, expected,
+                "wrong errors for {:?}. got {:?}",
+                input, errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_loop_control_errors() {
+        let tests = vec![
+            ("break;", vec!["1:1: break outside of a loop"]),
+            (
+                "if (x) { continue }",
+                vec!["1:10: continue outside of a loop"],
+            ),
+            (
+                "while (x) { let f = fn() { break; }; }",
+                vec!["1:28: break outside of a loop"],
+            ),
+            (
+                "while (x) { macro() { continue; }; break; }",
+                vec!["1:23: continue outside of a loop"],
+            ),
+            (
+                "while (x = 1) { }",
+                vec!["1:10: expected next token to be ), got ="],
+            ),
+            (
+                "for (x in xs) { fn() { continue; }; }",
+                vec!["1:24: continue outside of a loop"],
+            ),
+            (
+                "for (x xs) { }",
+                vec!["1:8: expected next token to be IN, got IDENT"],
+            ),
+            (
+                "for (k, 1 in h) { }",
+                vec!["1:9: expected next token to be IDENT, got INT"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors, expected,
+                "wrong errors for {:?}. got {:?}",
+                input, errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_statement_endings() {
+        let input = "let f = fn(x) { return x }\nlet y = 1\nreturn y";
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+
+        assert_eq!(
+            program.string(),
+            "let f = fn(x) return x;;let y = 1;return y;"
+        );
+        let errors: Vec<String> = parser.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "2:1: expected next token to be ;, got LET",
+                "3:1: expected next token to be ;, got RETURN"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_error_fixes() {
+        let tests = vec![
+            ("let x = 5\nx;", "insert `;`", TextEdit::insert(9, ";")),
+            (
+                "if (x = 1) { x }",
+                "replace `=` with `==`",
+                TextEdit::replace(6, 7, "=="),
+            ),
+        ];
+
+        for (input, title, edit) in tests {
+            let mut parser = Parser::new(Lexer::new(input.to_string()));
+            parser.parse_program();
+
+            let fix = parser.errors().first().and_then(|e| e.fix.clone());
+            assert_eq!(
+                fix,
+                Some(Fix {
+                    title: title.to_string(),
+                    edits: vec![edit],
+                }),
+                "wrong fix for {:?}. got {:?}",
+                input,
+                fix
+            );
+        }
     }
 
-    fn test_let_statement(stmt: &Box<dyn Statement>, name: &str) -> bool {
+    fn test_let_statement(stmt: &dyn Statement, name: &str) -> bool {
         assert_eq!(
             stmt.token_literal(),
             "let",
@@ -378,7 +2108,7 @@ mod parser_tests {
     fn check_parser_errors(parser: &Parser) {
         let errors = &parser.errors;
 
-        if errors.len() == 0 {
+        if errors.is_empty() {
             return;
         }
 
@@ -388,7 +2118,7 @@ mod parser_tests {
         }
 
         assert!(
-            errors.len() == 0,
+            errors.is_empty(),
             "parser has {} errors: {}",
             errors.len(),
             error_message
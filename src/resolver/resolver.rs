@@ -0,0 +1,717 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    ast::{
+        ast::{
+            AssignExpression, BlockStatement, ForStatement, FunctionLiteral, Identifier,
+            LetStatement, MacroLiteral, Program,
+        },
+        visit::{walk_block_statement, Visitor},
+    },
+    lexer::lexer::Lexer,
+    token::token::{Span, EOF, LBRACE, RBRACE},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindingKind {
+    Let,
+    Parameter,
+    LoopVariable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Binding {
+    pub name: String,
+    pub kind: BindingKind,
+    // Span of the name where it is declared.
+    pub span: Span,
+    pub scope: usize,
+    // Whether a let binding is bound to a function literal.
+    pub is_function: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceKind {
+    Read,
+    // The name being assigned to, including by compound assignments: they
+    // read the old value only to replace it.
+    Write,
+}
+
+// An identifier used as an expression or assigned to. `binding` is None
+// when no binding of that name is visible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    pub span: Span,
+    pub binding: Option<usize>,
+}
+
+// Every block opens a scope. A function or macro's parameters live in the
+// same scope as the lets of its body. The global scope is always index 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scope {
+    pub parent: Option<usize>,
+    // The let binding the function is assigned to, if any.
+    pub owner: Option<usize>,
+    // Offsets of the block's `{` and `}`.
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveError {
+    pub message: String,
+    pub span: Span,
+    // A visible name close to an undefined one.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.line, self.span.column, self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean {}?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub bindings: Vec<Binding>,
+    pub references: Vec<Reference>,
+    pub scopes: Vec<Scope>,
+    pub errors: Vec<ResolveError>,
+}
+
+impl Resolution {
+    // The binding declared or referenced by the identifier at `offset`,
+    // with the span of that identifier. An offset just past the end of the
+    // identifier still counts, since that is where editors usually leave
+    // the cursor.
+    pub fn binding_at(&self, offset: u32) -> Option<(usize, Span)> {
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        if let Some(index) = self.bindings.iter().position(|b| contains(&b.span)) {
+            return Some((index, self.bindings[index].span));
+        }
+        self.references
+            .iter()
+            .find(|r| contains(&r.span))
+            .and_then(|r| Some((r.binding?, r.span)))
+    }
+
+    // Spans of the identifiers reading `binding`.
+    pub fn references_to(&self, binding: usize) -> Vec<Span> {
+        self.references_of_kind(binding, ReferenceKind::Read)
+    }
+
+    // Spans of the names assigning to `binding`.
+    pub fn writes_to(&self, binding: usize) -> Vec<Span> {
+        self.references_of_kind(binding, ReferenceKind::Write)
+    }
+
+    fn references_of_kind(&self, binding: usize, kind: ReferenceKind) -> Vec<Span> {
+        self.references
+            .iter()
+            .filter(|r| r.binding == Some(binding) && r.kind == kind)
+            .map(|r| r.span)
+            .collect()
+    }
+
+    // The innermost scope whose body contains `offset`.
+    pub fn scope_at(&self, offset: u32) -> usize {
+        let mut innermost = 0;
+        for (index, scope) in self.scopes.iter().enumerate() {
+            if scope.start < offset && offset <= scope.end {
+                innermost = index;
+            }
+        }
+        innermost
+    }
+
+    // Bindings visible at `offset`, innermost first. A name declared more
+    // than once is only listed for its closest declaration.
+    pub fn visible_at(&self, offset: u32) -> Vec<&Binding> {
+        let mut visible: Vec<&Binding> = vec![];
+        let mut scope = Some(self.scope_at(offset));
+        while let Some(index) = scope {
+            let declared = self
+                .bindings
+                .iter()
+                .rev()
+                .filter(|b| b.scope == index && b.span.end <= offset);
+            for binding in declared {
+                if !visible.iter().any(|b| b.name == binding.name) {
+                    visible.push(binding);
+                }
+            }
+            scope = self.scopes[index].parent;
+        }
+        visible
+    }
+}
+
+// Links every identifier in `program` to the let binding or parameter it
+// refers to, reporting undefined identifiers and duplicate parameters.
+// `source` is the text the program was parsed from; it is used to find
+// where blocks end.
+//
+// Statements are resolved in order, so a name is only visible after its
+// declaration. There are two exceptions: a let bound to a function literal
+// is declared before the body so the function can call itself, and a
+// function body may use a top-level let declared after the function, as
+// it only runs once called.
+pub fn resolve(source: &str, program: &Program) -> Resolution {
+    let mut resolver = Resolver {
+        resolution: Resolution {
+            scopes: vec![Scope {
+                parent: None,
+                owner: None,
+                start: 0,
+                end: u32::MAX,
+            }],
+            ..Default::default()
+        },
+        block_ends: block_ends(source),
+        names: vec![HashMap::new()],
+        current: 0,
+        owner: None,
+        functions: 0,
+        pending: vec![],
+    };
+    resolver.visit_program(program);
+    resolver.resolve_pending();
+    resolver.resolution
+}
+
+// Start offset of every `{` mapped to the start offset of its `}`.
+fn block_ends(source: &str) -> HashMap<u32, u32> {
+    let mut lexer = Lexer::new(source.to_string());
+    let mut ends = HashMap::new();
+    let mut open = vec![];
+    loop {
+        let tok = lexer.next_token();
+        if tok.r#type == EOF {
+            break;
+        } else if tok.r#type == LBRACE {
+            open.push(tok.span.start);
+        } else if tok.r#type == RBRACE {
+            if let Some(start) = open.pop() {
+                ends.insert(start, tok.span.start);
+            }
+        }
+    }
+    ends
+}
+
+struct Resolver {
+    resolution: Resolution,
+    block_ends: HashMap<u32, u32>,
+    // Latest binding of each name, per open scope.
+    names: Vec<HashMap<String, usize>>,
+    current: usize,
+    // Set while resolving the value of a let bound to a function literal.
+    owner: Option<usize>,
+    // Number of function and macro bodies around the current node.
+    functions: usize,
+    // Undefined names used in function bodies, to look up again among the
+    // top-level bindings once the program is resolved: the index of the
+    // reference, the message to report, and a suggestion from the scopes
+    // visible at the reference.
+    pending: Vec<(usize, String, Option<String>)>,
+}
+
+impl Resolver {
+    fn declare(&mut self, name: &Identifier, kind: BindingKind, is_function: bool) -> usize {
+        let index = self.resolution.bindings.len();
+        self.resolution.bindings.push(Binding {
+            name: name.value.clone(),
+            kind,
+            span: name.token.span,
+            scope: self.current,
+            is_function,
+        });
+        self.names
+            .last_mut()
+            .expect("no open scope")
+            .insert(name.value.clone(), index);
+        index
+    }
+
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .rev()
+            .find_map(|names| names.get(name).copied())
+    }
+
+    // The visible name closest to `name` by edit distance, if any is close
+    // enough to be a likely typo: about one edit per three characters, and
+    // never a rewrite of the whole name.
+    fn suggest(&self, name: &str) -> Option<String> {
+        let length = name.chars().count();
+        let max_distance = length.div_ceil(3).min(length.saturating_sub(1));
+        let mut best: Option<(usize, &String)> = None;
+        for names in self.names.iter().rev() {
+            let mut candidates: Vec<&String> = names.keys().collect();
+            candidates.sort();
+            for candidate in candidates {
+                let distance = edit_distance(name, candidate);
+                if distance > 0
+                    && distance <= max_distance
+                    && best.is_none_or(|(d, _)| distance < d)
+                {
+                    best = Some((distance, candidate));
+                }
+            }
+        }
+        best.map(|(_, candidate)| candidate.clone())
+    }
+
+    fn enter_scope(&mut self, block: &BlockStatement, owner: Option<usize>) -> usize {
+        let start = block.token.span.start;
+        let scope = self.resolution.scopes.len();
+        self.resolution.scopes.push(Scope {
+            parent: Some(self.current),
+            owner,
+            start,
+            end: self.block_ends.get(&start).copied().unwrap_or(u32::MAX),
+        });
+
+        let parent = self.current;
+        self.current = scope;
+        self.names.push(HashMap::new());
+        parent
+    }
+
+    fn exit_scope(&mut self, parent: usize) {
+        self.names.pop();
+        self.current = parent;
+    }
+
+    // Records a use of `identifier`, reporting `undefined` if no binding of
+    // that name is visible.
+    fn reference(&mut self, identifier: &Identifier, kind: ReferenceKind, undefined: &str) {
+        let binding = self.lookup(&identifier.value);
+        if binding.is_none() {
+            let message = format!("{} {}", undefined, identifier.value);
+            let suggestion = self.suggest(&identifier.value);
+            if self.functions > 0 {
+                let index = self.resolution.references.len();
+                self.pending.push((index, message, suggestion));
+            } else {
+                self.resolution.errors.push(ResolveError {
+                    message,
+                    span: identifier.token.span,
+                    suggestion,
+                });
+            }
+        }
+        self.resolution.references.push(Reference {
+            name: identifier.value.clone(),
+            kind,
+            span: identifier.token.span,
+            binding,
+        });
+    }
+
+    // Links the pending references to the top-level bindings declared after
+    // them, reporting the ones that are still undefined.
+    fn resolve_pending(&mut self) {
+        for (index, message, suggestion) in std::mem::take(&mut self.pending) {
+            let name = self.resolution.references[index].name.clone();
+            let binding = self.names[0].get(&name).copied();
+            self.resolution.references[index].binding = binding;
+            if binding.is_none() {
+                self.resolution.errors.push(ResolveError {
+                    message,
+                    span: self.resolution.references[index].span,
+                    suggestion: suggestion.or_else(|| self.suggest(&name)),
+                });
+            }
+        }
+        self.resolution.errors.sort_by_key(|e| e.span.start);
+    }
+
+    fn function_like(&mut self, parameters: &[Identifier], body: &BlockStatement) {
+        let owner = self.owner.take();
+        self.functions += 1;
+        let parent = self.enter_scope(body, owner);
+        for (i, parameter) in parameters.iter().enumerate() {
+            if parameters[..i].iter().any(|p| p.value == parameter.value) {
+                self.resolution.errors.push(ResolveError {
+                    message: format!("duplicate parameter {}", parameter.value),
+                    span: parameter.token.span,
+                    suggestion: None,
+                });
+            }
+            self.declare(parameter, BindingKind::Parameter, false);
+        }
+        // The body shares the parameters' scope.
+        walk_block_statement(self, body);
+        self.exit_scope(parent);
+        self.functions -= 1;
+    }
+}
+
+impl Visitor for Resolver {
+    fn visit_let_statement(&mut self, statement: &LetStatement) {
+        if statement.value.as_function_literal().is_some() {
+            let binding = self.declare(&statement.name, BindingKind::Let, true);
+            self.owner = Some(binding);
+            self.visit_expression(statement.value.as_ref());
+            self.owner = None;
+        } else {
+            self.visit_expression(statement.value.as_ref());
+            self.declare(&statement.name, BindingKind::Let, false);
+        }
+    }
+
+    fn visit_block_statement(&mut self, block: &BlockStatement) {
+        let parent = self.enter_scope(block, None);
+        walk_block_statement(self, block);
+        self.exit_scope(parent);
+    }
+
+    // The iterable is resolved outside the loop; the loop variables are
+    // only visible in the body, which shares their scope.
+    fn visit_for_statement(&mut self, statement: &ForStatement) {
+        self.visit_expression(statement.iterable.as_ref());
+        let parent = self.enter_scope(&statement.body, None);
+        if let Some(key) = &statement.key {
+            if key.value == statement.value.value {
+                self.resolution.errors.push(ResolveError {
+                    message: format!("duplicate loop variable {}", key.value),
+                    span: statement.value.token.span,
+                    suggestion: None,
+                });
+            }
+            self.declare(key, BindingKind::LoopVariable, false);
+        }
+        self.declare(&statement.value, BindingKind::LoopVariable, false);
+        walk_block_statement(self, &statement.body);
+        self.exit_scope(parent);
+    }
+
+    fn visit_identifier(&mut self, identifier: &Identifier) {
+        self.reference(identifier, ReferenceKind::Read, "undefined identifier");
+    }
+
+    // The value is resolved first, as it is evaluated before the binding is
+    // updated. Assigning never declares a name; inside a function it
+    // updates the enclosing binding the function closes over.
+    fn visit_assign_expression(&mut self, expression: &AssignExpression) {
+        self.visit_expression(expression.value.as_ref());
+        self.reference(
+            &expression.name,
+            ReferenceKind::Write,
+            "assignment to undeclared name",
+        );
+    }
+
+    fn visit_function_literal(&mut self, literal: &FunctionLiteral) {
+        self.function_like(&literal.parameters, &literal.body);
+    }
+
+    fn visit_macro_literal(&mut self, literal: &MacroLiteral) {
+        self.function_like(&literal.parameters, &literal.body);
+    }
+}
+
+// Levenshtein distance between `a` and `b`, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod resolver_tests {
+    use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    fn resolve_source(input: &str) -> Resolution {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "parser has errors: {:?}",
+            parser.errors()
+        );
+        resolve(input, &program)
+    }
+
+    // Each reference as "name@line:column -> line:column of its binding",
+    // with writes marked.
+    fn describe(resolution: &Resolution) -> Vec<String> {
+        resolution
+            .references
+            .iter()
+            .map(|r| {
+                let target = match r.binding {
+                    Some(index) => {
+                        let span = resolution.bindings[index].span;
+                        format!("{}:{}", span.line, span.column)
+                    }
+                    None => "unresolved".to_string(),
+                };
+                let write = match r.kind {
+                    ReferenceKind::Read => "",
+                    ReferenceKind::Write => " (write)",
+                };
+                format!(
+                    "{}@{}:{}{} -> {}",
+                    r.name, r.span.line, r.span.column, write, target
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let input = "let x = 1;
+let f = fn(y) { let z = y; f; x; z; w; };
+let x = x;
+x;
+macro(x) { x; };";
+        let resolution = resolve_source(input);
+
+        assert_eq!(
+            describe(&resolution),
+            vec![
+                "y@2:25 -> 2:12",
+                "f@2:28 -> 2:5",
+                "x@2:31 -> 1:5",
+                "z@2:34 -> 2:21",
+                "w@2:37 -> unresolved",
+                "x@3:9 -> 1:5",
+                "x@4:1 -> 3:5",
+                "x@5:12 -> 5:7",
+            ]
+        );
+
+        let kinds: Vec<(&str, BindingKind, bool)> = resolution
+            .bindings
+            .iter()
+            .map(|b| (b.name.as_str(), b.kind, b.is_function))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("x", BindingKind::Let, false),
+                ("f", BindingKind::Let, true),
+                ("y", BindingKind::Parameter, false),
+                ("z", BindingKind::Let, false),
+                ("x", BindingKind::Let, false),
+                ("x", BindingKind::Parameter, false),
+            ]
+        );
+        assert_eq!(
+            resolution.scopes[1].owner,
+            Some(1),
+            "function scope owner wrong"
+        );
+        assert_eq!(resolution.scopes[2].owner, None, "macro scope owner wrong");
+    }
+
+    #[test]
+    fn test_resolve_for_statement() {
+        let input = "let x = 1;
+for (k, x in x) { k; x; };
+x; k;";
+        let resolution = resolve_source(input);
+
+        assert_eq!(
+            describe(&resolution),
+            vec![
+                "x@2:14 -> 1:5",
+                "k@2:19 -> 2:6",
+                "x@2:22 -> 2:9",
+                "x@3:1 -> 1:5",
+                "k@3:4 -> unresolved",
+            ]
+        );
+        assert_eq!(resolution.bindings[1].kind, BindingKind::LoopVariable);
+    }
+
+    #[test]
+    fn test_resolve_assignments() {
+        let input = "let n = 0;
+let inc = fn() { n += 1; let n = 5; n = n; };";
+        let resolution = resolve_source(input);
+
+        assert!(resolution.errors.is_empty(), "got {:?}", resolution.errors);
+        assert_eq!(
+            describe(&resolution),
+            vec![
+                "n@2:18 (write) -> 1:5",
+                "n@2:41 -> 2:30",
+                "n@2:37 (write) -> 2:30"
+            ]
+        );
+        assert_eq!(resolution.references_to(0), vec![]);
+        assert_eq!(resolution.writes_to(0).len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_forward_references() {
+        let input = "let isEven = fn(n) { if (n == 0) { true } else { isOdd(n - 1) } };
+let isOdd = fn(n) { if (n == 0) { false } else { isEven(n - 1) } };
+let f = fn(n) { g = n; g };
+let g = 1;
+let h = fn() { later; missing };
+later;
+let later = 2;";
+        let resolution = resolve_source(input);
+
+        let errors: Vec<String> = resolution.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "5:23: undefined identifier missing",
+                "6:1: undefined identifier later",
+            ]
+        );
+        let described = describe(&resolution);
+        for expected in [
+            "isOdd@1:50 -> 2:5",
+            "n@1:56 -> 1:17",
+            "isEven@2:50 -> 1:5",
+            "g@3:17 (write) -> 4:5",
+            "g@3:24 -> 4:5",
+            "later@5:16 -> 7:5",
+        ] {
+            assert!(
+                described.contains(&expected.to_string()),
+                "missing {}. got {:?}",
+                expected,
+                described
+            );
+        }
+    }
+
+    #[test]
+    fn test_binding_at_and_references() {
+        let input = "let a = 1; a; let b = fn(a) { a; }; a;";
+        let resolution = resolve_source(input);
+
+        // On the declaration, inside a reference and just after one.
+        let binding_at = |offset| resolution.binding_at(offset).map(|(b, s)| (b, s.start));
+        assert_eq!(binding_at(4), Some((0, 4)));
+        assert_eq!(binding_at(11), Some((0, 11)));
+        assert_eq!(binding_at(31), Some((2, 30)));
+        assert_eq!(binding_at(9), None);
+
+        let references: Vec<u32> = resolution
+            .references_to(0)
+            .iter()
+            .map(|s| s.start)
+            .collect();
+        assert_eq!(references, vec![11, 36]);
+    }
+
+    #[test]
+    fn test_visible_at() {
+        let input = "let a = 1;\nlet f = fn(b, a) {\n  let c = b;\n  \n};\nlet d = 2;";
+        let resolution = resolve_source(input);
+
+        let names = |offset: u32| -> Vec<String> {
+            resolution
+                .visible_at(offset)
+                .iter()
+                .map(|b| format!("{:?} {}", b.kind, b.name))
+                .collect()
+        };
+
+        let inside = input.find("\n  \n").unwrap() as u32 + 3;
+        assert_eq!(
+            names(inside),
+            vec!["Let c", "Parameter a", "Parameter b", "Let f"],
+            "wrong bindings inside the function"
+        );
+        assert_eq!(names(input.len() as u32), vec!["Let d", "Let f", "Let a"]);
+        assert_eq!(names(0), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let input = "let counter = 1;
+let f = fn(value, other, value) { valeu; countr; x; };
+macro(m) { counter; };
+for (i, i in counter) {};
+let g = fn() { counter += 1; countr = 2; y = counter; };";
+        let resolution = resolve_source(input);
+
+        let errors: Vec<String> = resolution.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "2:26: duplicate parameter value",
+                "2:35: undefined identifier valeu; did you mean value?",
+                "2:42: undefined identifier countr; did you mean counter?",
+                "2:50: undefined identifier x",
+                "4:9: duplicate loop variable i",
+                "5:30: assignment to undeclared name countr; did you mean counter?",
+                "5:42: assignment to undeclared name y",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binding_at() {
+        let input = "let a = 1; fn(b) { a; b; };";
+        let resolution = resolve_source(input);
+
+        let tests = vec![
+            (4, Some((0, 4))),
+            (5, Some((0, 4))),
+            (14, Some((1, 14))),
+            (19, Some((0, 19))),
+            (22, Some((1, 22))),
+            (9, None),
+        ];
+        for (offset, expected) in tests {
+            let found = resolution
+                .binding_at(offset)
+                .map(|(index, span)| (index, span.start));
+            assert_eq!(found, expected, "wrong binding at {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let tests = vec![
+            ("", "", 0),
+            ("abc", "abc", 0),
+            ("abc", "", 3),
+            ("kitten", "sitting", 3),
+            ("valeu", "value", 2),
+            ("é", "e", 1),
+        ];
+
+        for (a, b, expected) in tests {
+            assert_eq!(
+                edit_distance(a, b),
+                expected,
+                "edit_distance({:?}, {:?}) wrong",
+                a,
+                b
+            );
+        }
+    }
+}
@@ -4,6 +4,18 @@ pub type TokenType = &'static str;
 pub struct Token {
     pub r#type: TokenType,
     pub literal: String,
+    pub span: Span,
+}
+
+// Location of a token in the source. `start` and `end` are character
+// offsets (end exclusive); `line` and `column` are 1-based and point at
+// `start`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+    pub line: u32,
+    pub column: u32,
 }
 
 pub const ILLEGAL: &str = "ILLEGAL";
@@ -11,6 +23,7 @@ pub const EOF: &str = "EOF";
 
 pub const IDENT: &str = "IDENT"; // add, foobar, x, y, ...
 pub const INT: &str = "INT"; // 1343456
+pub const FLOAT: &str = "FLOAT"; // 3.14, 1e-9
 
 // Operators
 pub const ASSIGN: &str = "=";
@@ -25,9 +38,16 @@ pub const GT: &str = ">";
 pub const EQ: &str = "==";
 pub const NEQ: &str = "!=";
 
+pub const PLUS_ASSIGN: &str = "+=";
+pub const MINUS_ASSIGN: &str = "-=";
+pub const ASTERISK_ASSIGN: &str = "*=";
+pub const SLASH_ASSIGN: &str = "/=";
+
 // Delimiters
 pub const COMMA: &str = ",";
 pub const SEMICOLON: &str = ";";
+pub const COLON: &str = ":";
+pub const ARROW: &str = "->";
 
 pub const LPAREN: &str = "(";
 pub const RPAREN: &str = ")";
@@ -42,8 +62,14 @@ pub const FALSE: &str = "FALSE";
 pub const IF: &str = "IF";
 pub const ELSE: &str = "ELSE";
 pub const RETURN: &str = "RETURN";
+pub const MACRO: &str = "MACRO";
+pub const WHILE: &str = "WHILE";
+pub const BREAK: &str = "BREAK";
+pub const CONTINUE: &str = "CONTINUE";
+pub const FOR: &str = "FOR";
+pub const IN: &str = "IN";
 
-const KEYWORDS: [(&str, &str); 7] = [
+pub const KEYWORDS: [(&str, &str); 13] = [
     ("fn", FUNCTION),
     ("let", LET),
     ("true", TRUE),
@@ -51,6 +77,12 @@ const KEYWORDS: [(&str, &str); 7] = [
     ("if", IF),
     ("else", ELSE),
     ("return", RETURN),
+    ("macro", MACRO),
+    ("while", WHILE),
+    ("break", BREAK),
+    ("continue", CONTINUE),
+    ("for", FOR),
+    ("in", IN),
 ];
 
 pub fn lookup_ident(ident: String) -> &'static str {
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::ast::{BlockStatement, Expression, FunctionLiteral, Program, Statement, TypeExpression},
+    token::token::{Span, ASSIGN, ASTERISK, BANG, EQ, GT, LT, MINUS, NEQ, PLUS, SLASH},
+};
+
+use super::types::{annotated_type, Type, TypeError};
+
+// Checks the program against its type annotations and leaves everything
+// else dynamic. Literals, annotated names, functions whose parameters and
+// result are all annotated and calls to those functions have a static
+// type; every other value is dynamic, and so is anything computed from one. Errors are only reported
+// between static types, so code without annotations is checked no further
+// than its literals.
+//
+// Unlike `infer`, nothing is guessed from how a value is used: an
+// unannotated binding stays dynamic even if its value has a static type.
+pub fn check(program: &Program) -> Vec<TypeError> {
+    let mut checker = Checker {
+        scopes: vec![HashMap::new()],
+        returns: vec![],
+        errors: vec![],
+    };
+    for statement in program.statements.iter() {
+        checker.statement(statement.as_ref());
+    }
+    checker.errors
+}
+
+struct Checker {
+    // Declared type of every name in scope, `None` where it is dynamic.
+    scopes: Vec<HashMap<String, Option<Type>>>,
+    // Declared return type of every enclosing function, innermost last.
+    returns: Vec<Option<Type>>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(TypeError { message, span });
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<Type>) {
+        self.scopes
+            .last_mut()
+            .expect("no open scope")
+            .insert(name.to_string(), ty);
+    }
+
+    // An unknown type name is reported and treated as dynamic.
+    fn annotation(&mut self, r#type: Option<&TypeExpression>) -> Option<Type> {
+        match annotated_type(r#type?) {
+            Ok(ty) => Some(ty),
+            Err(err) => {
+                self.errors.push(err);
+                None
+            }
+        }
+    }
+
+    // Returns the static type of the value the statement leaves behind, if
+    // it has one.
+    fn statement(&mut self, statement: &dyn Statement) -> Option<Type> {
+        if let Some(stmt) = statement.as_let_statement() {
+            // Declared first so that a function can refer to itself.
+            let declared = self.annotation(stmt.r#type.as_ref());
+            if stmt.value.as_function_literal().is_some() {
+                self.declare(&stmt.name.value, declared.clone());
+            }
+            // A function literal without a return annotation returns what
+            // the declaration says it does.
+            let value = match (&declared, stmt.value.as_function_literal()) {
+                (Some(declared @ Type::Function(_, result)), Some(literal))
+                    if literal.return_type.is_none() && literal_matches(declared, literal) =>
+                {
+                    self.function_literal(literal, Some(result.as_ref().clone()))
+                }
+                _ => self.expression(stmt.value.as_ref()),
+            };
+            // A function with some annotations missing is dynamic, but the
+            // annotations it has must still agree with the declaration.
+            let mismatch = match (&declared, &value, stmt.value.as_function_literal()) {
+                (Some(declared), Some(value), _) if declared != value => Some(value.to_string()),
+                (Some(declared), None, Some(literal)) if !literal_matches(declared, literal) => {
+                    Some(literal_type(literal))
+                }
+                _ => None,
+            };
+            if let (Some(declared), Some(value)) = (&declared, mismatch) {
+                let message = format!(
+                    "{} is declared {} but its value is {}",
+                    stmt.name.value, declared, value
+                );
+                self.error(stmt.name.token.span, message);
+            }
+            self.declare(&stmt.name.value, declared);
+            Some(Type::Null)
+        } else if let Some(stmt) = statement.as_return_statement() {
+            let value = self.expression(stmt.return_value.as_ref());
+            if let (Some(Some(declared)), Some(value)) = (self.returns.last(), &value) {
+                if declared != value {
+                    let message = format!(
+                        "function returns {} but is declared to return {}",
+                        value, declared
+                    );
+                    self.error(stmt.token.span, message);
+                }
+            }
+            None
+        } else if let Some(stmt) = statement.as_expression_statement() {
+            self.expression(stmt.expression.as_ref())
+        } else if let Some(block) = statement.as_block_statement() {
+            self.block(block)
+        } else if let Some(stmt) = statement.as_while_statement() {
+            self.expression(stmt.condition.as_ref());
+            self.block(&stmt.body);
+            Some(Type::Null)
+        } else if let Some(stmt) = statement.as_for_statement() {
+            self.expression(stmt.iterable.as_ref());
+            self.scopes.push(HashMap::new());
+            for variable in stmt.key.iter().chain(Some(&stmt.value)) {
+                self.declare(&variable.value, None);
+            }
+            self.statements(&stmt.body);
+            self.scopes.pop();
+            Some(Type::Null)
+        } else {
+            None
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        let ty = self.statements(block);
+        self.scopes.pop();
+        ty
+    }
+
+    fn statements(&mut self, block: &BlockStatement) -> Option<Type> {
+        let mut ty = Some(Type::Null);
+        for statement in block.statements.iter() {
+            ty = self.statement(statement.as_ref());
+        }
+        ty
+    }
+
+    fn expression(&mut self, expression: &dyn Expression) -> Option<Type> {
+        if let Some(identifier) = expression.as_identifier_expression() {
+            self.scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&identifier.value))
+                .cloned()
+                .flatten()
+        } else if expression.as_integer_literal().is_some() {
+            Some(Type::Int)
+        } else if expression.as_float_literal().is_some() {
+            Some(Type::Float)
+        } else if expression.as_boolean().is_some() {
+            Some(Type::Bool)
+        } else if let Some(prefix) = expression.as_prefix_expression() {
+            let right = self.expression(prefix.right.as_ref());
+            match prefix.token.r#type {
+                MINUS => match right {
+                    Some(Type::Int) | Some(Type::Float) | None => right,
+                    Some(ty) => {
+                        self.error(prefix.token.span, format!("cannot negate {}", ty));
+                        None
+                    }
+                },
+                BANG => Some(Type::Bool),
+                _ => None,
+            }
+        } else if let Some(infix) = expression.as_infix_expression() {
+            let left = self.expression(infix.left.as_ref());
+            let right = self.expression(infix.right.as_ref());
+            let op = &infix.operator;
+            let span = infix.token.span;
+            match infix.token.r#type {
+                PLUS | MINUS | ASTERISK | SLASH | LT | GT => {
+                    let result = match (left, right) {
+                        (Some(left), Some(right)) if left != right => {
+                            let message = format!("cannot apply {} to {} and {}", op, left, right);
+                            self.error(span, message);
+                            None
+                        }
+                        (Some(Type::Int), Some(_)) => Some(Type::Int),
+                        (Some(Type::Float), Some(_)) => Some(Type::Float),
+                        (Some(left), Some(_)) => {
+                            self.error(span, format!("cannot apply {} to {}", op, left));
+                            None
+                        }
+                        _ => None,
+                    };
+                    if matches!(infix.token.r#type, LT | GT) {
+                        Some(Type::Bool)
+                    } else {
+                        result
+                    }
+                }
+                EQ | NEQ => {
+                    if let (Some(left), Some(right)) = (&left, &right) {
+                        if left != right {
+                            let message = format!("cannot compare {} with {}", left, right);
+                            self.error(span, message);
+                        }
+                    }
+                    Some(Type::Bool)
+                }
+                _ => None,
+            }
+        } else if let Some(assign) = expression.as_assign_expression() {
+            let value = self.expression(assign.value.as_ref());
+            let target = self
+                .scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(&assign.name.value))
+                .cloned()
+                .flatten();
+            let op = &assign.operator;
+            let span = assign.token.span;
+            if let (Some(target), Some(value)) = (&target, &value) {
+                if target != value {
+                    let message = if assign.token.r#type == ASSIGN {
+                        format!(
+                            "cannot assign {} to {} of type {}",
+                            value, assign.name.value, target
+                        )
+                    } else {
+                        format!("cannot apply {} to {} and {}", op, target, value)
+                    };
+                    self.error(span, message);
+                } else if assign.token.r#type != ASSIGN
+                    && !matches!(target, Type::Int | Type::Float)
+                {
+                    self.error(span, format!("cannot apply {} to {}", op, target));
+                }
+            }
+            target
+        } else if let Some(call) = expression.as_call_expression() {
+            let function = self.expression(call.function.as_ref());
+            let mut arguments = vec![];
+            for argument in call.arguments.iter() {
+                arguments.push(self.expression(argument.as_ref()));
+            }
+            let result = match &function {
+                Some(Type::Function(parameters, result)) => {
+                    let agrees = parameters.len() == arguments.len()
+                        && parameters
+                            .iter()
+                            .zip(arguments.iter())
+                            .all(|(parameter, argument)| {
+                                argument.as_ref().is_none_or(|a| a == parameter)
+                            });
+                    agrees.then(|| result.as_ref().clone())
+                }
+                _ => None,
+            };
+            if let (Some(function), None) = (&function, &result) {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|a| a.as_ref().map_or("dynamic".to_string(), |a| a.to_string()))
+                    .collect();
+                let message = format!("cannot call {} with ({})", function, arguments.join(", "));
+                self.error(call.token.span, message);
+            }
+            result
+        } else if let Some(expr) = expression.as_if_expression() {
+            self.expression(expr.condition.as_ref());
+            let consequence = self.block(&expr.consequence);
+            match &expr.alternative {
+                Some(alternative) => {
+                    let alternative = self.block(alternative);
+                    match (consequence, alternative) {
+                        (Some(consequence), Some(alternative)) if consequence != alternative => {
+                            let message = format!(
+                                "if branches have different types: {} and {}",
+                                consequence, alternative
+                            );
+                            self.error(expr.token.span, message);
+                            None
+                        }
+                        (Some(consequence), Some(_)) => Some(consequence),
+                        _ => None,
+                    }
+                }
+                None => Some(Type::Null),
+            }
+        } else if let Some(literal) = expression.as_function_literal() {
+            let result = self.annotation(literal.return_type.as_ref());
+            self.function_literal(literal, result)
+        } else {
+            None
+        }
+    }
+
+    // Checks `literal` as a function returning `result`, which is dynamic
+    // when `None`.
+    fn function_literal(
+        &mut self,
+        literal: &FunctionLiteral,
+        result: Option<Type>,
+    ) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        let mut parameters = vec![];
+        for (parameter, r#type) in literal
+            .parameters
+            .iter()
+            .zip(literal.parameter_types.iter())
+        {
+            let ty = self.annotation(r#type.as_ref());
+            self.declare(&parameter.value, ty.clone());
+            parameters.push(ty);
+        }
+        self.returns.push(result.clone());
+        let body = self.statements(&literal.body);
+        self.returns.pop();
+        self.scopes.pop();
+
+        if let (Some(result), Some(body)) = (&result, &body) {
+            if result != body {
+                let message = format!(
+                    "function returns {} but its body ends with {}",
+                    result, body
+                );
+                self.error(literal.token.span, message);
+            }
+        }
+        let parameters = parameters.into_iter().collect::<Option<Vec<Type>>>()?;
+        Some(Type::Function(parameters, Box::new(result?)))
+    }
+}
+
+// Whether the annotations of `literal` agree with `declared`. Missing and
+// unknown annotations are dynamic and agree with anything.
+fn literal_matches(declared: &Type, literal: &FunctionLiteral) -> bool {
+    let Type::Function(parameters, result) = declared else {
+        return false;
+    };
+    let agrees = |r#type: &Option<TypeExpression>, expected: &Type| match r#type {
+        Some(r#type) => annotated_type(r#type).map_or(true, |ty| ty == *expected),
+        None => true,
+    };
+    parameters.len() == literal.parameters.len()
+        && literal
+            .parameter_types
+            .iter()
+            .zip(parameters.iter())
+            .all(|(r#type, expected)| agrees(r#type, expected))
+        && agrees(&literal.return_type, result)
+}
+
+// The type of `literal` as far as it is annotated, with "dynamic" for the
+// rest.
+fn literal_type(literal: &FunctionLiteral) -> String {
+    let show = |r#type: &Option<TypeExpression>| match r#type.as_ref().map(annotated_type) {
+        Some(Ok(ty)) => ty.to_string(),
+        _ => "dynamic".to_string(),
+    };
+    let parameters: Vec<String> = literal.parameter_types.iter().map(show).collect();
+    format!(
+        "fn({}) -> {}",
+        parameters.join(", "),
+        show(&literal.return_type)
+    )
+}
+
+#[cfg(test)]
+mod check_tests {
+    use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    fn check_source(input: &str) -> Vec<String> {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "{:?} should parse. got {:?}",
+            input,
+            parser.errors()
+        );
+        check(&program).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_annotated_code() {
+        let tests = vec![
+            "let x: int = 5;",
+            "let f: fn(int, int) -> bool = fn(a: int, b: int) -> bool { a < b };",
+            "let g = fn(n: float) -> float { if (n < 0.0) { return -n; }; n };",
+            "let h = fn(x: int) -> null { let y: int = x * 2; };",
+            "let r: fn(int) -> int = fn(n: int) -> int { let g: fn(int) -> int = r; n };",
+            "let f: fn(int) -> bool = fn(a: int) { if (a > 0) { return true; }; false };",
+            "let f: fn(int) -> int = fn(a: int) { a }; let n: int = f(f(1)); let x: bool = f(1) > 0;",
+        ];
+
+        for input in tests {
+            let errors = check_source(input);
+            assert!(
+                errors.is_empty(),
+                "unexpected errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_unannotated_code_is_dynamic() {
+        let tests = vec![
+            "let x = 5; let y: bool = x;",
+            "let id = fn(x) { x }; let n: int = id;",
+            "let f = fn(a, b: int) -> int { a * b };",
+            "let g: fn(int) -> int = fn(a) -> int { a };",
+            "let g: fn(int, bool) -> int = fn(a: int, b) { a };",
+            "let n: int = if (x) { 1 } else { y };",
+            "let f = fn(n: int) -> int { while (n > 0) { break; }; n };",
+            "let n: int = 1; for (n in h) { let b: bool = n; };",
+            "let x: int = 1; x = y; x += 2;",
+            "let id = fn(x) { x }; let n: bool = id(1); let m: int = g(true);",
+            "let f = fn(a: int) -> int { a }; let b: bool = fn(x) { f(x) }(1);",
+        ];
+
+        for input in tests {
+            let errors = check_source(input);
+            assert!(
+                errors.is_empty(),
+                "unexpected errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_annotation_errors() {
+        let tests = vec![
+            (
+                "let x: int = true;",
+                "1:5: x is declared int but its value is bool",
+            ),
+            ("let x: str = 1;", "1:8: unknown type str"),
+            (
+                "let f = fn(a: int) -> bool { a };",
+                "1:9: function returns bool but its body ends with int",
+            ),
+            (
+                "let f = fn(a: int) -> int { return a < 1; };",
+                "1:29: function returns bool but is declared to return int",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: int) -> float { 1.0 };",
+                "1:5: f is declared fn(int) -> int but its value is fn(int) -> float",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: bool) { 1 };",
+                "1:5: f is declared fn(int) -> int but its value is fn(bool) -> dynamic",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a) -> bool { true };",
+                "1:5: f is declared fn(int) -> int but its value is fn(dynamic) -> bool",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a, b) { a };",
+                "1:5: f is declared fn(int) -> int but its value is fn(dynamic, dynamic) -> dynamic",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: int) { true };",
+                "1:25: function returns int but its body ends with bool",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a) { return 1.5; };",
+                "1:33: function returns float but is declared to return int",
+            ),
+            (
+                "let g: fn(int) -> int = fn(a: num) { a };",
+                "1:31: unknown type num",
+            ),
+            (
+                "let n: int = fn(a) { a };",
+                "1:5: n is declared int but its value is fn(dynamic) -> dynamic",
+            ),
+            (
+                "let f = fn(a: int, b: bool) { a + b };",
+                "1:33: cannot apply + to int and bool",
+            ),
+            ("let f = fn(b: bool) { -b };", "1:23: cannot negate bool"),
+            (
+                "let f = fn(a: int) { if (a) { a } else { 1.5 } };",
+                "1:22: if branches have different types: int and float",
+            ),
+            (
+                "let f = fn(n: int) -> int { while (n > 0) { n } };",
+                "1:9: function returns int but its body ends with null",
+            ),
+            (
+                "let x: int = 1; x = 2.5;",
+                "1:19: cannot assign float to x of type int",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: int) { a }; let b: bool = f(1);",
+                "1:47: b is declared bool but its value is int",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: int) { a }; f(true);",
+                "1:44: cannot call fn(int) -> int with (bool)",
+            ),
+            (
+                "let f: fn(int) -> int = fn(a: int) { a }; f(1, x);",
+                "1:44: cannot call fn(int) -> int with (int, dynamic)",
+            ),
+            ("let n: int = 1; n(2);", "1:18: cannot call int with (int)"),
+            (
+                "let f = fn(b: bool) { b -= true };",
+                "1:25: cannot apply -= to bool",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let errors = check_source(input);
+            assert_eq!(
+                errors,
+                vec![expected],
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+}
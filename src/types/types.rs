@@ -0,0 +1,931 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    ast::{
+        ast::{
+            AssignExpression, BlockStatement, Expression, Identifier, Program, Statement,
+            TypeExpression,
+        },
+        visit::{walk_assign_expression, Visitor},
+    },
+    token::token::{Span, ASSIGN, ASTERISK, BANG, EQ, GT, LT, MINUS, NEQ, PLUS, SLASH},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    // The value of an `if` without `else` and of a block without a final
+    // expression.
+    Null,
+    Var(usize),
+    Function(Vec<Type>, Box<Type>),
+}
+
+// Variables print by number; messages from inference name them a, b, ...
+// instead.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Null => write!(f, "null"),
+            Type::Var(v) => write!(f, "t{}", v),
+            Type::Function(parameters, result) => {
+                let parameters: Vec<String> = parameters.iter().map(|p| p.to_string()).collect();
+                write!(f, "fn({}) -> {}", parameters.join(", "), result)
+            }
+        }
+    }
+}
+
+// The type an annotation stands for.
+pub fn annotated_type(r#type: &TypeExpression) -> Result<Type, TypeError> {
+    match r#type {
+        TypeExpression::Named(token) => match token.literal.as_str() {
+            "int" => Ok(Type::Int),
+            "float" => Ok(Type::Float),
+            "bool" => Ok(Type::Bool),
+            "null" => Ok(Type::Null),
+            name => Err(TypeError {
+                message: format!("unknown type {}", name),
+                span: token.span,
+            }),
+        },
+        TypeExpression::Function {
+            parameters, result, ..
+        } => Ok(Type::Function(
+            parameters
+                .iter()
+                .map(annotated_type)
+                .collect::<Result<Vec<Type>, TypeError>>()?,
+            Box::new(annotated_type(result)?),
+        )),
+    }
+}
+
+// A type with its generalized variables, which are replaced with fresh ones
+// every time the binding is used.
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<usize>,
+    ty: Type,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+// The inferred type of a top-level let binding, already printed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedBinding {
+    pub name: String,
+    pub span: Span,
+    pub ty: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Inference {
+    pub bindings: Vec<TypedBinding>,
+    pub errors: Vec<TypeError>,
+}
+
+// Hindley-Milner inference over `program`. Let bindings are generalized,
+// so a function bound with let can be used at different types. Bindings
+// that are assigned to are not, since every value assigned has to fit
+// every use. Arithmetic
+// and ordering need both operands to be the same number type; equality
+// needs them to be the same type. A call needs as many arguments as the
+// function has parameters, each of the parameter's type. Conditions and `!`
+// accept any value, like Monkey's truthiness rules.
+//
+// Annotations constrain the types they are attached to. Identifiers that
+// do not resolve get a fresh type; the resolver reports them. So does a
+// function body's use of a top-level let declared after the function.
+// Macro bodies work on syntax rather than values and are not checked.
+pub fn infer(program: &Program) -> Inference {
+    let mut assigned = Assigned::default();
+    assigned.visit_program(program);
+    let mut inferencer = Inferencer {
+        assigned: assigned.0,
+        vars: vec![],
+        numeric: vec![],
+        scopes: vec![HashMap::new()],
+        returns: vec![],
+        errors: vec![],
+    };
+
+    // The scheme of each top-level let is taken right after it is inferred,
+    // before a later let of the same name replaces it.
+    let mut top_level = vec![];
+    for statement in program.statements.iter() {
+        inferencer.statement(statement.as_ref());
+        if let Some(stmt) = statement.as_let_statement() {
+            let scheme = inferencer.scopes[0][&stmt.name.value].clone();
+            top_level.push((&stmt.name, scheme));
+        }
+    }
+
+    let bindings = top_level
+        .into_iter()
+        .map(|(name, scheme)| TypedBinding {
+            name: name.value.clone(),
+            span: name.token.span,
+            ty: inferencer.show_scheme(&scheme),
+        })
+        .collect();
+    Inference {
+        bindings,
+        errors: inferencer.errors,
+    }
+}
+
+// Names that are the target of an assignment anywhere in the program.
+// Shadowing is ignored, which only holds back more bindings than needed.
+#[derive(Default)]
+struct Assigned(HashSet<String>);
+
+impl Visitor for Assigned {
+    fn visit_assign_expression(&mut self, expression: &AssignExpression) {
+        self.0.insert(expression.name.value.clone());
+        walk_assign_expression(self, expression);
+    }
+}
+
+struct Inferencer {
+    assigned: HashSet<String>,
+    // What each type variable has been unified with, if anything.
+    vars: Vec<Option<Type>>,
+    // Whether each type variable may only stand for int or float.
+    numeric: Vec<bool>,
+    scopes: Vec<HashMap<String, Scheme>>,
+    // Return type of every enclosing function, innermost last, and whether
+    // it is annotated.
+    returns: Vec<(Type, bool)>,
+    errors: Vec<TypeError>,
+}
+
+impl Inferencer {
+    fn fresh(&mut self) -> Type {
+        self.vars.push(None);
+        self.numeric.push(false);
+        Type::Var(self.vars.len() - 1)
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(TypeError { message, span });
+    }
+
+    fn declare(&mut self, name: &str, scheme: Scheme) {
+        self.scopes
+            .last_mut()
+            .expect("no open scope")
+            .insert(name.to_string(), scheme);
+    }
+
+    fn monomorphic(ty: Type) -> Scheme {
+        Scheme { vars: vec![], ty }
+    }
+
+    // The type of an annotation, if there is one. An unknown type name is
+    // reported and left unconstrained.
+    fn annotation(&mut self, r#type: Option<&TypeExpression>) -> Option<Type> {
+        match annotated_type(r#type?) {
+            Ok(ty) => Some(ty),
+            Err(err) => {
+                self.errors.push(err);
+                Some(self.fresh())
+            }
+        }
+    }
+
+    // Returns the type of the value the statement leaves behind, which is
+    // the value of a block when it is the block's last statement.
+    fn statement(&mut self, statement: &dyn Statement) -> Type {
+        if let Some(stmt) = statement.as_let_statement() {
+            // As in the resolver, a function is declared before its body so
+            // it can refer to itself. It is only generalized once the value
+            // is known.
+            let declared = self.annotation(stmt.r#type.as_ref());
+            let value = if stmt.value.as_function_literal().is_some() {
+                let own = declared.clone().unwrap_or_else(|| self.fresh());
+                self.declare(&stmt.name.value, Inferencer::monomorphic(own.clone()));
+                let value = self.expression(stmt.value.as_ref());
+                if declared.is_none() && !self.unify(&own, &value) {
+                    let message = format!("{} has an infinite type", stmt.name.value);
+                    self.error(stmt.name.token.span, message);
+                }
+                value
+            } else {
+                self.expression(stmt.value.as_ref())
+            };
+            // A binding whose value does not match its annotation keeps the
+            // annotated type.
+            let ty = match declared {
+                Some(declared) => {
+                    if !self.unify(&declared, &value) {
+                        let shown = self.show(&[&declared, &value]);
+                        let message = format!(
+                            "{} is declared {} but its value is {}",
+                            stmt.name.value, shown[0], shown[1]
+                        );
+                        self.error(stmt.name.token.span, message);
+                    }
+                    declared
+                }
+                None => value,
+            };
+            let scheme = if self.assigned.contains(&stmt.name.value) {
+                Inferencer::monomorphic(ty)
+            } else {
+                self.generalize(&ty, &stmt.name.value)
+            };
+            self.declare(&stmt.name.value, scheme);
+            Type::Null
+        } else if let Some(stmt) = statement.as_return_statement() {
+            let value = self.expression(stmt.return_value.as_ref());
+            if let Some((expected, declared)) = self.returns.last().cloned() {
+                if !self.unify(&expected, &value) {
+                    let shown = self.show(&[&expected, &value]);
+                    let message = if declared {
+                        format!(
+                            "function returns {} but is declared to return {}",
+                            shown[1], shown[0]
+                        )
+                    } else {
+                        format!(
+                            "function returns {} here but {} elsewhere",
+                            shown[1], shown[0]
+                        )
+                    };
+                    self.error(stmt.token.span, message);
+                }
+            }
+            // Nothing after a return runs, so the block's value is free.
+            self.fresh()
+        } else if let Some(stmt) = statement.as_expression_statement() {
+            self.expression(stmt.expression.as_ref())
+        } else if let Some(block) = statement.as_block_statement() {
+            self.block(block)
+        } else if let Some(stmt) = statement.as_while_statement() {
+            self.expression(stmt.condition.as_ref());
+            self.block(&stmt.body);
+            Type::Null
+        } else if let Some(stmt) = statement.as_for_statement() {
+            // There are no collection types, so nothing is known about the
+            // loop variables.
+            self.expression(stmt.iterable.as_ref());
+            self.scopes.push(HashMap::new());
+            for variable in stmt.key.iter().chain(Some(&stmt.value)) {
+                let ty = self.fresh();
+                self.declare(&variable.value, Inferencer::monomorphic(ty));
+            }
+            self.statements(&stmt.body);
+            self.scopes.pop();
+            Type::Null
+        } else {
+            self.fresh()
+        }
+    }
+
+    fn block(&mut self, block: &BlockStatement) -> Type {
+        self.scopes.push(HashMap::new());
+        let ty = self.statements(block);
+        self.scopes.pop();
+        ty
+    }
+
+    fn statements(&mut self, block: &BlockStatement) -> Type {
+        let mut ty = Type::Null;
+        for statement in block.statements.iter() {
+            ty = self.statement(statement.as_ref());
+        }
+        ty
+    }
+
+    fn expression(&mut self, expression: &dyn Expression) -> Type {
+        if let Some(identifier) = expression.as_identifier_expression() {
+            self.identifier(identifier)
+        } else if expression.as_integer_literal().is_some() {
+            Type::Int
+        } else if expression.as_float_literal().is_some() {
+            Type::Float
+        } else if expression.as_boolean().is_some() {
+            Type::Bool
+        } else if let Some(prefix) = expression.as_prefix_expression() {
+            let right = self.expression(prefix.right.as_ref());
+            match prefix.token.r#type {
+                MINUS => {
+                    if !self.require_numeric(&right) {
+                        let message = format!("cannot negate {}", self.show(&[&right])[0]);
+                        self.error(prefix.token.span, message);
+                    }
+                    right
+                }
+                BANG => Type::Bool,
+                _ => self.fresh(),
+            }
+        } else if let Some(infix) = expression.as_infix_expression() {
+            let left = self.expression(infix.left.as_ref());
+            let right = self.expression(infix.right.as_ref());
+            let op = &infix.operator;
+            let span = infix.token.span;
+            match infix.token.r#type {
+                PLUS | MINUS | ASTERISK | SLASH | LT | GT => {
+                    if !self.unify(&left, &right) {
+                        let shown = self.show(&[&left, &right]);
+                        let message =
+                            format!("cannot apply {} to {} and {}", op, shown[0], shown[1]);
+                        self.error(span, message);
+                    } else if !self.require_numeric(&left) {
+                        let message = format!("cannot apply {} to {}", op, self.show(&[&left])[0]);
+                        self.error(span, message);
+                    }
+                    if matches!(infix.token.r#type, LT | GT) {
+                        Type::Bool
+                    } else {
+                        left
+                    }
+                }
+                EQ | NEQ => {
+                    if !self.unify(&left, &right) {
+                        let shown = self.show(&[&left, &right]);
+                        let message = format!("cannot compare {} with {}", shown[0], shown[1]);
+                        self.error(span, message);
+                    }
+                    Type::Bool
+                }
+                _ => self.fresh(),
+            }
+        } else if let Some(assign) = expression.as_assign_expression() {
+            let value = self.expression(assign.value.as_ref());
+            let target = self.identifier(&assign.name);
+            let op = &assign.operator;
+            let span = assign.token.span;
+            if !self.unify(&target, &value) {
+                let shown = self.show(&[&value, &target]);
+                let message = if assign.token.r#type == ASSIGN {
+                    format!(
+                        "cannot assign {} to {} of type {}",
+                        shown[0], assign.name.value, shown[1]
+                    )
+                } else {
+                    format!("cannot apply {} to {} and {}", op, shown[1], shown[0])
+                };
+                self.error(span, message);
+            } else if assign.token.r#type != ASSIGN && !self.require_numeric(&target) {
+                let message = format!("cannot apply {} to {}", op, self.show(&[&target])[0]);
+                self.error(span, message);
+            }
+            target
+        } else if let Some(call) = expression.as_call_expression() {
+            let function = self.expression(call.function.as_ref());
+            let mut arguments = vec![];
+            for argument in call.arguments.iter() {
+                arguments.push(self.expression(argument.as_ref()));
+            }
+            let result = self.fresh();
+            let expected = Type::Function(arguments.clone(), Box::new(result.clone()));
+            if !self.unify(&function, &expected) {
+                let mut types = vec![&function];
+                types.extend(arguments.iter());
+                let shown = self.show(&types);
+                let message = format!("cannot call {} with ({})", shown[0], shown[1..].join(", "));
+                self.error(call.token.span, message);
+            }
+            result
+        } else if let Some(expr) = expression.as_if_expression() {
+            self.expression(expr.condition.as_ref());
+            let consequence = self.block(&expr.consequence);
+            match &expr.alternative {
+                Some(alternative) => {
+                    let alternative = self.block(alternative);
+                    if !self.unify(&consequence, &alternative) {
+                        let shown = self.show(&[&consequence, &alternative]);
+                        let message = format!(
+                            "if branches have different types: {} and {}",
+                            shown[0], shown[1]
+                        );
+                        self.error(expr.token.span, message);
+                    }
+                    consequence
+                }
+                None => Type::Null,
+            }
+        } else if let Some(literal) = expression.as_function_literal() {
+            self.scopes.push(HashMap::new());
+            let mut parameters = vec![];
+            for (parameter, r#type) in literal
+                .parameters
+                .iter()
+                .zip(literal.parameter_types.iter())
+            {
+                let ty = self
+                    .annotation(r#type.as_ref())
+                    .unwrap_or_else(|| self.fresh());
+                self.declare(&parameter.value, Inferencer::monomorphic(ty.clone()));
+                parameters.push(ty);
+            }
+            let declared = self.annotation(literal.return_type.as_ref());
+            let result = declared.clone().unwrap_or_else(|| self.fresh());
+            self.returns.push((result.clone(), declared.is_some()));
+            let body = self.statements(&literal.body);
+            self.returns.pop();
+            self.scopes.pop();
+
+            if !self.unify(&result, &body) {
+                let shown = self.show(&[&result, &body]);
+                let message = format!(
+                    "function returns {} but its body ends with {}",
+                    shown[0], shown[1]
+                );
+                self.error(literal.token.span, message);
+            }
+            Type::Function(parameters, Box::new(result))
+        } else {
+            self.fresh()
+        }
+    }
+
+    fn identifier(&mut self, identifier: &Identifier) -> Type {
+        let scheme = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&identifier.value))
+            .cloned();
+        match scheme {
+            Some(scheme) => self.instantiate(&scheme),
+            None => self.fresh(),
+        }
+    }
+
+    // Follows variable bindings until reaching a type that is not a bound
+    // variable.
+    fn resolve(&self, ty: &Type) -> Type {
+        let mut ty = ty.clone();
+        while let Type::Var(v) = ty {
+            match &self.vars[v] {
+                Some(bound) => ty = bound.clone(),
+                None => break,
+            }
+        }
+        ty
+    }
+
+    // `ty` with every bound variable replaced, at any depth.
+    fn zonk(&self, ty: &Type) -> Type {
+        match self.resolve(ty) {
+            Type::Function(parameters, result) => Type::Function(
+                parameters.iter().map(|p| self.zonk(p)).collect(),
+                Box::new(self.zonk(&result)),
+            ),
+            ty => ty,
+        }
+    }
+
+    fn unify(&mut self, a: &Type, b: &Type) -> bool {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        match (&a, &b) {
+            (Type::Var(x), Type::Var(y)) if x == y => true,
+            (Type::Var(v), ty) | (ty, Type::Var(v)) => self.bind(*v, ty),
+            (Type::Function(p1, r1), Type::Function(p2, r2)) => {
+                p1.len() == p2.len()
+                    && p1.iter().zip(p2.iter()).all(|(x, y)| self.unify(x, y))
+                    && self.unify(r1, r2)
+            }
+            _ => a == b,
+        }
+    }
+
+    fn bind(&mut self, var: usize, ty: &Type) -> bool {
+        if self.occurs(var, ty) {
+            return false;
+        }
+        if self.numeric[var] {
+            match ty {
+                Type::Var(other) => self.numeric[*other] = true,
+                Type::Int | Type::Float => {}
+                _ => return false,
+            }
+        }
+        self.vars[var] = Some(ty.clone());
+        true
+    }
+
+    fn occurs(&self, var: usize, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(v) => v == var,
+            Type::Function(parameters, result) => {
+                parameters.iter().any(|p| self.occurs(var, p)) || self.occurs(var, &result)
+            }
+            _ => false,
+        }
+    }
+
+    fn require_numeric(&mut self, ty: &Type) -> bool {
+        match self.resolve(ty) {
+            Type::Var(v) => {
+                self.numeric[v] = true;
+                true
+            }
+            Type::Int | Type::Float => true,
+            _ => false,
+        }
+    }
+
+    // Generalizes the variables of `ty` that no other binding in scope
+    // mentions. `name` is the binding being generalized, whose own
+    // placeholder is still in scope and would otherwise hold every
+    // variable back.
+    fn generalize(&self, ty: &Type, name: &str) -> Scheme {
+        let ty = self.zonk(ty);
+        let mut in_scope = vec![];
+        for (depth, scope) in self.scopes.iter().enumerate() {
+            for (binding, scheme) in scope.iter() {
+                if depth == self.scopes.len() - 1 && binding == name {
+                    continue;
+                }
+                for var in free_vars(&self.zonk(&scheme.ty)) {
+                    if !scheme.vars.contains(&var) {
+                        in_scope.push(var);
+                    }
+                }
+            }
+        }
+        let vars = free_vars(&ty)
+            .into_iter()
+            .filter(|v| !in_scope.contains(v))
+            .collect();
+        Scheme { vars, ty }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let mut fresh = HashMap::new();
+        for var in scheme.vars.iter() {
+            let ty = self.fresh();
+            if let Type::Var(new) = ty {
+                self.numeric[new] = self.numeric[*var];
+            }
+            fresh.insert(*var, ty);
+        }
+        substitute(&self.zonk(&scheme.ty), &fresh)
+    }
+
+    // Prints `types` with variables named a, b, ... in order of
+    // appearance, shared across all of them.
+    fn show(&self, types: &[&Type]) -> Vec<String> {
+        let mut names = vec![];
+        types
+            .iter()
+            .map(|ty| {
+                let mut out = String::new();
+                self.write_type(&self.zonk(ty), &mut names, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    // A binding's type, with the variables limited to numbers listed at the
+    // end, as in `fn(a, a) -> a where a: num`.
+    fn show_scheme(&self, scheme: &Scheme) -> String {
+        let mut names = vec![];
+        let mut out = String::new();
+        self.write_type(&self.zonk(&scheme.ty), &mut names, &mut out);
+
+        let numeric: Vec<String> = names
+            .iter()
+            .enumerate()
+            .filter(|(_, var)| self.numeric[**var])
+            .map(|(i, _)| format!("{}: num", var_name(i)))
+            .collect();
+        if !numeric.is_empty() {
+            out.push_str(&format!(" where {}", numeric.join(", ")));
+        }
+        out
+    }
+
+    fn write_type(&self, ty: &Type, names: &mut Vec<usize>, out: &mut String) {
+        match ty {
+            Type::Int => out.push_str("int"),
+            Type::Float => out.push_str("float"),
+            Type::Bool => out.push_str("bool"),
+            Type::Null => out.push_str("null"),
+            Type::Var(v) => {
+                let index = match names.iter().position(|n| n == v) {
+                    Some(index) => index,
+                    None => {
+                        names.push(*v);
+                        names.len() - 1
+                    }
+                };
+                out.push_str(&var_name(index));
+            }
+            Type::Function(parameters, result) => {
+                out.push_str("fn(");
+                for (i, parameter) in parameters.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    self.write_type(parameter, names, out);
+                }
+                out.push_str(") -> ");
+                self.write_type(result, names, out);
+            }
+        }
+    }
+}
+
+fn var_name(index: usize) -> String {
+    if index < 26 {
+        ((b'a' + index as u8) as char).to_string()
+    } else {
+        format!("t{}", index)
+    }
+}
+
+// Unbound variables of an already zonked type, in order of appearance.
+fn free_vars(ty: &Type) -> Vec<usize> {
+    let mut vars = vec![];
+    collect_vars(ty, &mut vars);
+    vars
+}
+
+fn collect_vars(ty: &Type, vars: &mut Vec<usize>) {
+    match ty {
+        Type::Var(v) if !vars.contains(v) => vars.push(*v),
+        Type::Function(parameters, result) => {
+            for parameter in parameters {
+                collect_vars(parameter, vars);
+            }
+            collect_vars(result, vars);
+        }
+        _ => {}
+    }
+}
+
+fn substitute(ty: &Type, fresh: &HashMap<usize, Type>) -> Type {
+    match ty {
+        Type::Var(v) => fresh.get(v).cloned().unwrap_or(Type::Var(*v)),
+        Type::Function(parameters, result) => Type::Function(
+            parameters.iter().map(|p| substitute(p, fresh)).collect(),
+            Box::new(substitute(result, fresh)),
+        ),
+        ty => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod types_tests {
+    use crate::{lexer::lexer::Lexer, parser::parser::Parser};
+
+    use super::*;
+
+    fn infer_source(input: &str) -> Inference {
+        let mut parser = Parser::new(Lexer::new(input.to_string()));
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "{:?} should parse. got {:?}",
+            input,
+            parser.errors()
+        );
+        infer(&program)
+    }
+
+    #[test]
+    fn test_infer_bindings() {
+        let tests = vec![
+            ("let x = 5;", "x: int"),
+            ("let x = -2.5 * 2.0;", "x: float"),
+            ("let b = !5 == (1 < 2);", "b: bool"),
+            ("let id = fn(x) { x };", "id: fn(a) -> a"),
+            ("let k = fn(x, y) { x };", "k: fn(a, b) -> a"),
+            (
+                "let add = fn(a, b) { a + b };",
+                "add: fn(a, a) -> a where a: num",
+            ),
+            (
+                "let sign = fn(n) { if (n < 0) { return -1; }; 1 };",
+                "sign: fn(int) -> int",
+            ),
+            ("let f = fn(x) { if (x) { x } };", "f: fn(a) -> null"),
+            (
+                "let compose = fn(f, g) { fn(x) { f } };",
+                "compose: fn(a, b) -> fn(c) -> a",
+            ),
+            ("let x = 1; let x = x == 2;", "x: bool"),
+            (
+                "let f = fn(n) { while (n > 0) { if (n == 5) { break; }; continue; 1.5 } };",
+                "f: fn(int) -> null",
+            ),
+            (
+                "let f = fn(h) { for (k, v in h) { k + 1; v == true } };",
+                "f: fn(a) -> null",
+            ),
+            ("let f = fn(n) { n -= 1.5; n };", "f: fn(float) -> float"),
+            ("let f = fn(a, b) { a = b };", "f: fn(a, a) -> a"),
+            (
+                "let fact = fn(n) { if (n < 2) { return 1; }; n * fact(n - 1) };",
+                "fact: fn(int) -> int",
+            ),
+            (
+                "let apply = fn(f, x) { f(x) };",
+                "apply: fn(fn(a) -> b, a) -> b",
+            ),
+            ("let n = fn(x) { x + 1.5 }(2.0);", "n: float"),
+        ];
+
+        for (input, expected) in tests {
+            let inference = infer_source(input);
+            assert!(
+                inference.errors.is_empty(),
+                "unexpected errors for {:?}. got {:?}",
+                input,
+                inference.errors
+            );
+            let binding = inference.bindings.last().unwrap();
+            let got = format!("{}: {}", binding.name, binding.ty);
+            assert_eq!(got, expected, "wrong type for {:?}. got {}", input, got);
+        }
+    }
+
+    #[test]
+    fn test_shadowed_bindings() {
+        let inference = infer_source("let x = 1; let f = fn(y) { y }; let x = true; let f = 2.5;");
+        assert!(inference.errors.is_empty(), "got {:?}", inference.errors);
+        let bindings: Vec<String> = inference
+            .bindings
+            .iter()
+            .map(|b| format!("{}@{}: {}", b.name, b.span.column, b.ty))
+            .collect();
+        assert_eq!(
+            bindings,
+            vec!["x@5: int", "f@16: fn(a) -> a", "x@37: bool", "f@51: float"]
+        );
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        // Without generalization, the second comparison would need the
+        // identity function to return both int and bool.
+        let inference = infer_source(
+            "let id = fn(x) { x }; let n = id == fn(a) { 1 }; let b = id == fn(a) { true };",
+        );
+        assert!(inference.errors.is_empty(), "got {:?}", inference.errors);
+
+        // The same goes for calls.
+        let inference = infer_source("let id = fn(x) { x }; let n = id(1); let b = id(true);");
+        assert!(inference.errors.is_empty(), "got {:?}", inference.errors);
+        let bindings: Vec<String> = inference
+            .bindings
+            .iter()
+            .map(|b| format!("{}: {}", b.name, b.ty))
+            .collect();
+        assert_eq!(bindings, vec!["id: fn(a) -> a", "n: int", "b: bool"]);
+
+        // Neither are bindings that are assigned to, or the assignments
+        // below would each get their own copy of the identity's type.
+        let inference = infer_source("let g = fn(x) { x }; g = fn(x) { x + 1 }; g = fn(x) { !x };");
+        let errors: Vec<String> = inference.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec!["1:45: cannot assign fn(int) -> bool to g of type fn(int) -> int"]
+        );
+
+        // Parameters are not generalized.
+        let inference =
+            infer_source("let f = fn(id) { let n = id == fn(a) { 1 }; id == fn(a) { true } };");
+        let errors: Vec<String> = inference.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec!["1:48: cannot compare fn(a) -> int with fn(a) -> bool"]
+        );
+    }
+
+    #[test]
+    fn test_annotations() {
+        let inference = infer_source(
+            "let add = fn(a: float, b) { a + b }; let n: int = 1; let id: fn(int) -> int = fn(x) { x };",
+        );
+        assert!(inference.errors.is_empty(), "got {:?}", inference.errors);
+        let bindings: Vec<String> = inference
+            .bindings
+            .iter()
+            .map(|b| format!("{}: {}", b.name, b.ty))
+            .collect();
+        assert_eq!(
+            bindings,
+            vec![
+                "add: fn(float, float) -> float",
+                "n: int",
+                "id: fn(int) -> int"
+            ]
+        );
+
+        let tests = vec![
+            (
+                "let x: int = 2.5;",
+                "1:5: x is declared int but its value is float",
+            ),
+            ("let x: text = 2.5;", "1:8: unknown type text"),
+            (
+                "let f = fn(x) -> bool { x + 1 };",
+                "1:9: function returns bool but its body ends with int",
+            ),
+            (
+                "let f = fn(x) -> int { return x == 1; };",
+                "1:24: function returns bool but is declared to return int",
+            ),
+        ];
+        for (input, expected) in tests {
+            let inference = infer_source(input);
+            let errors: Vec<String> = inference.errors.iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors,
+                vec![expected],
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let tests = vec![
+            ("1 + true;", "1:3: cannot apply + to int and bool"),
+            ("1 < 2.5;", "1:3: cannot apply < to int and float"),
+            ("true * false;", "1:6: cannot apply * to bool"),
+            ("-true;", "1:1: cannot negate bool"),
+            (
+                "1 == fn() { 1 };",
+                "1:3: cannot compare int with fn() -> int",
+            ),
+            (
+                "if (true) { 1 } else { false };",
+                "1:1: if branches have different types: int and bool",
+            ),
+            (
+                "let f = fn(x) { if (x) { return 1; }; true };",
+                "1:9: function returns int but its body ends with bool",
+            ),
+            (
+                "let f = fn(x) { if (x) { return 1; }; return true; };",
+                "1:39: function returns bool here but int elsewhere",
+            ),
+            ("let f = fn() { f };", "1:5: f has an infinite type"),
+            (
+                "let x = 1; x = true;",
+                "1:14: cannot assign bool to x of type int",
+            ),
+            ("let b = true; b += false;", "1:17: cannot apply += to bool"),
+            (
+                "let x = 1; x *= 2.5;",
+                "1:14: cannot apply *= to int and float",
+            ),
+            (
+                "let inc = fn(n) { n + 1 }; inc(true);",
+                "1:31: cannot call fn(int) -> int with (bool)",
+            ),
+            (
+                "let inc = fn(n) { n + 1 }; inc(1, 2);",
+                "1:31: cannot call fn(int) -> int with (int, int)",
+            ),
+            (
+                "let k = fn(a, b) { a }; k(1, 2) + k(true, 2);",
+                "1:33: cannot apply + to int and bool",
+            ),
+            ("1(2);", "1:2: cannot call int with (int)"),
+        ];
+
+        for (input, expected) in tests {
+            let inference = infer_source(input);
+            let errors: Vec<String> = inference.errors.iter().map(|e| e.to_string()).collect();
+            assert_eq!(
+                errors,
+                vec![expected],
+                "wrong errors for {:?}. got {:?}",
+                input,
+                errors
+            );
+        }
+    }
+}
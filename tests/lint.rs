@@ -0,0 +1,31 @@
+use std::{fs, process::Command};
+
+// Runs `writing-an-interpreter lint --fix` on a file holding `source` and
+// returns what the file holds afterwards.
+fn fix_file(name: &str, source: &str) -> String {
+    let dir = format!("{}/{}", env!("CARGO_TARGET_TMPDIR"), name);
+    fs::create_dir_all(&dir).unwrap();
+    let path = format!("{}/main.monkey", dir);
+    fs::write(&path, source).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_writing-an-interpreter"))
+        .args(["lint", "--fix", &path])
+        .output()
+        .expect("failed to run lint");
+    fs::read_to_string(&path).unwrap()
+}
+
+#[test]
+fn test_fix_keeps_top_level_bindings() {
+    let source = "let add = fn(a, b) { a + b }; let helper = fn(x) { add }; let config = 5;\n";
+    assert_eq!(fix_file("top_level", source), source);
+}
+
+#[test]
+fn test_fix_runs_once() {
+    // Removing b leaves a unused, which is reported but kept.
+    let source = "let f = fn() {\n    let a = 1;\n    let b = a;\n};\nf;\n";
+    assert_eq!(
+        fix_file("once", source),
+        "let f = fn() {\n    let a = 1;\n};\nf;\n"
+    );
+}
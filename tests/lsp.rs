@@ -0,0 +1,354 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+// Drives `writing-an-interpreter lsp` over its stdin and stdout the way an
+// editor would. Responses are compared as text: the server serializes
+// object fields in a fixed order, so the output is stable.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_writing-an-interpreter"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("failed to start the language server");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Client {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    fn send(&mut self, body: &str) {
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> String {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        String::from_utf8(body).unwrap()
+    }
+
+    // Sends a request and returns its "result" (or "error") as text.
+    fn request(&mut self, method: &str, params: &str) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{}}}"#,
+            id, method, params
+        ));
+        let response = self.receive();
+        let prefix = format!(r#"{{"jsonrpc":"2.0","id":{},"#, id);
+        assert!(
+            response.starts_with(&prefix),
+            "unexpected response {}",
+            response
+        );
+        response[prefix.len()..response.len() - 1].to_string()
+    }
+
+    fn notify(&mut self, method: &str, params: &str) {
+        self.send(&format!(
+            r#"{{"jsonrpc":"2.0","method":"{}","params":{}}}"#,
+            method, params
+        ));
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> String {
+        self.notify(
+            "textDocument/didOpen",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}","languageId":"monkey","version":1,"text":{:?}}}}}"#,
+                uri, text
+            ),
+        );
+        self.receive()
+    }
+
+    fn exit(mut self) -> i32 {
+        assert_eq!(self.request("shutdown", "null"), r#""result":null"#);
+        self.notify("exit", "null");
+        self.child.wait().unwrap().code().unwrap()
+    }
+}
+
+fn position(uri: &str, line: u32, character: u32) -> String {
+    format!(
+        r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":{},"character":{}}}}}"#,
+        uri, line, character
+    )
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> String {
+    format!(
+        r#"{{"start":{{"line":{},"character":{}}},"end":{{"line":{},"character":{}}}}}"#,
+        start.0, start.1, end.0, end.1
+    )
+}
+
+const URI: &str = "file:///test.monkey";
+const SOURCE: &str = "let add = fn(a, b) {\n  let sum = a;\n  sum;\n};\nadd;\n";
+
+#[test]
+fn test_initialize_and_shutdown() {
+    let mut client = Client::start();
+    let result = client.request("initialize", r#"{"capabilities":{}}"#);
+    assert!(
+        result.contains(r#""textDocumentSync":1"#) && result.contains(r#""hoverProvider":true"#),
+        "unexpected capabilities {}",
+        result
+    );
+    client.notify("initialized", "{}");
+
+    assert_eq!(
+        client.request("textDocument/formatting", "{}"),
+        r#""error":{"code":-32601,"message":"unknown method textDocument/formatting"}"#
+    );
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn test_diagnostics() {
+    let mut client = Client::start();
+    client.request("initialize", "{}");
+
+    let published = client.open(URI, "let x = 5;\nlet = 1;");
+    assert_eq!(
+        published,
+        format!(
+            r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{{"uri":"{}","diagnostics":[{{"range":{},"severity":1,"source":"monkey","message":"expected next token to be IDENT, got ="}}]}}}}"#,
+            URI,
+            range((1, 4), (1, 5))
+        )
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":"let x = 5;\nx;"}}]}}"#,
+            URI
+        ),
+    );
+    assert!(
+        client.receive().ends_with(r#""diagnostics":[]}}"#),
+        "fixed document still has diagnostics"
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":3}},"contentChanges":[{{"text":"let count = 5;\ncont;"}}]}}"#,
+            URI
+        ),
+    );
+    assert!(
+        client.receive().ends_with(&format!(
+            r#""diagnostics":[{{"range":{},"severity":1,"source":"monkey","message":"undefined identifier cont; did you mean count?"}},{{"range":{},"severity":2,"source":"monkey","message":"let binding count is never used [unused-let]"}}]}}}}"#,
+            range((1, 0), (1, 4)),
+            range((0, 4), (0, 9))
+        )),
+        "undefined identifier and unused binding not reported"
+    );
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn test_navigation() {
+    let mut client = Client::start();
+    client.request("initialize", "{}");
+    client.open(URI, SOURCE);
+
+    // `a` in `let sum = a;`
+    assert_eq!(
+        client.request("textDocument/definition", &position(URI, 1, 12)),
+        format!(
+            r#""result":{{"uri":"{}","range":{}}}"#,
+            URI,
+            range((0, 13), (0, 14))
+        )
+    );
+
+    let hover = client.request("textDocument/hover", &position(URI, 4, 1));
+    assert_eq!(
+        hover,
+        format!(
+            r#""result":{{"contents":{{"kind":"markdown","value":"```monkey\nlet add = fn(a, b) {{\n```\nlet binding `add`, declared on line 1"}},"range":{}}}"#,
+            range((4, 0), (4, 3))
+        )
+    );
+
+    // `sum` from its declaration, including the declaration itself.
+    let references = client.request(
+        "textDocument/references",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":1,"character":7}},"context":{{"includeDeclaration":true}}}}"#,
+            URI
+        ),
+    );
+    assert_eq!(
+        references,
+        format!(
+            r#""result":[{{"uri":"{0}","range":{1}}},{{"uri":"{0}","range":{2}}}]"#,
+            URI,
+            range((1, 6), (1, 9)),
+            range((2, 2), (2, 5))
+        )
+    );
+
+    // Nothing is bound at a keyword.
+    assert_eq!(
+        client.request("textDocument/definition", &position(URI, 0, 1)),
+        r#""result":null"#
+    );
+
+    // Assignments are listed with the declaration, not as references.
+    let uri = "file:///counter.monkey";
+    client.open(uri, "let n = 0;\nn = n + 1;\n");
+    let references = |client: &mut Client, include_declaration| {
+        client.request(
+            "textDocument/references",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}"}},"position":{{"line":0,"character":4}},"context":{{"includeDeclaration":{}}}}}"#,
+                uri, include_declaration
+            ),
+        )
+    };
+    assert_eq!(
+        references(&mut client, false),
+        format!(
+            r#""result":[{{"uri":"{}","range":{}}}]"#,
+            uri,
+            range((1, 4), (1, 5))
+        )
+    );
+    assert_eq!(
+        references(&mut client, true),
+        format!(
+            r#""result":[{{"uri":"{0}","range":{1}}},{{"uri":"{0}","range":{2}}},{{"uri":"{0}","range":{3}}}]"#,
+            uri,
+            range((0, 4), (0, 5)),
+            range((1, 0), (1, 1)),
+            range((1, 4), (1, 5))
+        )
+    );
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn test_symbols_and_completion() {
+    let mut client = Client::start();
+    client.request("initialize", "{}");
+    client.open(URI, SOURCE);
+
+    assert_eq!(
+        client.request(
+            "textDocument/documentSymbol",
+            &format!(r#"{{"textDocument":{{"uri":"{}"}}}}"#, URI)
+        ),
+        format!(
+            r#""result":[{{"name":"add","kind":12,"range":{0},"selectionRange":{0},"children":[{{"name":"sum","kind":13,"range":{1},"selectionRange":{1},"children":[]}}]}}]"#,
+            range((0, 4), (0, 7)),
+            range((1, 6), (1, 9))
+        )
+    );
+
+    // Inside the function body, after `let sum = a;`.
+    let completion = client.request("textDocument/completion", &position(URI, 2, 2));
+    let labels: Vec<&str> = completion
+        .split(r#""label":""#)
+        .skip(1)
+        .map(|item| &item[..item.find('"').unwrap()])
+        .collect();
+    assert_eq!(
+        labels,
+        vec![
+            "sum", "b", "a", "add", "fn", "let", "true", "false", "if", "else", "return", "macro",
+            "while", "break", "continue", "for", "in"
+        ]
+    );
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn test_code_actions() {
+    let mut client = Client::start();
+    client.request("initialize", "{}");
+    client.open(URI, "let unused = 1;\nlet y = 2\ny;");
+
+    let actions = |client: &mut Client, start, end| {
+        client.request(
+            "textDocument/codeAction",
+            &format!(
+                r#"{{"textDocument":{{"uri":"{}"}},"range":{},"context":{{"diagnostics":[]}}}}"#,
+                URI,
+                range(start, end)
+            ),
+        )
+    };
+    let action = |title: &str, edit_range: String, text: &str| {
+        format!(
+            r#"{{"title":"{}","kind":"quickfix","edit":{{"changes":{{"{}":[{{"range":{},"newText":"{}"}}]}}}}}}"#,
+            title, URI, edit_range, text
+        )
+    };
+
+    // Lints wait for the parse error to be fixed.
+    assert_eq!(
+        actions(&mut client, (2, 0), (2, 0)),
+        format!(
+            r#""result":[{}]"#,
+            action("insert `;`", range((1, 9), (1, 9)), ";")
+        )
+    );
+    assert_eq!(actions(&mut client, (0, 0), (0, 3)), r#""result":[]"#);
+
+    client.notify(
+        "textDocument/didChange",
+        &format!(
+            r#"{{"textDocument":{{"uri":"{}","version":2}},"contentChanges":[{{"text":"let f = fn() {{\n    let unused = 1;\n}};\nf;"}}]}}"#,
+            URI
+        ),
+    );
+    client.receive();
+    assert_eq!(
+        actions(&mut client, (1, 10), (1, 10)),
+        format!(
+            r#""result":[{}]"#,
+            action("remove unused binding unused", range((1, 0), (2, 0)), "")
+        )
+    );
+    assert_eq!(client.exit(), 0);
+}
+
+#[test]
+fn test_exit_without_shutdown() {
+    let mut client = Client::start();
+    client.request("initialize", "{}");
+    client.notify("exit", "null");
+    assert_eq!(client.child.wait().unwrap().code(), Some(1));
+}